@@ -1,53 +1,93 @@
 //! # Implementation of command line interface utilizing Rust clap library
+use crate::google::dialogflow::api::DeployMode;
+use crate::google::dialogflow::options::{
+    AgentOptions, ClonedMetadata, Platforms, SynonymsDedupe, SynonymsNormalization,
+};
 use crate::google::gcloud::translate::TranslationProviders;
 use clap::{App, Arg, ArgMatches};
 use std::path::Path;
 
 #[derive(Debug)]
 pub struct CommandLine<'a> {
-    pub gdf_agent_zip_path: &'a Path,
+    pub gdf_agent_zip_path: Option<&'a Path>,
+    pub from_project: Option<String>,
+    pub export_uri: Option<String>,
     pub output_folder: &'a Path,
     pub from_lang: String,
     pub to_lang: String,
-    pub gcloud_svc_acc_cred: &'a Path,
+    pub gcloud_svc_acc_cred: Option<&'a Path>,
+    pub token_url: Option<String>,
+    pub project_id: Option<String>,
+    pub storage_url: Option<String>,
+    pub translate_url: Option<String>,
+    pub dialogflow_url: Option<String>,
+    pub bucket_creation_delay: Option<u64>,
+    pub operation_timeout: Option<u64>,
+    pub retry_attempts: u32,
+    pub retry_backoff: u64,
     pub translation_mode: TranslationProviders,
     pub create_output_tsv: bool,
     pub v2_task_count: usize,
+    pub v2_chars_per_minute: Option<u64>,
+    pub v2_requests_per_minute: Option<u64>,
     pub skip_entities_translation: bool,
     pub skip_utterances_translation: bool,
     pub skip_responses_translation: bool,
     pub glossary_path: Option<&'a Path>,
+    pub models: Vec<&'a str>,
+    pub deploy_mode: Option<DeployMode>,
+    pub deploy_project: Option<String>,
+    pub train_agent: bool,
+    pub assume_yes: bool,
+    pub agent_options: AgentOptions,
+    pub entity_values: Vec<&'a str>,
+    pub payload_rules: Vec<&'a str>,
+    pub payload_excluded_keys: Vec<&'a str>,
+    pub media_urls_path: Option<&'a Path>,
+    pub url_rewrites_path: Option<&'a Path>,
+    pub abbreviations_path: Option<&'a Path>,
 }
 
-impl<'a> CommandLine<'a> {
-    fn new(
-        gdf_agent_zip_path: &'a Path,
-        output_folder: &'a Path,
-        from_lang: String,
-        to_lang: String,
-        gcloud_svc_acc_cred: &'a Path,
-        translation_mode: TranslationProviders,
-        create_output_tsv: bool,
-        v2_task_count: usize,
-        skip_entities_translation: bool,
-        skip_utterances_translation: bool,
-        skip_responses_translation: bool,
-        glossary_path: Option<&'a Path>,
-    ) -> Self {
-        CommandLine {
-            gdf_agent_zip_path,
-            output_folder,
-            from_lang,
-            to_lang,
-            gcloud_svc_acc_cred,
-            translation_mode,
-            create_output_tsv,
-            v2_task_count,
-            skip_entities_translation,
-            skip_utterances_translation,
-            skip_responses_translation,
-            glossary_path,
-        }
+/// rate limits and timeouts must be positive, zero would mean waiting forever or giving up immediately
+fn is_positive_integer(val: String) -> Result<(), String> {
+    match val.parse::<u64>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ => Err(format!("{} is not positive integer", val)),
+    }
+}
+
+/// at least one attempt is needed to send the request at all
+fn is_attempt_count(val: String) -> Result<(), String> {
+    match val.parse::<u32>() {
+        Ok(number) if number > 0 => Ok(()),
+        _ => Err(format!("{} is not positive integer", val)),
+    }
+}
+
+fn is_non_negative_integer(val: String) -> Result<(), String> {
+    match val.parse::<u64>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} is not non-negative integer", val)),
+    }
+}
+
+/// counter of cloned training phrases is stored as i8 in ES agent
+fn is_cloned_count(val: String) -> Result<(), String> {
+    match val.parse::<i8>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!(
+            "{} is not integer between {} and {}",
+            val,
+            i8::MIN,
+            i8::MAX
+        )),
+    }
+}
+
+fn is_epoch_seconds(val: String) -> Result<(), String> {
+    match val.parse::<i64>() {
+        Ok(_) => Ok(()),
+        Err(_) => Err(format!("{} is not timestamp in epoch seconds", val)),
     }
 }
 
@@ -61,9 +101,25 @@ pub fn get_cmd_line_parser<'a, 'b>() -> App<'a, 'b> {
                 .short("f")
                 .long("agent-file")
                 .value_name("FILE")
-                .help("ZIP file with exported GDF agent")
+                .help("ZIP file with exported GDF agent (ES or CX)")
                 .takes_value(true)
-                .required(true)
+                .required_unless("from_project")
+                .conflicts_with("from_project")
+        )
+        .arg(
+            Arg::with_name("from_project")
+                .long("from-project")
+                .value_name("PROJECT ID")
+                .help("Google Cloud project whose agent is exported directly from Dialogflow instead of using --agent-file. Exported agent is stored into output folder as exported_agent.zip.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("export_uri")
+                .long("export-uri")
+                .value_name("gs://BUCKET/OBJECT")
+                .help("Cloud Storage URI where agent is exported when using --from-project (intended for large agents). If not specified agent is returned directly by Dialogflow API.")
+                .takes_value(true)
+                .requires("from_project")
         )
         .arg(
             Arg::with_name("output_folder")
@@ -97,9 +153,85 @@ pub fn get_cmd_line_parser<'a, 'b>() -> App<'a, 'b> {
                 .short("c")
                 .long("cred-file")
                 .value_name("FILE")
-                .help("Path to Google Cloud service account credentials used to run translation via Google Translate V2/V3 API. Must have respective priviledges: See github README for more details.")
+                .help("Path to Google Cloud credentials (service account key or gcloud authorized_user file) used to run translation via Google Translate V2/V3 API. Must have respective priviledges: See github README for more details. If not specified credentials are taken from GDF_TRANSLATE_CREDENTIALS_JSON, GOOGLE_APPLICATION_CREDENTIALS or gcloud application default credentials.")
                 .takes_value(true)
-                .required(true)
+                .required(false)
+        )
+        .arg(
+            Arg::with_name("token_url")
+                .long("token-url")
+                .value_name("URL")
+                .help("OAuth2 token endpoint used to retrieve access tokens. If not specified defaults to https://www.googleapis.com/oauth2/v4/token.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_TOKEN_URL")
+        )
+        .arg(
+            Arg::with_name("project_id")
+                .long("project-id")
+                .value_name("PROJECT ID")
+                .help("Google Cloud project used for V3 translation. If not specified project from credentials is used.")
+                .takes_value(true)
+                .env("GOOGLE_CLOUD_PROJECT")
+        )
+        .arg(
+            Arg::with_name("storage_url")
+                .long("storage-url")
+                .value_name("URL")
+                .help("Base URL of Google Cloud Storage API. Intended for running against local emulators (e.g. fake-gcs-server). If not specified defaults to https://storage.googleapis.com.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_STORAGE_URL")
+        )
+        .arg(
+            Arg::with_name("translate_url")
+                .long("translate-url")
+                .value_name("URL")
+                .help("Base URL of Google Translate V2/V3 API. Intended for running against local emulators. If not specified defaults to https://translation.googleapis.com.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_TRANSLATE_URL")
+        )
+        .arg(
+            Arg::with_name("dialogflow_url")
+                .long("dialogflow-url")
+                .value_name("URL")
+                .help("Base URL of Dialogflow ES API used by --from-project. Intended for running against local emulators. If not specified defaults to https://dialogflow.googleapis.com.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_DIALOGFLOW_URL")
+        )
+        .arg(
+            Arg::with_name("bucket_creation_delay")
+                .long("bucket-delay")
+                .value_name("MILLISECONDS")
+                .help("Delay between creation of temporary storage buckets used by V3 API. If not specified defaults to 20000. Ignored when using V2 API.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_BUCKET_DELAY")
+                .validator(is_non_negative_integer)
+        )
+        .arg(
+            Arg::with_name("operation_timeout")
+                .long("operation-timeout")
+                .value_name("MILLISECONDS")
+                .help("Maximal time to wait for long running operation (Dialogflow agent export, restore/import, training or V3 batch translation) to finish. If not specified defaults to 600000.")
+                .takes_value(true)
+                .env("GDF_TRANSLATE_OPERATION_TIMEOUT")
+                .validator(is_positive_integer)
+        )
+        .arg(
+            Arg::with_name("retry_attempts")
+                .long("retry-attempts")
+                .value_name("INTEGER")
+                .help("Maximal number of attempts for every Google API call (including the first one). Only idempotent calls or calls rejected with HTTP 429/503 or 403 rateLimitExceeded are retried. If not specified defaults to 5.")
+                .takes_value(true)
+                .default_value("5")
+                .validator(is_attempt_count)
+        )
+        .arg(
+            Arg::with_name("retry_backoff")
+                .long("retry-backoff")
+                .value_name("MILLISECONDS")
+                .help("Initial delay before retrying failed Google API call. Delay is doubled (with random jitter) for every next attempt up to 32 seconds. Retry-After header returned by Google API takes precedence (up to 32 seconds as well). If not specified defaults to 1000.")
+                .takes_value(true)
+                .default_value("1000")
+                .validator(is_non_negative_integer)
         )
         .arg(
             Arg::with_name("translation_mode")
@@ -120,6 +252,24 @@ pub fn get_cmd_line_parser<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .default_value("10")
         )
+        .arg(
+            Arg::with_name("v2_chars_per_minute")
+                .long("chars-per-minute")
+                .value_name("INTEGER")
+                .help("Maximal number of characters per minute sent to Google V2 translation API (shared by all tasks). If not specified characters are not limited. Ignored when using V3 API.")
+                .takes_value(true)
+                .validator(is_positive_integer)
+                .env("GDF_TRANSLATE_CHARS_PER_MINUTE")
+        )
+        .arg(
+            Arg::with_name("v2_requests_per_minute")
+                .long("requests-per-minute")
+                .value_name("INTEGER")
+                .help("Maximal number of requests per minute sent to Google V2 translation API (shared by all tasks). If not specified requests are not limited. Ignored when using V3 API.")
+                .takes_value(true)
+                .validator(is_positive_integer)
+                .env("GDF_TRANSLATE_REQUESTS_PER_MINUTE")
+        )
         .arg(
             Arg::with_name("create_output_tsv")
                 .short("d")
@@ -157,22 +307,239 @@ pub fn get_cmd_line_parser<'a, 'b>() -> App<'a, 'b> {
                 .takes_value(true)
                 .required(false)
         )
+        .arg(
+            Arg::with_name("model")
+                .long("model")
+                .value_name("[LANG=]MODEL")
+                .help("Translation model used by V3 API. Either full model name (projects/.../models/...), general model (general/nmt, general/base) or ID of AutoML model. Can be prefixed with target language (e.g. de=general/nmt) and specified multiple times. If not specified general NMT model is used. Ignored when using V2 API.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .required(false)
+        )
+        .arg(
+            Arg::with_name("deploy_mode")
+                .long("deploy")
+                .value_name("restore/import")
+                .help("Deploys translated agent to Dialogflow when translation is finished. restore replaces the agent completely, import adds/replaces intents and entities. Current agent (if not empty) is exported into output folder as backup_agent.zip first.")
+                .takes_value(true)
+                .possible_values(&["restore", "import"])
+        )
+        .arg(
+            Arg::with_name("deploy_project")
+                .long("deploy-project")
+                .value_name("PROJECT ID")
+                .help("Google Cloud project of the agent translated agent is deployed to. If not specified project from --from-project is used.")
+                .takes_value(true)
+                .requires("deploy_mode")
+        )
+        .arg(
+            Arg::with_name("train_agent")
+                .long("train")
+                .help("If present agent is trained after deployment and tool waits until training is finished.")
+                .takes_value(false)
+                .requires("deploy_mode")
+        )
+        .arg(
+            Arg::with_name("assume_yes")
+                .long("yes")
+                .help("If present no confirmation is required when restoring over non-empty agent.")
+                .takes_value(false)
+                .requires("deploy_mode")
+        )
+        .arg(
+            Arg::with_name("translate_manifest")
+                .long("translate-manifest")
+                .help("If present agent description, short description and examples are translated as well. Agent has single (not language specific) version of these texts so they are replaced by translation.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("translate_default_values")
+                .long("translate-default-values")
+                .help("If present literal default values of intent parameters are translated. Parameter has single (not language specific) default value so it is replaced by translation. By default default values are kept and reported.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("time_zone")
+                .long("time-zone")
+                .value_name("TIME ZONE")
+                .help("Time zone of the agent set when target language is added, e.g. Europe/Berlin. If not specified time zone of the agent is not changed.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("spell_correction")
+                .long("spell-correction")
+                .value_name("on/off")
+                .help("Enables/disables spell correction of the agent when target language is added. If not specified spell correction setting is not changed.")
+                .takes_value(true)
+                .possible_values(&["on", "off"])
+        )
+        .arg(
+            Arg::with_name("entity_values")
+                .long("entity-values")
+                .value_name("[ENTITY=]keep/translate")
+                .help("Whether entity reference values are translated or kept as in source language (only synonyms are translated then). Can be prefixed with entity name (e.g. delivery-type=keep) and specified multiple times. If not specified reference values are translated.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("normalize_synonyms")
+                .long("normalize-synonyms")
+                .help("If present translated entity synonyms are normalized: punctuation is stripped, empty and duplicated synonyms are removed, value of map entity is added to its synonyms and entries colliding with other entries are removed. All changes are listed in run summary.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("synonyms_dedupe")
+                .long("synonyms-dedupe")
+                .value_name("exact/ignore-case/ignore-case-diacritics")
+                .help("How duplicated synonyms are detected by --normalize-synonyms. If not specified ignore-case is used.")
+                .takes_value(true)
+                .possible_values(&["exact", "ignore-case", "ignore-case-diacritics"])
+                .requires("normalize_synonyms")
+        )
+        .arg(
+            Arg::with_name("keep_cloned_metadata")
+                .long("keep-cloned-metadata")
+                .help("If present training phrases cloned to target language keep ID, counter and timestamp of source language phrases. Intended for debugging, by default cloned phrases get new IDs.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("cloned_count")
+                .long("cloned-count")
+                .value_name("INTEGER")
+                .help("Counter of training phrases cloned to target language (ES agents only). If not specified defaults to 0.")
+                .takes_value(true)
+                .validator(is_cloned_count)
+                .conflicts_with("keep_cloned_metadata")
+        )
+        .arg(
+            Arg::with_name("cloned_updated")
+                .long("cloned-updated")
+                .value_name("EPOCH SECONDS")
+                .help("Timestamp of training phrases cloned to target language (ES agents only). If not specified current time is used.")
+                .takes_value(true)
+                .validator(is_epoch_seconds)
+                .conflicts_with("keep_cloned_metadata")
+        )
+        .arg(
+            Arg::with_name("payload_text")
+                .long("payload-text")
+                .value_name("RULE")
+                .help("Rule selecting texts of custom payloads to translate. Either JSONPath (e.g. $.richContent[*][*].options[*].text) or JSON Pointer with * as wildcard (e.g. /richContent/*/*/options/*/text). Can be specified multiple times. If not specified custom payloads are copied to target language without translation.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+        )
+        .arg(
+            Arg::with_name("payload_exclude_key")
+                .long("payload-exclude-key")
+                .value_name("KEY")
+                .help("Key of custom payloads whose values are never translated even if selected by --payload-text. URLs, IDs, postbacks, events and similar keys are excluded always. Can be specified multiple times.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .requires("payload_text")
+        )
+        .arg(
+            Arg::with_name("media_urls")
+                .long("media-urls")
+                .value_name("FILE")
+                .help("Path to JSON file mapping image and media URLs (or URL patterns with *) to localized URLs per language. Mapped URLs are substituted when messages are cloned to target language, URLs without mapping are reported.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("url_rewrites")
+                .long("url-rewrites")
+                .value_name("FILE")
+                .help("Path to JSON file with URL rewrite rules (regex and replacement) per target language. Rules are applied to URLs of link-outs, buttons, browse carousel items and postbacks when messages are cloned to target language. Rewritten and untouched URLs are reported.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("abbreviations")
+                .long("abbreviations")
+                .value_name("FILE")
+                .help("Path to JSON file with abbreviations of phrases per target language. Translated texts exceeding length limit of their channel (e.g. suggestion chips, quick replies) are shortened by the abbreviations. Shortened texts and texts still exceeding the limit are reported.")
+                .takes_value(true)
+        )
+        .arg(
+            Arg::with_name("truncate_quick_replies")
+                .long("truncate-quick-replies")
+                .help("If present translated quick replies exceeding 20 characters (even with --abbreviations applied) are truncated at word boundary, Dialogflow does not accept longer ones. By default they are only reported.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("platforms")
+                .long("platforms")
+                .value_name("PLATFORMS")
+                .help("Comma separated platforms (channels) of responses to translate, e.g. default,facebook. Responses without platform belong to default platform. If not specified responses of all platforms are translated.")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+        )
+        .arg(
+            Arg::with_name("exclude_platforms")
+                .long("exclude-platforms")
+                .value_name("PLATFORMS")
+                .help("Comma separated platforms (channels) of responses not to translate, e.g. slack,skype.")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+        )
+        .arg(
+            Arg::with_name("copy_skipped_platforms")
+                .long("copy-skipped-platforms")
+                .help("If present responses of platforms not selected by --platforms/--exclude-platforms are copied to target language untranslated. By default they are not copied at all.")
+                .takes_value(false)
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .help("If present agent files containing fields unknown to gdf_translate (e.g. added to export format by Google recently) are rejected. By default unknown fields are copied to translated agent unchanged and listed in the report.")
+                .takes_value(false)
+        )
 }
 
 pub fn get_cmdl_options<'a>(matches: &'a ArgMatches) -> CommandLine<'a> {
     let translation_mode;
 
-    // safe to unwrap 5 belove listed params since they are defined for clap as required required!
-    let gdf_agent_zip_path = Path::new(matches.value_of("gdf_agent_zip_path").unwrap());
+    // safe to unwrap 3 belove listed params since they are defined for clap as required required!
     let output_folder = Path::new(matches.value_of("output_folder").unwrap());
     let from_lang = matches.value_of("from_lang").unwrap();
     let to_lang = matches.value_of("to_lang").unwrap();
-    let gcloud_svc_acc_cred = Path::new(matches.value_of("gcloud_svc_acc_cred").unwrap());
+    // agent file is required unless agent is exported directly from dialogflow (--from-project)
+    let gdf_agent_zip_path = matches.value_of("gdf_agent_zip_path").map(Path::new);
+    let from_project = matches.value_of("from_project").map(|val| val.to_owned());
+    let export_uri = matches.value_of("export_uri").map(|val| val.to_owned());
+    let gcloud_svc_acc_cred = matches.value_of_os("gcloud_svc_acc_cred").map(Path::new);
+    let token_url = matches.value_of("token_url").map(|val| val.to_owned());
+    let project_id = matches.value_of("project_id").map(|val| val.to_owned());
+    let storage_url = matches.value_of("storage_url").map(|val| val.to_owned());
+    let translate_url = matches.value_of("translate_url").map(|val| val.to_owned());
+    let dialogflow_url = matches.value_of("dialogflow_url").map(|val| val.to_owned());
+    let bucket_creation_delay = matches
+        .value_of("bucket_creation_delay")
+        .map(|val| val.parse::<u64>().unwrap());
+    let operation_timeout = matches
+        .value_of("operation_timeout")
+        .map(|val| val.parse::<u64>().unwrap());
     let create_output_tsv = matches.is_present("create_output_tsv");
     let skip_entities_translation = matches.is_present("skip_entities_translation");
     let skip_utterances_translation = matches.is_present("skip_utterances_translation");
     let skip_responses_translation = matches.is_present("skip_responses_translation");
 
+    let retry_attempts = matches
+        .value_of("retry_attempts")
+        .unwrap()
+        .parse::<u32>()
+        .unwrap();
+
+    let retry_backoff = matches
+        .value_of("retry_backoff")
+        .unwrap()
+        .parse::<u64>()
+        .unwrap();
+
     let v2_task_count = matches
         .value_of("v2_task_count")
         .unwrap()
@@ -180,6 +547,14 @@ pub fn get_cmdl_options<'a>(matches: &'a ArgMatches) -> CommandLine<'a> {
         .parse::<usize>()
         .unwrap();
 
+    let v2_chars_per_minute = matches
+        .value_of("v2_chars_per_minute")
+        .map(|val| val.parse::<u64>().unwrap());
+
+    let v2_requests_per_minute = matches
+        .value_of("v2_requests_per_minute")
+        .map(|val| val.parse::<u64>().unwrap());
+
     if let Some(val) = matches.value_of("translation_mode") {
         match val {
             "v2" | "V2" => translation_mode = TranslationProviders::GoogleTranslateV2,
@@ -195,18 +570,123 @@ pub fn get_cmdl_options<'a>(matches: &'a ArgMatches) -> CommandLine<'a> {
         glossary = Some(Path::new(val));
     }
 
-    CommandLine::new(
+    let deploy_mode = matches.value_of("deploy_mode").map(|val| match val {
+        "restore" => DeployMode::Restore,
+        "import" => DeployMode::Import,
+        _ => unreachable!(),
+    });
+    let deploy_project = matches
+        .value_of("deploy_project")
+        .or_else(|| matches.value_of("from_project"))
+        .map(|val| val.to_owned());
+    let train_agent = matches.is_present("train_agent");
+    let assume_yes = matches.is_present("assume_yes");
+
+    let agent_options = AgentOptions {
+        translate_manifest: matches.is_present("translate_manifest"),
+        translate_default_values: matches.is_present("translate_default_values"),
+        time_zone: matches.value_of("time_zone").map(|val| val.to_owned()),
+        spell_correction: matches.value_of("spell_correction").map(|val| val == "on"),
+        synonyms_normalization: if matches.is_present("normalize_synonyms") {
+            Some(SynonymsNormalization {
+                dedupe: match matches.value_of("synonyms_dedupe") {
+                    Some("exact") => SynonymsDedupe::Exact,
+                    Some("ignore-case-diacritics") => SynonymsDedupe::IgnoreCaseDiacritics,
+                    _ => SynonymsDedupe::IgnoreCase,
+                },
+            })
+        } else {
+            None
+        },
+        cloned_metadata: ClonedMetadata {
+            keep: matches.is_present("keep_cloned_metadata"),
+            count: matches
+                .value_of("cloned_count")
+                .map(|val| val.parse::<i8>().unwrap())
+                .unwrap_or_default(),
+            updated: matches
+                .value_of("cloned_updated")
+                .map(|val| val.parse::<i64>().unwrap()),
+        },
+        platforms: Platforms::new(
+            &matches
+                .values_of("platforms")
+                .map(|values| values.collect::<Vec<&str>>())
+                .unwrap_or_default(),
+            &matches
+                .values_of("exclude_platforms")
+                .map(|values| values.collect::<Vec<&str>>())
+                .unwrap_or_default(),
+            matches.is_present("copy_skipped_platforms"),
+        ),
+        truncate_quick_replies: matches.is_present("truncate_quick_replies"),
+        strict: matches.is_present("strict"),
+        ..Default::default()
+    };
+
+    let models = matches
+        .values_of("model")
+        .map(|values| values.collect())
+        .unwrap_or_default();
+
+    let entity_values = matches
+        .values_of("entity_values")
+        .map(|values| values.collect())
+        .unwrap_or_default();
+
+    let payload_rules = matches
+        .values_of("payload_text")
+        .map(|values| values.collect())
+        .unwrap_or_default();
+
+    let payload_excluded_keys = matches
+        .values_of("payload_exclude_key")
+        .map(|values| values.collect())
+        .unwrap_or_default();
+
+    let media_urls_path = matches.value_of("media_urls").map(Path::new);
+
+    let url_rewrites_path = matches.value_of("url_rewrites").map(Path::new);
+
+    let abbreviations_path = matches.value_of("abbreviations").map(Path::new);
+
+    CommandLine {
         gdf_agent_zip_path,
+        from_project,
+        export_uri,
         output_folder,
-        from_lang.to_owned(),
-        to_lang.to_owned(),
+        from_lang: from_lang.to_owned(),
+        to_lang: to_lang.to_owned(),
         gcloud_svc_acc_cred,
+        token_url,
+        project_id,
+        storage_url,
+        translate_url,
+        dialogflow_url,
+        bucket_creation_delay,
+        operation_timeout,
+        retry_attempts,
+        retry_backoff,
         translation_mode,
         create_output_tsv,
         v2_task_count,
+        v2_chars_per_minute,
+        v2_requests_per_minute,
         skip_entities_translation,
         skip_utterances_translation,
         skip_responses_translation,
-        glossary,
-    )
+        glossary_path: glossary,
+        models,
+        deploy_mode,
+        deploy_project,
+        train_agent,
+        assume_yes,
+        agent_options,
+        entity_values,
+        payload_rules,
+        payload_excluded_keys,
+        media_urls_path,
+        url_rewrites_path,
+        abbreviations_path,
+    }
 }
@@ -55,6 +55,14 @@ impl From<GlobError> for Error {
     }
 }
 
+impl From<walkdir::Error> for Error {
+    fn from(error: walkdir::Error) -> Error {
+        Error {
+            message: format!("{}", error),
+        }
+    }
+}
+
 // required by surf crate
 impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
     fn from(error: Box<dyn std::error::Error + Send + Sync>) -> Error {
@@ -1,2 +1,122 @@
 pub mod agent;
+pub mod api;
+pub mod cx;
+pub mod ids;
+pub mod limits;
+pub mod media;
+pub mod options;
+pub mod payload;
 pub mod responses;
+pub mod synonyms;
+pub mod unknown_fields;
+pub mod url_rewrites;
+
+use crate::errors::Result;
+use crate::summary::ReportItem;
+use agent::{parse_gdf_agent_zip, GoogleDialogflowAgent};
+use cx::{is_cx_agent_zip, parse_cx_agent_zip, CxAgent};
+use options::AgentOptions;
+use std::collections;
+
+/// Dialogflow ES or CX agent, translation providers work with both the same way
+#[derive(Debug)]
+pub enum DialogflowAgent {
+    Es(Box<GoogleDialogflowAgent>),
+    Cx(Box<CxAgent>),
+}
+
+impl DialogflowAgent {
+    pub fn to_translation(
+        &mut self,
+        lang_from: &str,
+        lang_to: &str,
+        skip_entities_translation: bool,
+        skip_utterances_translation: bool,
+        skip_responses_translation: bool,
+        options: &AgentOptions,
+    ) -> collections::HashMap<String, String> {
+        match self {
+            DialogflowAgent::Es(agent) => agent.to_translation(
+                lang_from,
+                lang_to,
+                skip_entities_translation,
+                skip_utterances_translation,
+                skip_responses_translation,
+                options,
+            ),
+            DialogflowAgent::Cx(agent) => agent.to_translation(
+                lang_from,
+                lang_to,
+                skip_entities_translation,
+                skip_utterances_translation,
+                skip_responses_translation,
+                options,
+            ),
+        }
+    }
+
+    pub fn from_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+        lang_to: &str,
+        skip_entities_translation: bool,
+        skip_utterances_translation: bool,
+        skip_responses_translation: bool,
+        options: &AgentOptions,
+    ) {
+        match self {
+            DialogflowAgent::Es(agent) => agent.from_translation(
+                translations_map,
+                lang_to,
+                skip_entities_translation,
+                skip_utterances_translation,
+                skip_responses_translation,
+                options,
+            ),
+            DialogflowAgent::Cx(agent) => agent.from_translation(
+                translations_map,
+                lang_to,
+                skip_entities_translation,
+                skip_utterances_translation,
+                skip_responses_translation,
+                options,
+            ),
+        }
+    }
+
+    pub fn add_supported_language(&mut self, lang: &str, options: &AgentOptions) {
+        match self {
+            DialogflowAgent::Es(agent) => agent.add_supported_language(lang, options),
+            DialogflowAgent::Cx(agent) => agent.add_supported_language(lang, options),
+        }
+    }
+
+    /// findings of translation collected by the agent since last call
+    pub fn take_report(&mut self) -> Vec<ReportItem> {
+        match self {
+            DialogflowAgent::Es(agent) => agent.take_report(),
+            DialogflowAgent::Cx(agent) => agent.take_report(),
+        }
+    }
+
+    pub fn serialize(&self, target_folder: &str) -> Result<()> {
+        match self {
+            DialogflowAgent::Es(agent) => agent.serialize(target_folder),
+            DialogflowAgent::Cx(agent) => agent.serialize(target_folder),
+        }
+    }
+}
+
+/// parses ES or CX agent zip, CX agent is recognized by flows folder.
+/// Unknown fields fail the parsing only in strict mode, otherwise they are reported.
+pub fn parse_dialogflow_agent_zip(zip_path: &str, strict: bool) -> Result<DialogflowAgent> {
+    if is_cx_agent_zip(zip_path)? {
+        Ok(DialogflowAgent::Cx(Box::new(parse_cx_agent_zip(
+            zip_path, strict,
+        )?)))
+    } else {
+        Ok(DialogflowAgent::Es(Box::new(parse_gdf_agent_zip(
+            zip_path, strict,
+        )?)))
+    }
+}
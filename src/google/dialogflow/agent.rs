@@ -7,12 +7,19 @@ use intents::*;
 use utterances::*;
 
 use crate::errors::{Error, Result};
+use crate::google::dialogflow::ids::duplicated_ids;
+use crate::google::dialogflow::limits::{is_truncated, length_limit, truncate};
+use crate::google::dialogflow::options::AgentOptions;
 use crate::google::dialogflow::responses::normalize_json_for_gdf_agent_serialization;
+use crate::google::dialogflow::unknown_fields::{
+    report_unknown_fields, unknown_fields, UnknownFields,
+};
 use crate::google::gcloud::translate::Translate;
 use crate::parse_gdf_agent_files;
 use crate::serialize_gdf_agent_section;
+use crate::ssml::translated_ssml;
+use crate::summary::ReportItem;
 use crate::zip::{unzip_file, zip_directory};
-use assert_json_diff::assert_json_eq_no_panic;
 use glob::glob;
 use lazy_static::lazy_static;
 use log::debug;
@@ -25,8 +32,12 @@ use std::fs;
 use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::SystemTime;
 
+static TMP_FOLDER_SEQ: AtomicUsize = AtomicUsize::new(0);
+
 lazy_static! {
     pub static ref RE_ENTITY_ENTRY_FILE: Regex =
         Regex::new(r"(\w+entries_)([a-zA-Z-\d]+).json").unwrap();
@@ -39,6 +50,8 @@ lazy_static! {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Package {
     pub version: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -61,6 +74,8 @@ pub struct AgentManifestGoogleAssistantOauthLinking {
 
     #[serde(rename = "grantType")]
     pub grant_type: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -99,6 +114,8 @@ pub struct AgentManifestGoogleAssistant {
 
     #[serde(rename = "isDeviceAgent")]
     pub is_device_agent: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -116,6 +133,8 @@ pub struct AgentManifestWebhook {
 
     #[serde(rename = "cloudFunctionsInitialized")]
     cloud_functions_initialized: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -196,6 +215,36 @@ pub struct AgentManifest {
     #[serde(rename = "enableSpellCorrection")]
     pub enable_spell_correction: Option<bool>,
 
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for AgentManifest {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for text in [&self.description, &self.short_description, &self.examples].iter() {
+            if !text.trim().is_empty() {
+                map_to_translate.insert(format!("{:p}", *text), text.to_string());
+            }
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        for text in [
+            &mut self.description,
+            &mut self.short_description,
+            &mut self.examples,
+        ]
+        .iter_mut()
+        {
+            if let Some(translated) = translations_map.get(&format!("{:p}", *text)) {
+                **text = translated.to_owned();
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -206,6 +255,8 @@ pub struct GoogleDialogflowAgent {
     utterances: Vec<IntentUtterancesFile>,
     agent: AgentManifest,
     package: Package,
+    /// findings of translation reported to the user in run summary
+    report: Vec<ReportItem>,
 }
 
 impl GoogleDialogflowAgent {
@@ -224,6 +275,42 @@ impl GoogleDialogflowAgent {
             utterances,
             agent,
             package,
+            report: vec![],
+        }
+    }
+
+    /// findings collected since last call
+    pub fn take_report(&mut self) -> Vec<ReportItem> {
+        std::mem::take(&mut self.report)
+    }
+
+    /// IDs of intents, entities and utterances must be unique across the agent
+    fn report_duplicated_ids(&mut self) {
+        let ids = self
+            .intents
+            .iter()
+            .map(|file| (file.file_content.id.as_str(), file.file_name.as_str()))
+            .chain(
+                self.entities
+                    .iter()
+                    .map(|file| (file.file_content.id.as_str(), file.file_name.as_str())),
+            )
+            .chain(self.utterances.iter().flat_map(|file| {
+                file.file_content.iter().filter_map(move |utterance| {
+                    utterance
+                        .id
+                        .as_deref()
+                        .map(|id| (id, file.file_name.as_str()))
+                })
+            }));
+        for (id, files) in duplicated_ids(ids) {
+            for file in files.iter() {
+                self.report.push(ReportItem::new(
+                    "duplicated_id",
+                    file,
+                    format!("ID {} is used {} times", id, files.len()),
+                ));
+            }
         }
     }
 
@@ -241,11 +328,19 @@ impl GoogleDialogflowAgent {
         format!("{}{}", entity_file_name, ".json")
     }
 
-    pub fn add_supported_language(&mut self, lang: &str) {
+    /// adds language to supported languages. Time zone and spell correction are agent wide settings,
+    /// if specified they are updated as well
+    pub fn add_supported_language(&mut self, lang: &str, options: &AgentOptions) {
         let lang_to_add = lang.to_owned();
         if !self.agent.supported_languages.contains(&lang_to_add) {
             self.agent.supported_languages.push(lang_to_add);
         }
+        if let Some(time_zone) = &options.time_zone {
+            self.agent.default_timezone = time_zone.to_owned();
+        }
+        if let Some(spell_correction) = options.spell_correction {
+            self.agent.enable_spell_correction = Some(spell_correction);
+        }
     }
 
     pub fn to_translation(
@@ -255,18 +350,28 @@ impl GoogleDialogflowAgent {
         skip_entities_translation: bool,
         skip_utterances_translation: bool,
         skip_responses_translation: bool,
+        options: &AgentOptions,
     ) -> collections::HashMap<String, String> {
         let mut translations_map: collections::HashMap<String, String> =
             collections::HashMap::new();
 
+        if options.translate_manifest {
+            translations_map.extend(self.agent.to_translation());
+        }
+
         // for regex entities
         let mut entity_entry_files_to_skip: Vec<String> = vec![];
+        // entities whose reference values stay in source language
+        let mut entity_entry_files_keeping_values: Vec<String> = vec![];
 
         if skip_entities_translation == false {
             // create new entity entry files and add their content to map to translate
             let mut new_entity_entry_files = vec![];
             for entity_entry_file in self.entity_entries.iter() {
-                debug!("GoogleDialogFLowAgent::to_translation, processing file {}", &entity_entry_file.file_name);
+                debug!(
+                    "GoogleDialogFLowAgent::to_translation, processing file {}",
+                    &entity_entry_file.file_name
+                );
                 let caps = RE_ENTITY_ENTRY_FILE
                     .captures(&entity_entry_file.file_name)
                     .unwrap();
@@ -285,9 +390,17 @@ impl GoogleDialogflowAgent {
 
                 if &caps[2] == lang_from {
                     let new_file = entity_entry_file.to_new_language(lang_to);
-                    if entity_files[0].file_content.is_regexp == true {
-                        // we will be skipping regex entities
+                    let keep_values = options
+                        .entity_values
+                        .keep_values(&entity_files[0].file_content.name);
+                    if entity_files[0].file_content.is_regexp == true
+                        || (keep_values && entity_files[0].file_content.is_enum)
+                    {
+                        // we will be skipping regex entities and list entities keeping values
+                        // (their only synonym is the value itself)
                         entity_entry_files_to_skip.push(new_file.file_name.clone());
+                    } else if keep_values {
+                        entity_entry_files_keeping_values.push(new_file.file_name.clone());
                     }
 
                     new_entity_entry_files.push(new_file);
@@ -301,13 +414,20 @@ impl GoogleDialogflowAgent {
                 if entity_entry_files_to_skip.contains(&new_entity_entry_file.file_name) {
                     continue; // skip entity entry files for regex entities!
                 }
+                let keep_values =
+                    entity_entry_files_keeping_values.contains(&new_entity_entry_file.file_name);
                 for new_entity_entry in new_entity_entry_file.file_content.iter() {
-                    if RE_COMPOSITE_ENTITY.is_match(&new_entity_entry.value) == false
-                        && RE_COMPOSITE_ENTITY_NO_ALIAS.is_match(&new_entity_entry.value) == false
-                    /* skip composite entities*/
-                    {
-                        translations_map.extend(new_entity_entry.to_translation());
+                    let mut entry_translations = if new_entity_entry.is_composite() {
+                        // entity references are protected by placeholders
+                        new_entity_entry.composite_to_translation()
+                    } else {
+                        new_entity_entry.to_translation()
+                    };
+                    if keep_values {
+                        // only synonyms are translated, webhooks rely on reference values
+                        entry_translations.remove(&format!("{:p}", &new_entity_entry.value));
                     }
+                    translations_map.extend(entry_translations);
                 }
             }
 
@@ -323,12 +443,18 @@ impl GoogleDialogflowAgent {
                     .unwrap();
 
                 if &caps[2] == lang_from {
-                    new_utterance_files.push(utterance_file.to_new_language(lang_to));
+                    new_utterance_files
+                        .push(utterance_file.to_new_language(lang_to, &options.cloned_metadata));
                 }
             }
 
             for new_utterance_file in new_utterance_files.iter() {
                 for utterance in new_utterance_file.file_content.iter() {
+                    if utterance.is_template {
+                        // entity tokens of templates are protected by placeholders
+                        translations_map.extend(utterance.template_to_translation());
+                        continue;
+                    }
                     for utterance_data in utterance.data.iter() {
                         translations_map.extend(utterance_data.to_translation());
                     }
@@ -343,15 +469,13 @@ impl GoogleDialogflowAgent {
             let mut intents_not_to_translate = vec![];
             'intent_loop: for intent_file in self.intents.iter() {
                 let intent = &intent_file.file_content;
-                for intent_response in intent.responses.iter() {
-                    for intent_response_message in intent_response.messages.iter() {
-                        if intent_response_message.get_message_lang() == lang_to {
-                            // if intent has already messages in target language just skip it
-                            // DialogFlow will translate some intent sby default when new lang is added
-                            // e.g. Default Welcome Intent, Fallback
-                            intents_not_to_translate.push(intent.name.to_string());
-                            continue 'intent_loop;
-                        }
+                for intent_response_message in intent.messages() {
+                    if intent_response_message.get_message_lang() == lang_to {
+                        // if intent has already messages in target language just skip it
+                        // DialogFlow will translate some intent sby default when new lang is added
+                        // e.g. Default Welcome Intent, Fallback
+                        intents_not_to_translate.push(intent.name.to_string());
+                        continue 'intent_loop;
                     }
                 }
             }
@@ -366,31 +490,94 @@ impl GoogleDialogflowAgent {
 
                 //... for those that still needs to be translated iterate all responses in source language
                 // clone them (while changing the target language) + add the references' addresses into translation map
-                for intent_response in intent.responses.iter_mut() {
+                // (conditional responses are cloned the same way, their conditions are kept as they are)
+                for messages in intent.message_lists_mut() {
                     let mut new_messages = vec![];
-                    for intent_response_message in intent_response.messages.iter() {
+                    for intent_response_message in messages.iter() {
                         if intent_response_message.get_message_lang() == lang_from {
+                            // messages of platforms which are not translated are copied only on demand
+                            if !options
+                                .platforms
+                                .is_cloned(intent_response_message.get_platform())
+                            {
+                                continue;
+                            }
                             let new_message = intent_response_message.new_message(lang_to);
-                            if let Some(message) = new_message {
+                            if let Some(mut message) = new_message {
+                                if let Some(media_urls) = &options.media_urls {
+                                    for url in message.media_urls_mut() {
+                                        if url.is_empty() {
+                                            continue;
+                                        }
+                                        match media_urls.localize(url, lang_to) {
+                                            Some(localized_url) => *url = localized_url,
+                                            None => self.report.push(ReportItem::new(
+                                                "media_url",
+                                                &intent_file.file_name,
+                                                format!("no {} URL for {}", lang_to, url),
+                                            )),
+                                        }
+                                    }
+                                }
+                                if let Some(url_rewrites) = &options.url_rewrites {
+                                    for finding in
+                                        url_rewrites.rewrite_urls(message.link_urls_mut(), lang_to)
+                                    {
+                                        self.report.push(ReportItem::new(
+                                            "url",
+                                            &intent_file.file_name,
+                                            finding,
+                                        ));
+                                    }
+                                }
                                 new_messages.push(message);
                             }
                         }
                     }
-                    intent_response.messages.extend(new_messages);
+                    messages.extend(new_messages);
+                }
+
+                // slot filling prompts get target language variants, parameters are not moved anymore
+                // so the addresses stored in translation map stay valid
+                for intent_response in intent.responses.iter_mut() {
+                    for parameter in intent_response.parameters.iter_mut() {
+                        parameter.add_language(lang_from, lang_to);
+                        translations_map.extend(
+                            parameter
+                                .collect_translation(lang_to, options.translate_default_values),
+                        );
+                        if options.translate_default_values {
+                            continue;
+                        }
+                        if let Some(default_value) = parameter.literal_default_value() {
+                            self.report.push(ReportItem::new(
+                                "default_value",
+                                &intent_file.file_name,
+                                format!(
+                                    "default value {} of parameter {} is shared by all languages, not translated",
+                                    default_value, parameter.name
+                                ),
+                            ));
+                        }
+                    }
                 }
             }
 
             for intent_file in self.intents.iter() {
-                for intent_response in intent_file.file_content.responses.iter() {
-                    for message in intent_response.messages.iter() {
-                        if message.get_message_lang() == lang_to {
-                            translations_map.extend(message.to_translation());
-                        }
+                for message in intent_file.file_content.messages() {
+                    if message.get_message_lang() == lang_to
+                        && options.platforms.is_selected(message.get_platform())
+                    {
+                        translations_map.extend(message.to_translation());
+                        translations_map
+                            .extend(message.payload_to_translation(&options.payload_rules));
                     }
                 }
             }
         }
 
+        self.report_duplicated_ids();
+
         translations_map
     }
 
@@ -401,11 +588,64 @@ impl GoogleDialogflowAgent {
         skip_entities_translation: bool,
         skip_utterances_translation: bool,
         skip_responses_translation: bool,
+        options: &AgentOptions,
     ) {
+        self.agent.from_translation(translations_map);
+
         if skip_entities_translation == false {
             for entity_entry_file in self.entity_entries.iter_mut() {
                 for entity_entry in entity_entry_file.file_content.iter_mut() {
-                    entity_entry.from_translation(translations_map);
+                    if entity_entry.is_composite() {
+                        for problem in entity_entry.composite_apply_translation(translations_map) {
+                            self.report.push(ReportItem::new(
+                                "composite_entity",
+                                &entity_entry_file.file_name,
+                                format!("{}, translation not applied", problem),
+                            ));
+                        }
+                    } else {
+                        entity_entry.from_translation(translations_map);
+                    }
+                }
+
+                let caps = RE_ENTITY_ENTRY_FILE
+                    .captures(&entity_entry_file.file_name)
+                    .unwrap();
+                if &caps[2] != lang_to {
+                    continue;
+                }
+                let entity_file_name =
+                    GoogleDialogflowAgent::entity_entry_file_name_to_entity_filename(
+                        &entity_entry_file.file_name,
+                    );
+                if let Some(entity_file) = self
+                    .entities
+                    .iter()
+                    .find(|entity| entity.file_name == entity_file_name)
+                {
+                    let entity = &entity_file.file_content;
+                    if entity.is_regexp {
+                        continue;
+                    }
+                    let keep_values = options.entity_values.keep_values(&entity.name);
+                    if keep_values {
+                        for entity_entry in entity_entry_file.file_content.iter_mut() {
+                            entity_entry.adjust_synonyms_to_kept_value(entity.is_enum);
+                        }
+                    }
+                    if let Some(normalization) = &options.synonyms_normalization {
+                        for change in normalization.normalize(
+                            &mut entity_entry_file.file_content,
+                            !entity.is_enum,
+                            keep_values,
+                        ) {
+                            self.report.push(ReportItem::new(
+                                "synonyms",
+                                &entity_entry_file.file_name,
+                                change,
+                            ));
+                        }
+                    }
                 }
             }
         }
@@ -413,6 +653,16 @@ impl GoogleDialogflowAgent {
         if skip_utterances_translation == false {
             for utterances_file in self.utterances.iter_mut() {
                 for utterance_file in utterances_file.file_content.iter_mut() {
+                    if utterance_file.is_template {
+                        for problem in utterance_file.template_apply_translation(translations_map) {
+                            self.report.push(ReportItem::new(
+                                "template_utterance",
+                                &utterances_file.file_name,
+                                format!("{}, translation not applied", problem),
+                            ));
+                        }
+                        continue;
+                    }
                     for utterance_data in utterance_file.data.iter_mut() {
                         utterance_data.from_translation(translations_map);
                     }
@@ -422,13 +672,91 @@ impl GoogleDialogflowAgent {
 
         if skip_responses_translation == false {
             for intent_file in self.intents.iter_mut() {
-                for intent_response in intent_file.file_content.responses.iter_mut() {
-                    for message in intent_response.messages.iter_mut() {
-                        if message.get_message_lang() == lang_to {
+                for messages in intent_file.file_content.message_lists_mut() {
+                    for message in messages.iter_mut() {
+                        if message.get_message_lang() == lang_to
+                            && options.platforms.is_selected(message.get_platform())
+                        {
+                            // invalid SSML translation is not applied
+                            for ssml in message.ssml_texts() {
+                                if let Err(issue) = translated_ssml(ssml, translations_map) {
+                                    self.report.push(ReportItem::new(
+                                        "ssml",
+                                        &intent_file.file_name,
+                                        format!("{}, translation not applied", issue),
+                                    ));
+                                }
+                            }
                             message.from_translation(translations_map);
                         }
                     }
                 }
+                for intent_response in intent_file.file_content.responses.iter_mut() {
+                    for parameter in intent_response.parameters.iter_mut() {
+                        parameter.apply_translation(translations_map, lang_to);
+                    }
+                }
+            }
+            self.check_length_limits(lang_to, options);
+        }
+    }
+
+    /// reports texts of messages in target language exceeding length limit of their channel.
+    /// If abbreviations are provided such texts are shortened first.
+    fn check_length_limits(&mut self, lang_to: &str, options: &AgentOptions) {
+        for intent_file in self.intents.iter_mut() {
+            for messages in intent_file.file_content.message_lists_mut() {
+                for message in messages.iter_mut() {
+                    if message.get_message_lang() != lang_to
+                        || !options.platforms.is_selected(message.get_platform())
+                    {
+                        continue;
+                    }
+                    let platform = message.get_platform().to_owned();
+                    for (field, text) in message.length_limited_texts_mut() {
+                        let limit = match length_limit(&platform, field) {
+                            Some(limit) => limit,
+                            None => continue,
+                        };
+                        let length = text.chars().count();
+                        if length <= limit {
+                            continue;
+                        }
+
+                        let shortened = options
+                            .abbreviations
+                            .as_ref()
+                            .and_then(|abbreviations| abbreviations.shorten(text, lang_to, limit));
+                        let finding = match shortened {
+                            Some(shortened) => {
+                                let finding = format!(
+                                    "{} {} \"{}\" shortened to \"{}\", {} characters max",
+                                    platform, field, text, shortened, limit
+                                );
+                                *text = shortened;
+                                finding
+                            }
+                            None if options.truncate_quick_replies && is_truncated(field) => {
+                                let truncated = truncate(text, limit);
+                                let finding = format!(
+                                    "{} {} \"{}\" truncated to \"{}\", {} characters max",
+                                    platform, field, text, truncated, limit
+                                );
+                                *text = truncated;
+                                finding
+                            }
+                            None => format!(
+                                "{} {} \"{}\" has {} characters, {} characters max",
+                                platform, field, text, length, limit
+                            ),
+                        };
+                        self.report.push(ReportItem::new(
+                            "length_limit",
+                            &intent_file.file_name,
+                            finding,
+                        ));
+                    }
+                }
             }
         }
     }
@@ -478,12 +806,20 @@ parse_gdf_agent_files!(
     IntentUtterancesFile
 );
 
-pub fn parse_gdf_agent_zip(zip_path: &str) -> Result<GoogleDialogflowAgent> {
-    // create temp folder name as epoch time in sec
+/// unzips agent into newly created temp folder (next to current binary) and returns the folder path
+pub(crate) fn unzip_agent_to_tmp_folder(zip_path: &str) -> Result<PathBuf> {
+    // create temp folder name as epoch time in sec + process id + sequence number
+    // so that agents parsed in parallel (e.g. by tests) do not share the folder
     let ts_sec = SystemTime::now()
         .duration_since(SystemTime::UNIX_EPOCH)
         .unwrap()
         .as_secs();
+    let tmp_folder_name = format!(
+        "{}_{}_{}",
+        ts_sec,
+        process::id(),
+        TMP_FOLDER_SEQ.fetch_add(1, Ordering::SeqCst)
+    );
 
     // from curent binary path remove executable name (e.g. ddf_translate.exe) and add temp folder name
     let tmp_working_folder_path = current_exe()?
@@ -491,7 +827,21 @@ pub fn parse_gdf_agent_zip(zip_path: &str) -> Result<GoogleDialogflowAgent> {
         .as_ref()
         .parent()
         .unwrap()
-        .join(Path::new(&ts_sec.to_string()));
+        .join(Path::new(&tmp_folder_name));
+
+    debug!(
+        "unzip_agent_to_tmp_folder: creating folder={:?}",
+        tmp_working_folder_path
+    );
+    fs::create_dir_all(&tmp_working_folder_path)?;
+    unzip_file(zip_path, tmp_working_folder_path.to_str().unwrap())?;
+    Ok(tmp_working_folder_path)
+}
+
+/// parses ES agent zip. Fields unknown to gdf_translate are copied unchanged and reported,
+/// in strict mode they fail the parsing
+pub fn parse_gdf_agent_zip(zip_path: &str, strict: bool) -> Result<GoogleDialogflowAgent> {
+    let tmp_working_folder_path = unzip_agent_to_tmp_folder(zip_path)?;
 
     let agent_manifest_file = tmp_working_folder_path.join("agent.json");
     let package_file = tmp_working_folder_path.join("package.json");
@@ -526,67 +876,56 @@ pub fn parse_gdf_agent_zip(zip_path: &str) -> Result<GoogleDialogflowAgent> {
     glob_intents.push("*.json");
     debug!("parse_gdf_agent_zip: glob_intents={:?}", glob_intents);
 
-    // convert to string slice
-    let tmp_working_folder_path = tmp_working_folder_path.to_str().unwrap();
-
-    debug!(
-        "parse_gdf_agent_zip: creating folder={}",
-        tmp_working_folder_path
-    );
-    fs::create_dir_all(tmp_working_folder_path)?;
-    unzip_file(zip_path, tmp_working_folder_path)?;
-
-    let entities = parse_gdf_agent_files_entity(&glob_entities)?;
-    let entity_entries = parse_gdf_agent_files_entity_entries(&glob_entity_entries)?;
-    let intents = parse_gdf_agent_files_intent(&glob_intents)?;
-    let utterances = parse_gdf_agent_files_intent_utterances(&glob_intents_usersays)?;
+    let mut report = vec![];
+    let entities = parse_gdf_agent_files_entity(&glob_entities, strict, &mut report)?;
+    let entity_entries =
+        parse_gdf_agent_files_entity_entries(&glob_entity_entries, strict, &mut report)?;
+    let intents = parse_gdf_agent_files_intent(&glob_intents, strict, &mut report)?;
+    let utterances =
+        parse_gdf_agent_files_intent_utterances(&glob_intents_usersays, strict, &mut report)?;
 
     // process agent.json
     debug!("parse_gdf_agent_zip: processing agent.json");
-    let file_str = fs::read_to_string(agent_manifest_file)?;
+    let file_str = fs::read_to_string(&agent_manifest_file)?;
     let agent_manifest: AgentManifest = serde_json::from_str(&file_str)?;
-    let serialized_str = serde_json::to_string(&agent_manifest).unwrap();
-    let comparison_result = assert_json_eq_no_panic(
-        &serde_json::from_str(&serialized_str)?,
-        &serde_json::from_str(&file_str)?,
-    );
-
-    if let Err(err_msg) = comparison_result {
-        return Err(Error::new(err_msg));
-    }
+    let fields = unknown_fields(&agent_manifest, &file_str, strict)?;
+    report_unknown_fields(&mut report, agent_manifest_file.to_str().unwrap(), fields);
 
     // process package.json
     debug!("parse_gdf_agent_zip: processing package.json");
-    let file_str = fs::read_to_string(package_file)?;
+    let file_str = fs::read_to_string(&package_file)?;
     let package: Package = serde_json::from_str(&file_str)?;
-    let serialized_str = serde_json::to_string(&package).unwrap();
-    let comparison_result = assert_json_eq_no_panic(
-        &serde_json::from_str(&serialized_str)?,
-        &serde_json::from_str(&file_str)?,
-    );
+    let fields = unknown_fields(&package, &file_str, strict)?;
+    report_unknown_fields(&mut report, package_file.to_str().unwrap(), fields);
 
-    if let Err(err_msg) = comparison_result {
-        return Err(Error::new(err_msg));
-    }
-
-    Ok(GoogleDialogflowAgent::new(
+    let mut agent = GoogleDialogflowAgent::new(
         entities,
         entity_entries,
         intents,
         utterances,
         agent_manifest,
         package,
-    ))
+    );
+    agent.report = report;
+    Ok(agent)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::google::dialogflow::limits::Abbreviations;
+    use crate::google::dialogflow::media::MediaUrls;
+    use crate::google::dialogflow::options::{
+        ClonedMetadata, EntityValues, Platforms, SynonymsNormalization,
+    };
     use crate::google::dialogflow::responses::normalize_json;
+    use crate::google::dialogflow::url_rewrites::UrlRewrites;
     use crate::google::gcloud::translate::dummy_translate;
     use crate::init_logging;
+    use crate::placeholders;
     use crate::translation_tests_assertions;
-    use assert_json_diff::assert_json_eq; // set RUST_LOG=gdf_translate::google::dialogflow::agent=debug
+    use assert_json_diff::{assert_json_eq, assert_json_eq_no_panic}; // set RUST_LOG=gdf_translate::google::dialogflow::agent=debug
+    use std::env;
 
     const SAMPLE_AGENTS_FOLDER: &str =
         "C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/sample_agents/";
@@ -595,6 +934,41 @@ mod tests {
     const SAMPLE_SENSITIVE_AGENTS_FOLDER: &str =
         "C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/testdata/agents/";
 
+    const SMART_HOME_AGENT: &str = "./examples/sample_agents/Smart-Home.zip";
+
+    /// Smart-Home sample agent, starting point of the translation tests
+    fn smart_home_agent() -> Result<GoogleDialogflowAgent> {
+        parse_gdf_agent_zip(SMART_HOME_AGENT, false)
+    }
+
+    /// Smart-Home sample agent whose first intent responds with given messages (JSON array)
+    fn smart_home_agent_responding(messages: &str) -> Result<GoogleDialogflowAgent> {
+        let mut agent = smart_home_agent()?;
+        agent.intents[0].file_content.responses[0].messages = serde_json::from_str(messages)?;
+        Ok(agent)
+    }
+
+    /// translates responses of the agent from English to German, `translate` stands in for translation API
+    fn translate_responses(
+        agent: &mut GoogleDialogflowAgent,
+        options: &AgentOptions,
+        translate: impl FnOnce(&mut collections::HashMap<String, String>),
+    ) {
+        let mut translation_map = agent.to_translation("en", "de", true, true, false, options);
+        translate(&mut translation_map);
+        agent.from_translation(&translation_map, "de", true, true, false, options);
+    }
+
+    /// messages of the first intent cloned to German
+    fn german_messages(agent: &GoogleDialogflowAgent) -> Vec<serde_json::Value> {
+        agent.intents[0].file_content.responses[0]
+            .messages
+            .iter()
+            .filter(|message| message.get_message_lang() == "de")
+            .map(|message| serde_json::to_value(message).unwrap())
+            .collect()
+    }
+
     #[derive(Debug)]
     struct DummyStructSlave {
         pub foo: String,
@@ -1235,6 +1609,7 @@ mod tests {
         let entity_entry = EntityEntry {
             value: "back".to_owned(),
             synonyms: vec!["rear".to_owned(), "tail end".to_owned()],
+            extra: UnknownFields::default(),
         };
 
         let entity_entries_file = EntityEntriesFile::new(
@@ -1249,6 +1624,71 @@ mod tests {
         assert_eq!(cloned, entity_entries_file_expected);
     }
 
+    // cargo test -- --show-output test_utterances_file_to_new_language
+    #[test]
+    fn test_utterances_file_to_new_language() -> Result<()> {
+        let utterances_str = r#"
+        [
+          {
+            "id": "9dfa147d-d2d8-4703-a693-8edef11322a2",
+            "data": [{ "text": "where is my order", "userDefined": false }],
+            "isTemplate": false,
+            "count": 3,
+            "lang": "en",
+            "updated": 1591000000
+          }
+        ]
+        "#;
+        let utterances_file = IntentUtterancesFile::new(
+            "order status_usersays_en.json".to_owned(),
+            serde_json::from_str(utterances_str)?,
+        );
+
+        let metadata = ClonedMetadata {
+            updated: Some(1600000000),
+            ..Default::default()
+        };
+        let cloned = utterances_file.to_new_language("de", &metadata);
+        assert_eq!(cloned.file_name, "order status_usersays_de.json");
+        let utterance = &cloned.file_content[0];
+        assert_ne!(utterance.id, utterances_file.file_content[0].id);
+        assert_eq!(utterance.id.as_ref().unwrap().len(), 36);
+        assert_eq!(utterance.count, 0);
+        assert_eq!(utterance.updated, 1600000000);
+        assert_eq!(utterance.lang.as_deref(), Some("de"));
+
+        // original behaviour, cloned utterances are reported as duplicated
+        let metadata = ClonedMetadata {
+            keep: true,
+            ..Default::default()
+        };
+        let cloned = utterances_file.to_new_language("de", &metadata);
+        assert_eq!(
+            cloned.file_content[0].id,
+            utterances_file.file_content[0].id
+        );
+        assert_eq!(cloned.file_content[0].count, 3);
+        assert_eq!(cloned.file_content[0].updated, 1591000000);
+
+        let mut agent = smart_home_agent()?;
+        let options = AgentOptions {
+            cloned_metadata: metadata,
+            ..Default::default()
+        };
+        agent.to_translation("en", "de", true, false, true, &options);
+        let report = agent.take_report();
+        assert!(!report.is_empty());
+        assert!(report.iter().all(|item| item.category == "duplicated_id"));
+        assert!(report
+            .iter()
+            .all(|item| item.message.ends_with("is used 2 times")));
+
+        let mut agent = smart_home_agent()?;
+        agent.to_translation("en", "de", true, false, true, &AgentOptions::default());
+        assert!(agent.take_report().is_empty());
+        Ok(())
+    }
+
     // cargo test -- --show-output test_translation_mechanics
     #[test]
     fn test_translation_mechanics() {
@@ -1333,6 +1773,675 @@ mod tests {
         Ok(())
     }
 
+    // cargo test -- --show-output test_translate_composite_entity_entry
+    #[test]
+    fn test_translate_composite_entity_entry() -> Result<()> {
+        let mut entity_entry = EntityEntry {
+            value: "@sys.number:amount @currency:cur per month".to_owned(),
+            synonyms: vec![
+                "@sys.number:amount @currency:cur per month".to_owned(),
+                "@sys.number:amount @currency:cur monthly".to_owned(),
+                "@sys.number:amount @currency:cur".to_owned(),
+            ],
+            extra: UnknownFields::default(),
+        };
+        assert!(entity_entry.is_composite());
+
+        let mut translations_map = entity_entry.composite_to_translation();
+        // last synonym contains entity references only
+        assert_eq!(translations_map.len(), 3);
+        assert_eq!(
+            translations_map[&format!("{:p}", &entity_entry.value)],
+            r#"<span translate="no">0</span> <span translate="no">1</span> per month"#
+        );
+
+        dummy_translate(&mut translations_map);
+        // translation lost one of the references
+        translations_map.insert(
+            format!("{:p}", &entity_entry.synonyms[1]),
+            r#"<span translate="no">0</span> monatlich"#.to_owned(),
+        );
+        let problems = entity_entry.composite_apply_translation(&translations_map);
+        assert_eq!(
+            problems,
+            vec!["'@sys.number:amount @currency:cur monthly': @currency:cur is missing"]
+        );
+        assert_eq!(
+            entity_entry.value,
+            "@sys.number:amount @currency:cur per month_translated"
+        );
+        assert_eq!(
+            entity_entry.synonyms,
+            vec![
+                "@sys.number:amount @currency:cur per month_translated",
+                "@sys.number:amount @currency:cur monthly",
+                "@sys.number:amount @currency:cur"
+            ]
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_template_utterances
+    #[test]
+    fn test_translate_template_utterances() -> Result<()> {
+        let mut agent = smart_home_agent()?;
+        let utterances_file = agent
+            .utterances
+            .iter_mut()
+            .find(|file| file.file_name.ends_with("_usersays_en.json"))
+            .unwrap();
+        let templates_file_name = utterances_file.file_name.replace("_en.json", "_de.json");
+        for (utterance, text) in utterances_file.file_content.iter_mut().zip(&[
+            "remind me @sys.date:date at @sys.time:time please",
+            "switch on @device:device",
+        ]) {
+            utterance.is_template = true;
+            utterance.data = vec![IntentUtteranceData {
+                text: text.to_string(),
+                alias: None,
+                meta: None,
+                user_defined: false,
+                extra: UnknownFields::default(),
+            }];
+        }
+
+        let options = AgentOptions::default();
+        let mut translations_map = agent.to_translation("en", "de", true, false, true, &options);
+        let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+        assert!(texts.contains(
+            &r#"remind me <span translate="no">0</span> at <span translate="no">1</span> please"#
+        ));
+        assert!(!texts.contains(&"remind me @sys.date:date at @sys.time:time please"));
+
+        // dummy translation appends text right after the last token of the second template
+        dummy_translate(&mut translations_map);
+        agent.from_translation(&translations_map, "de", true, false, true, &options);
+
+        let templates = &agent
+            .utterances
+            .iter()
+            .find(|file| file.file_name == templates_file_name)
+            .unwrap()
+            .file_content;
+        assert_eq!(
+            templates[0].data[0].text,
+            "remind me @sys.date:date at @sys.time:time please_translated"
+        );
+        assert_eq!(templates[1].data[0].text, "switch on @device:device");
+
+        let report = agent.take_report();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].category, "template_utterance");
+        assert_eq!(report[0].file, templates_file_name);
+        assert_eq!(
+            report[0].message,
+            "'switch on @device:device': @device:device is merged with adjacent text, translation not applied"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_localize_media_urls
+    #[test]
+    fn test_translate_localize_media_urls() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              {
+                "type": "3",
+                "platform": "facebook",
+                "lang": "en",
+                "imageUrl": "https://cdn.example.com/en/banner.png"
+              },
+              {
+                "type": "basic_card",
+                "platform": "google",
+                "lang": "en",
+                "formattedText": "Smart home",
+                "image": { "url": "https://cdn.example.com/logo.png", "accessibilityText": "logo" }
+              }
+            ]
+            "#,
+        )?;
+        let intent_file_name = agent.intents[0].file_name.to_owned();
+
+        let options = AgentOptions {
+            media_urls: Some(MediaUrls::parse(
+                r#"{ "https://cdn.example.com/en/*": { "de": "https://cdn.example.com/de/*" } }"#,
+            )?),
+            ..Default::default()
+        };
+        agent.to_translation("en", "de", true, true, false, &options);
+
+        let cloned = german_messages(&agent);
+        assert!(cloned
+            .iter()
+            .any(|message| message["imageUrl"] == "https://cdn.example.com/de/banner.png"));
+        assert!(cloned
+            .iter()
+            .any(|message| message["image"]["url"] == "https://cdn.example.com/logo.png"));
+
+        let report = agent.take_report();
+        let media_report: Vec<&ReportItem> = report
+            .iter()
+            .filter(|item| item.category == "media_url")
+            .collect();
+        assert_eq!(media_report.len(), 1);
+        assert_eq!(media_report[0].file, intent_file_name);
+        assert_eq!(
+            media_report[0].message,
+            "no de URL for https://cdn.example.com/logo.png"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_rewrite_link_urls
+    #[test]
+    fn test_translate_rewrite_link_urls() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              {
+                "type": "link_out_chip",
+                "platform": "google",
+                "lang": "en",
+                "destinationName": "Help",
+                "url": "https://example.com/en-us/help"
+              },
+              {
+                "type": "basic_card",
+                "platform": "google",
+                "lang": "en",
+                "formattedText": "Smart home",
+                "buttons": [
+                  {
+                    "title": "Shop",
+                    "openUrlAction": { "url": "https://shop.example.com/?lang=en", "urlTypeHint": "URL_TYPE_HINT_UNSPECIFIED" }
+                  }
+                ]
+              },
+              {
+                "type": "1",
+                "platform": "facebook",
+                "lang": "en",
+                "title": "Lights",
+                "buttons": [
+                  { "text": "Manual", "postback": "https://docs.example.com/manual.pdf" },
+                  { "text": "Turn off", "postback": "turn off the lights" }
+                ]
+              }
+            ]
+            "#,
+        )?;
+
+        let options = AgentOptions {
+            url_rewrites: Some(UrlRewrites::parse(
+                r#"
+                {
+                  "de": [
+                    { "match": "/en-us/", "replace": "/de-de/" },
+                    { "match": "([?&]lang=)en\\b", "replace": "${1}de" }
+                  ]
+                }
+                "#,
+            )?),
+            ..Default::default()
+        };
+        agent.to_translation("en", "de", true, true, false, &options);
+
+        let cloned = german_messages(&agent);
+        assert!(cloned
+            .iter()
+            .any(|message| message["url"] == "https://example.com/de-de/help"));
+        assert!(cloned
+            .iter()
+            .any(|message| message["buttons"][0]["openUrlAction"]["url"]
+                == "https://shop.example.com/?lang=de"));
+        assert!(cloned
+            .iter()
+            .any(|message| message["buttons"][1]["postback"] == "turn off the lights"));
+
+        let url_report: Vec<String> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "url")
+            .map(|item| item.message)
+            .collect();
+        assert_eq!(
+            url_report,
+            vec![
+                "https://example.com/en-us/help rewritten to https://example.com/de-de/help",
+                "https://shop.example.com/?lang=en rewritten to https://shop.example.com/?lang=de",
+                "no de rewrite rule matches https://docs.example.com/manual.pdf",
+            ]
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_ssml_responses
+    #[test]
+    fn test_translate_ssml_responses() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              {
+                "type": "simple_response",
+                "platform": "google",
+                "lang": "en",
+                "items": [
+                  {
+                    "textToSpeech": "<speak>Lights are <emphasis>on</emphasis>.</speak>",
+                    "ssml": "",
+                    "displayText": "Lights are on."
+                  }
+                ]
+              },
+              {
+                "type": "telephony_synthesize_speech",
+                "platform": "telephony",
+                "lang": "en",
+                "ssml": "<speak>Lights are on.<break time=\"1s\"/>Anything else?</speak>"
+              }
+            ]
+            "#,
+        )?;
+        let intent_file_name = agent.intents[0].file_name.to_owned();
+
+        translate_responses(&mut agent, &AgentOptions::default(), |translations_map| {
+            let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+            assert!(texts.contains(&"Anything else?"));
+            assert!(texts.contains(
+                &r#"Lights are <span translate="no">0</span>on<span translate="no">1</span>."#
+            ));
+
+            // translation lost closing tag of emphasis
+            dummy_translate(translations_map);
+            for val in translations_map.values_mut() {
+                *val = val.replace(r#"<span translate="no">1</span>"#, "");
+            }
+        });
+
+        let cloned = german_messages(&agent);
+        assert!(cloned
+            .iter()
+            .any(|message| message["items"][0]["textToSpeech"]
+                == "<speak>Lights are <emphasis>on</emphasis>.</speak>"));
+        assert!(cloned.iter().any(|message| message["ssml"]
+            == r#"<speak>Lights are on._translated<break time="1s"/>Anything else?_translated</speak>"#));
+
+        let report = agent.take_report();
+        let ssml_report: Vec<&ReportItem> = report
+            .iter()
+            .filter(|item| item.category == "ssml")
+            .collect();
+        assert_eq!(ssml_report.len(), 1);
+        assert_eq!(ssml_report[0].file, intent_file_name);
+        assert_eq!(
+            ssml_report[0].message,
+            "SSML element </emphasis> is missing, translation not applied"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_selected_platforms
+    #[test]
+    fn test_translate_selected_platforms() -> Result<()> {
+        let translate = |copy_skipped: bool| -> Result<Vec<serde_json::Value>> {
+            let mut agent = smart_home_agent_responding(
+                r#"
+                [
+                  { "type": "0", "lang": "en", "speech": "Lights are on." },
+                  { "type": "0", "platform": "facebook", "lang": "en", "speech": "Lights on!" },
+                  { "type": "0", "platform": "slack", "lang": "en", "speech": "Lights are on :bulb:" }
+                ]
+                "#,
+            )?;
+            let options = AgentOptions {
+                platforms: Platforms::new(&["default", "Facebook"], &[], copy_skipped),
+                ..Default::default()
+            };
+            translate_responses(&mut agent, &options, |translation_map| {
+                let texts: Vec<&str> = translation_map.values().map(|val| val.as_str()).collect();
+                assert!(texts.contains(&"Lights on!"));
+                assert!(!texts.contains(&"Lights are on :bulb:"));
+                dummy_translate(translation_map);
+            });
+            Ok(german_messages(&agent))
+        };
+
+        let cloned = translate(false)?;
+        assert_eq!(cloned.len(), 2);
+        assert_eq!(cloned[0]["speech"], "Lights are on._translated");
+        assert_eq!(cloned[1]["platform"], "facebook");
+        assert_eq!(cloned[1]["speech"], "Lights on!_translated");
+
+        let cloned = translate(true)?;
+        assert_eq!(cloned.len(), 3);
+        assert_eq!(cloned[2]["platform"], "slack");
+        assert_eq!(cloned[2]["speech"], "Lights are on :bulb:");
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_length_limits
+    #[test]
+    fn test_translate_length_limits() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              {
+                "type": "suggestion_chips",
+                "platform": "google",
+                "lang": "en",
+                "suggestions": [
+                  { "title": "Track parcel" },
+                  { "title": "Yes" },
+                  { "title": "Delivery details" }
+                ]
+              },
+              {
+                "type": "2",
+                "platform": "facebook",
+                "lang": "en",
+                "title": "What next?",
+                "replies": [ "Show more information" ]
+              }
+            ]
+            "#,
+        )?;
+
+        let options = AgentOptions {
+            abbreviations: Some(Abbreviations::parse(
+                r#"{ "de": { "Sendungsverfolgung": "Tracking", "Informationen": "Infos" } }"#,
+            )?),
+            ..Default::default()
+        };
+        translate_responses(&mut agent, &options, |translation_map| {
+            for val in translation_map.values_mut() {
+                *val = match val.as_str() {
+                    "Track parcel" => "Sendungsverfolgung starten".to_owned(),
+                    "Yes" => "Ja".to_owned(),
+                    "Delivery details" => "Weitere Informationen zur Lieferung".to_owned(),
+                    "What next?" => "Wie weiter?".to_owned(),
+                    "Show more information" => "Weitere Informationen anzeigen".to_owned(),
+                    _ => val.to_owned(),
+                };
+            }
+        });
+
+        let cloned = german_messages(&agent);
+        assert_eq!(cloned[0]["suggestions"][0]["title"], "Tracking starten");
+        assert_eq!(cloned[0]["suggestions"][1]["title"], "Ja");
+        assert_eq!(
+            cloned[0]["suggestions"][2]["title"],
+            "Weitere Informationen zur Lieferung"
+        );
+        assert_eq!(cloned[1]["replies"][0], "Weitere Informationen anzeigen");
+
+        let limit_report: Vec<String> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "length_limit")
+            .map(|item| item.message)
+            .collect();
+        assert_eq!(
+            limit_report,
+            vec![
+                "google suggestion_chip \"Sendungsverfolgung starten\" shortened to \"Tracking starten\", 25 characters max",
+                "google suggestion_chip \"Weitere Informationen zur Lieferung\" has 35 characters, 25 characters max",
+                "facebook quick_reply \"Weitere Informationen anzeigen\" has 30 characters, 20 characters max",
+            ]
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_truncated_quick_replies
+    #[test]
+    fn test_translate_truncated_quick_replies() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              {
+                "type": "2",
+                "platform": "facebook",
+                "lang": "en",
+                "title": "What next?",
+                "replies": [ "Show more information", "Track parcel" ]
+              }
+            ]
+            "#,
+        )?;
+
+        let options = AgentOptions {
+            abbreviations: Some(Abbreviations::parse(
+                r#"{ "de": { "Sendungsverfolgung": "Tracking" } }"#,
+            )?),
+            truncate_quick_replies: true,
+            ..Default::default()
+        };
+        translate_responses(&mut agent, &options, |translation_map| {
+            for val in translation_map.values_mut() {
+                *val = match val.as_str() {
+                    "Show more information" => "Weitere Informationen anzeigen".to_owned(),
+                    "Track parcel" => "Sendungsverfolgung starten".to_owned(),
+                    _ => val.to_owned(),
+                };
+            }
+        });
+
+        let cloned = german_messages(&agent);
+        assert_eq!(cloned[0]["replies"][0], "Weitere");
+        assert_eq!(cloned[0]["replies"][1], "Tracking starten");
+
+        let limit_report: Vec<String> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "length_limit")
+            .map(|item| item.message)
+            .collect();
+        assert_eq!(
+            limit_report,
+            vec![
+                "facebook quick_reply \"Weitere Informationen anzeigen\" truncated to \"Weitere\", 20 characters max",
+                "facebook quick_reply \"Sendungsverfolgung starten\" shortened to \"Tracking starten\", 20 characters max",
+            ]
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_lost_reference
+    #[test]
+    fn test_translate_lost_reference() -> Result<()> {
+        let mut agent = smart_home_agent_responding(
+            r#"
+            [
+              { "type": "0", "lang": "en", "speech": "Lights in $room are on." },
+              { "type": "0", "lang": "en", "speech": "Anything else?" }
+            ]
+            "#,
+        )?;
+
+        translate_responses(&mut agent, &AgentOptions::default(), |translation_map| {
+            let references = placeholders::protect_references(translation_map);
+            dummy_translate(translation_map);
+            // translation lost the placeholder of $room
+            for val in translation_map.values_mut() {
+                *val = val.replace(r#"<span translate="no">0</span>"#, "");
+            }
+            let issues = placeholders::restore_references(translation_map, &references);
+            assert_eq!(
+                issues[0].1,
+                placeholders::PlaceholderIssue::Missing("$room".to_owned())
+            );
+        });
+
+        let cloned = german_messages(&agent);
+        // text with lost reference is left untranslated
+        assert_eq!(cloned[0]["speech"], "Lights in $room are on.");
+        assert_eq!(cloned[1]["speech"], "Anything else?_translated");
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_conditional_responses
+    #[test]
+    fn test_translate_conditional_responses() -> Result<()> {
+        let mut agent = smart_home_agent()?;
+        agent.intents[0].file_content.conditional_responses = serde_json::from_str(
+            r#"
+            [
+              {
+                "condition": "$room = \"kitchen\"",
+                "messages": [
+                  { "type": "0", "lang": "en", "speech": "Kitchen lights are on." }
+                ]
+              }
+            ]
+            "#,
+        )?;
+
+        translate_responses(&mut agent, &AgentOptions::default(), |translation_map| {
+            assert!(translation_map
+                .values()
+                .any(|val| val == "Kitchen lights are on."));
+            dummy_translate(translation_map);
+        });
+
+        let conditional_response = &agent.intents[0].file_content.conditional_responses[0];
+        assert_eq!(conditional_response.condition, "$room = \"kitchen\"");
+        assert_eq!(
+            serde_json::to_value(&conditional_response.messages)?,
+            serde_json::json!([
+                { "type": "0", "lang": "en", "speech": "Kitchen lights are on." },
+                { "type": "0", "lang": "de", "speech": "Kitchen lights are on._translated" }
+            ])
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_keep_entity_values
+    #[test]
+    fn test_translate_keep_entity_values() -> Result<()> {
+        let mut agent = smart_home_agent()?;
+        let options = AgentOptions {
+            entity_values: EntityValues::parse(&["keep", "room=translate"])?,
+            ..Default::default()
+        };
+        // lights as list entity, its only synonym is the value
+        for entity_file in agent.entities.iter_mut() {
+            if entity_file.file_content.name == "lights" {
+                entity_file.file_content.is_enum = true;
+            }
+        }
+        for entries_file in agent.entity_entries.iter_mut() {
+            if entries_file.file_name.ends_with("lights_entries_en.json") {
+                for entry in entries_file.file_content.iter_mut() {
+                    entry.synonyms = vec![entry.value.to_owned()];
+                }
+            }
+        }
+        let mut translation_map = agent.to_translation("en", "de", false, true, true, &options);
+        dummy_translate(&mut translation_map);
+        agent.from_translation(&translation_map, "de", false, true, true, &options);
+
+        let entries = |file_name: &str| -> Vec<EntityEntry> {
+            agent
+                .entity_entries
+                .iter()
+                .find(|entries_file| {
+                    Path::new(&entries_file.file_name).file_name().unwrap() == file_name
+                })
+                .unwrap()
+                .file_content
+                .clone()
+        };
+
+        let color = entries("color_entries_de.json");
+        assert_eq!(color[0].value, "blue");
+        // source language value must stay synonym of map entity
+        assert_eq!(
+            color[0].synonyms,
+            vec![
+                "blue",
+                "blue_translated",
+                "sleep_translated",
+                "sleeping_translated"
+            ]
+        );
+
+        // list entity keeping values is not translated at all
+        for entry in entries("lights_entries_de.json").iter() {
+            assert_eq!(entry.synonyms, vec![entry.value.to_owned()]);
+            assert!(!entry.value.ends_with("_translated"));
+        }
+
+        let room = entries("room_entries_de.json");
+        assert!(room[0].value.ends_with("_translated"));
+
+        // source language files are untouched
+        assert_eq!(entries("color_entries_en.json")[0].synonyms[0], "blue");
+
+        let mut list_entry = EntityEntry {
+            value: "red".to_owned(),
+            synonyms: vec!["rot".to_owned()],
+            extra: UnknownFields::default(),
+        };
+        // single synonym of list entity equals the value
+        list_entry.adjust_synonyms_to_kept_value(true);
+        assert_eq!(list_entry.synonyms, vec!["red"]);
+        list_entry.synonyms = vec!["rot".to_owned()];
+        list_entry.adjust_synonyms_to_kept_value(false);
+        assert_eq!(list_entry.synonyms, vec!["red", "rot"]);
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_normalize_synonyms
+    #[test]
+    fn test_translate_normalize_synonyms() -> Result<()> {
+        let mut agent = smart_home_agent()?;
+        let options = AgentOptions {
+            synonyms_normalization: Some(SynonymsNormalization::default()),
+            ..Default::default()
+        };
+        let mut translation_map = agent.to_translation("en", "de", false, true, true, &options);
+        for text in translation_map.values_mut() {
+            *text = match text.as_str() {
+                "blue" => "blau".to_owned(),
+                "sleep" | "sleeping" => "Schlaf.".to_owned(),
+                "party" => "Blau!".to_owned(),
+                _ => format!("{}_translated", text),
+            };
+        }
+        agent.from_translation(&translation_map, "de", false, true, true, &options);
+
+        let color = agent
+            .entity_entries
+            .iter()
+            .find(|entries_file| {
+                Path::new(&entries_file.file_name).file_name().unwrap() == "color_entries_de.json"
+            })
+            .unwrap();
+        assert_eq!(color.file_content[0].synonyms, vec!["blau", "Schlaf"]);
+        assert_eq!(color.file_content[2].value, "green_translated");
+        assert_eq!(color.file_content[2].synonyms, vec!["green_translated"]);
+
+        let color_file_name = color.file_name.to_owned();
+        let changes: Vec<String> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "synonyms" && item.file == color_file_name)
+            .map(|item| item.message)
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                "'blau': synonym 'Schlaf.' changed to 'Schlaf'",
+                "'blau': synonym 'Schlaf.' changed to 'Schlaf'",
+                "'blau': duplicated synonym 'Schlaf' removed",
+                "'green_translated': synonym 'Blau!' changed to 'Blau'",
+                "'green_translated': synonym 'Blau' removed, it is used by 'blau'",
+            ]
+        );
+        Ok(())
+    }
+
     // cargo test -- --show-output test_translate_intent_utternce
     #[test]
     fn test_translate_intent_utternce() -> Result<()> {
@@ -1384,6 +2493,63 @@ mod tests {
         Ok(())
     }
 
+    // cargo test -- --show-output test_translate_parameter_default_value
+    #[test]
+    fn test_translate_parameter_default_value() -> Result<()> {
+        let mut agent = smart_home_agent()?;
+        let options = AgentOptions::default();
+        for lang_to in &["de", "fr"] {
+            let mut translation_map =
+                agent.to_translation("en", lang_to, true, true, false, &options);
+            dummy_translate(&mut translation_map);
+            agent.from_translation(&translation_map, lang_to, true, true, false, &options);
+        }
+
+        let intent_file = agent
+            .intents
+            .iter()
+            .find(|intent_file| intent_file.file_name.ends_with("smarthome.locks.open.json"))
+            .unwrap();
+        let intent_file_name = intent_file.file_name.to_owned();
+        let parameter = serde_json::to_value(&intent_file.file_content.responses[0].parameters[0])?;
+        // default value is shared by all languages, source value survives both target languages
+        assert_eq!(parameter["defaultValue"], "door");
+
+        let default_value_report: Vec<ReportItem> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "default_value" && item.file == intent_file_name)
+            .collect();
+        // reported once per target language
+        assert_eq!(default_value_report.len(), 2);
+        assert_eq!(
+            default_value_report[0].message,
+            "default value door of parameter lock is shared by all languages, not translated"
+        );
+
+        // on demand default value is translated in place and not reported
+        let mut agent = smart_home_agent()?;
+        let options = AgentOptions {
+            translate_default_values: true,
+            ..Default::default()
+        };
+        let mut translation_map = agent.to_translation("en", "de", true, true, false, &options);
+        dummy_translate(&mut translation_map);
+        agent.from_translation(&translation_map, "de", true, true, false, &options);
+        let intent_file = agent
+            .intents
+            .iter()
+            .find(|intent_file| intent_file.file_name.ends_with("smarthome.locks.open.json"))
+            .unwrap();
+        let parameter = serde_json::to_value(&intent_file.file_content.responses[0].parameters[0])?;
+        assert_eq!(parameter["defaultValue"], "door_translated");
+        assert!(agent
+            .take_report()
+            .iter()
+            .all(|item| item.category != "default_value"));
+        Ok(())
+    }
+
     #[test]
     fn test_entity_entry_file_name_to_entity_filename() {
         assert_eq!(
@@ -1400,6 +2566,125 @@ mod tests {
         );
     }
 
+    // cargo test -- --show-output test_translate_sample_agents_round_trip
+    #[test]
+    fn test_translate_sample_agents_round_trip() -> Result<()> {
+        for agent_zip in &["Smart-Home.zip", "Coffee-Shop.zip", "Reminders.zip"] {
+            let path = format!("./examples/sample_agents/{}", agent_zip);
+            let mut agent = parse_gdf_agent_zip(&path, true)?;
+            let mut translation_map =
+                agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
+            dummy_translate(&mut translation_map);
+            agent.from_translation(
+                &translation_map,
+                "de",
+                false,
+                false,
+                false,
+                &AgentOptions::default(),
+            );
+            agent.add_supported_language("de", &AgentOptions::default());
+
+            let folder =
+                env::temp_dir().join(format!("gdf_round_trip_{}_{}", agent_zip, process::id()));
+            agent.serialize(folder.to_str().unwrap())?;
+
+            // translated agent passes the same lossless round trip check
+            let translated_zip = folder.join("_packed").join("TranslatedAgent.zip");
+            let translated_agent = parse_gdf_agent_zip(translated_zip.to_str().unwrap(), true)?;
+
+            let prompts: Vec<&IntentResponseParameter> = translated_agent
+                .intents
+                .iter()
+                .flat_map(|intent| intent.file_content.responses.iter())
+                .flat_map(|response| response.parameters.iter())
+                .collect();
+            let prompts_str = serde_json::to_string(&prompts)?;
+            assert!(prompts_str.contains(r#"{"lang":"de","value":"#));
+            assert!(prompts_str.contains(r#"_translated"}"#));
+            fs::remove_dir_all(&folder)?;
+        }
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_parse_unknown_fields
+    #[test]
+    fn test_parse_unknown_fields() -> Result<()> {
+        // add fields unknown to gdf_translate to exported agent
+        let folder = unzip_agent_to_tmp_folder(SMART_HOME_AGENT)?;
+        let agent_file = folder.join("agent.json");
+        let mut agent_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&agent_file)?)?;
+        agent_json["newAgentField"] = serde_json::json!({ "enabled": true });
+        fs::write(&agent_file, serde_json::to_string_pretty(&agent_json)?)?;
+
+        let intent_file = folder
+            .join("intents")
+            .join("smarthome.lights.switch.on.json");
+        let mut intent_json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&intent_file)?)?;
+        intent_json["newIntentField"] = serde_json::json!("value");
+        intent_json["responses"][0]["messages"][0]["newMessageField"] = serde_json::json!([1, 2]);
+        fs::write(&intent_file, serde_json::to_string_pretty(&intent_json)?)?;
+
+        let zip_path = env::temp_dir().join(format!("gdf_unknown_fields_{}.zip", process::id()));
+        zip_directory(folder.to_str().unwrap(), zip_path.to_str().unwrap())?;
+        fs::remove_dir_all(&folder)?;
+
+        let unknown_fields = |agent: &mut GoogleDialogflowAgent| -> Vec<String> {
+            let mut fields: Vec<String> = agent
+                .take_report()
+                .into_iter()
+                .filter(|item| item.category == "unknown_field")
+                .map(|item| item.message)
+                .collect();
+            fields.sort();
+            fields
+        };
+
+        assert!(parse_gdf_agent_zip(zip_path.to_str().unwrap(), true).is_err());
+        let mut agent = parse_gdf_agent_zip(zip_path.to_str().unwrap(), false)?;
+        assert_eq!(
+            unknown_fields(&mut agent),
+            vec![
+                "unknown field newAgentField is copied unchanged",
+                "unknown field newIntentField is copied unchanged",
+                "unknown field responses[0].messages[0].newMessageField is copied unchanged",
+            ]
+        );
+
+        // unknown fields are serialized back unchanged, also into cloned messages
+        let mut translation_map =
+            agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
+        dummy_translate(&mut translation_map);
+        agent.from_translation(
+            &translation_map,
+            "de",
+            false,
+            false,
+            false,
+            &AgentOptions::default(),
+        );
+        let target_folder = env::temp_dir().join(format!("gdf_unknown_fields_{}", process::id()));
+        agent.serialize(target_folder.to_str().unwrap())?;
+        let translated_zip = target_folder.join("_packed").join("TranslatedAgent.zip");
+        let mut translated_agent = parse_gdf_agent_zip(translated_zip.to_str().unwrap(), false)?;
+        let fields = unknown_fields(&mut translated_agent);
+        assert!(fields.contains(&"unknown field newAgentField is copied unchanged".to_owned()));
+        assert!(fields.contains(&"unknown field newIntentField is copied unchanged".to_owned()));
+        assert_eq!(
+            fields
+                .iter()
+                .filter(|field| field.contains(".newMessageField "))
+                .count(),
+            2
+        );
+
+        fs::remove_file(&zip_path)?;
+        fs::remove_dir_all(&target_folder)?;
+        Ok(())
+    }
+
     //
     // integration tests
     //
@@ -1424,9 +2709,9 @@ mod tests {
         init_logging();
         // let path = format!("c:/tmp/Express_CS_AM_PRD.zip");
         let path = format!("{}{}", SAMPLE_AGENTS_FOLDER, "FAQ.zip");
-        let mut agent = parse_gdf_agent_zip(&path)?;
+        let mut agent = parse_gdf_agent_zip(&path, false)?;
         println!("{:#?}", agent);
-        let map = agent.to_translation("en", "de", false, false, false);
+        let map = agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
         println!("{:#?}", map);
         Ok(())
     }
@@ -1436,14 +2721,22 @@ mod tests {
     #[ignore]
     fn test_dummy_translate_agent() -> Result<()> {
         let path = format!("{}{}", SAMPLE_AGENTS_FOLDER, "FAQ.zip");
-        let mut agent = parse_gdf_agent_zip(&path)?;
+        let mut agent = parse_gdf_agent_zip(&path, false)?;
         println!("agent before{:#?}", agent);
-        let mut translation_map = agent.to_translation("en", "de", false, false, false);
+        let mut translation_map =
+            agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
         println!("translation_map before{:#?}", translation_map);
         dummy_translate(&mut translation_map);
         println!("translation_map after{:#?}", translation_map);
 
-        agent.from_translation(&translation_map, "de", false, false, false);
+        agent.from_translation(
+            &translation_map,
+            "de",
+            false,
+            false,
+            false,
+            &AgentOptions::default(),
+        );
         println!("agent after{:#?}", agent);
         Ok(())
     }
@@ -1453,7 +2746,7 @@ mod tests {
     #[ignore]
     fn test_serialize_agent() -> Result<()> {
         let path = format!("{}{}", SAMPLE_AGENTS_FOLDER, "FAQ.zip");
-        let agent = parse_gdf_agent_zip(&path)?;
+        let agent = parse_gdf_agent_zip(&path, false)?;
         agent.serialize("c:/tmp/out")?;
         Ok(())
     }
@@ -1531,12 +2824,20 @@ mod tests {
 
             let path = format!("{}{}", SAMPLE_AGENTS_FOLDER, agent_zip);
             debug!("processing agent {}", agent_zip);
-            let mut agent = parse_gdf_agent_zip(&path)?;
-            let mut translation_map = agent.to_translation("en", "de", false, false, false);
+            let mut agent = parse_gdf_agent_zip(&path, false)?;
+            let mut translation_map =
+                agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
             // println!("translation_map before{:#?}", translation_map);
             dummy_translate(&mut translation_map);
             // println!("translation_map after{:#?}", translation_map);
-            agent.from_translation(&translation_map, "de", false, false, false);
+            agent.from_translation(
+                &translation_map,
+                "de",
+                false,
+                false,
+                false,
+                &AgentOptions::default(),
+            );
             // println!("agent after{:#?}", agent);
             agent.serialize(agent_output)?;
         }
@@ -1565,12 +2866,20 @@ mod tests {
 
             let path = format!("{}{}", SAMPLE_SENSITIVE_AGENTS_FOLDER, agent_zip);
             debug!("processing agent {}", agent_zip);
-            let mut agent = parse_gdf_agent_zip(&path)?;
-            let mut translation_map = agent.to_translation("en", "de", false, false, false);
+            let mut agent = parse_gdf_agent_zip(&path, false)?;
+            let mut translation_map =
+                agent.to_translation("en", "de", false, false, false, &AgentOptions::default());
             // println!("translation_map before{:#?}", translation_map);
             dummy_translate(&mut translation_map);
             // println!("translation_map after{:#?}", translation_map);
-            agent.from_translation(&translation_map, "de", false, false, false);
+            agent.from_translation(
+                &translation_map,
+                "de",
+                false,
+                false,
+                false,
+                &AgentOptions::default(),
+            );
             // println!("agent after{:#?}", agent);
             agent.serialize(agent_output)?;
         }
@@ -1,4 +1,11 @@
-use crate::google::dialogflow::agent::{Translate, RE_ENTITY_ENTRY_FILE};
+use crate::google::dialogflow::agent::{
+    Translate, RE_COMPOSITE_ENTITY, RE_COMPOSITE_ENTITY_NO_ALIAS, RE_ENTITY_ENTRY_FILE,
+};
+use crate::google::dialogflow::synonyms::SynonymsEntry;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::placeholders::{
+    protect_for_translation, protected_from_translation, RE_ENTITY_REFERENCE,
+};
 use regex::Captures;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -23,12 +30,65 @@ pub struct Entity {
 
     #[serde(rename = "allowFuzzyExtraction")]
     pub allow_fuzzy_extraction: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct EntityEntry {
     pub value: String,
     pub synonyms: Vec<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl EntityEntry {
+    /// values of composite entities reference other entities, e.g. @sys.number:amount @currency:cur per month
+    pub fn is_composite(&self) -> bool {
+        RE_COMPOSITE_ENTITY.is_match(&self.value)
+            || RE_COMPOSITE_ENTITY_NO_ALIAS.is_match(&self.value)
+    }
+
+    /// synonyms of entry whose reference value was kept in source language. List (enum) entity
+    /// has the value as its only synonym, value of map entity must be one of its synonyms.
+    pub fn adjust_synonyms_to_kept_value(&mut self, is_enum: bool) {
+        if is_enum {
+            self.synonyms = vec![self.value.to_owned()];
+        } else if !self.synonyms.contains(&self.value) {
+            self.synonyms.insert(0, self.value.to_owned());
+        }
+    }
+
+    /// translation map of composite entity entry, entity references are protected by placeholders
+    pub fn composite_to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for text in std::iter::once(&self.value).chain(self.synonyms.iter()) {
+            if let Some(protected) = protect_for_translation(text, &RE_ENTITY_REFERENCE) {
+                map_to_translate.insert(format!("{:p}", text), protected);
+            }
+        }
+
+        map_to_translate
+    }
+
+    /// applies translation of composite entity entry. Texts which lost or duplicated
+    /// some entity reference stay untranslated, description of such problems is returned.
+    pub fn composite_apply_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut problems = vec![];
+
+        for text in std::iter::once(&mut self.value).chain(self.synonyms.iter_mut()) {
+            let original = text.to_owned();
+            for issue in protected_from_translation(text, &RE_ENTITY_REFERENCE, translations_map) {
+                problems.push(format!("'{}': {}", original, issue));
+            }
+        }
+
+        problems
+    }
 }
 
 impl Translate for EntityEntry {
@@ -57,6 +117,28 @@ impl Translate for EntityEntry {
     }
 }
 
+impl SynonymsEntry for EntityEntry {
+    fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn value_mut(&mut self) -> &mut String {
+        &mut self.value
+    }
+
+    fn synonyms(&self) -> &Vec<String> {
+        &self.synonyms
+    }
+
+    fn synonyms_mut(&mut self) -> &mut Vec<String> {
+        &mut self.synonyms
+    }
+
+    fn is_composite(&self) -> bool {
+        EntityEntry::is_composite(self)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EntityFile {
     pub file_name: String,
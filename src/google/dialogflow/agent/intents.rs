@@ -1,11 +1,14 @@
 use crate::google::dialogflow::agent::Translate;
 use crate::google::dialogflow::responses::MessageType;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use serde::{Deserialize, Serialize};
 use std::collections;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IntentEvent {
     name: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -14,12 +17,26 @@ pub struct IntentResponseAffectedContext {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<collections::HashMap<String, String>>,
     pub lifespan: u16,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct IntentResponseParameterPrompt {
     lang: String,
     value: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl IntentResponseParameterPrompt {
+    fn new_prompt(&self, lang: &str) -> Self {
+        IntentResponseParameterPrompt {
+            lang: lang.to_owned(),
+            value: self.value.to_owned(),
+            extra: self.extra.clone(),
+        }
+    }
 }
 
 impl Translate for IntentResponseParameterPrompt {
@@ -30,13 +47,63 @@ impl Translate for IntentResponseParameterPrompt {
     }
 
     fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
-        self.value = translations_map
-            .get(&format!("{:p}", &self.value))
-            .unwrap()
-            .to_owned();
+        if let Some(value) = translations_map.get(&format!("{:p}", &self.value)) {
+            self.value = value.to_owned();
+        }
     }
 }
 
+/// item of promptMessages, noMatchPromptMessages and noInputPromptMessages.
+/// Plain strings carry no language (they belong to source language),
+/// target language variants are always added as {lang, value} objects
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+#[serde(untagged)]
+pub enum IntentResponseParameterPromptMessage {
+    Localized(IntentResponseParameterPrompt),
+    Text(String),
+}
+
+impl IntentResponseParameterPromptMessage {
+    fn is_lang(&self, lang: &str) -> bool {
+        match self {
+            IntentResponseParameterPromptMessage::Localized(prompt) => prompt.lang == lang,
+            IntentResponseParameterPromptMessage::Text(_) => false,
+        }
+    }
+}
+
+/// clones prompts in source language into target language variants.
+/// Nothing is added if target language variants already exist.
+fn add_prompt_messages_language(
+    prompt_messages: &mut Vec<IntentResponseParameterPromptMessage>,
+    lang_from: &str,
+    lang_to: &str,
+) {
+    if prompt_messages.iter().any(|item| item.is_lang(lang_to)) {
+        return;
+    }
+
+    let new_prompt_messages: Vec<IntentResponseParameterPromptMessage> = prompt_messages
+        .iter()
+        .filter_map(|item| match item {
+            IntentResponseParameterPromptMessage::Localized(prompt) if prompt.lang == lang_from => {
+                Some(prompt.new_prompt(lang_to))
+            }
+            IntentResponseParameterPromptMessage::Localized(_) => None,
+            IntentResponseParameterPromptMessage::Text(text) => {
+                Some(IntentResponseParameterPrompt {
+                    lang: lang_to.to_owned(),
+                    value: text.to_owned(),
+                    extra: UnknownFields::default(),
+                })
+            }
+        })
+        .map(IntentResponseParameterPromptMessage::Localized)
+        .collect();
+
+    prompt_messages.extend(new_prompt_messages);
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IntentResponseParameter {
     id: String,
@@ -46,7 +113,7 @@ pub struct IntentResponseParameter {
     #[serde(rename = "dataType")]
     data_type: String,
 
-    name: String,
+    pub name: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     value: Option<String>,
@@ -55,47 +122,129 @@ pub struct IntentResponseParameter {
     prompts: Option<Vec<IntentResponseParameterPrompt>>,
 
     #[serde(rename = "promptMessages")]
-    prompt_messages: Vec<String>,
+    prompt_messages: Vec<IntentResponseParameterPromptMessage>,
 
     #[serde(rename = "noMatchPromptMessages")]
-    no_match_prompt_messages: Vec<String>,
+    no_match_prompt_messages: Vec<IntentResponseParameterPromptMessage>,
 
     #[serde(rename = "noInputPromptMessages")]
-    no_input_prompt_messages: Vec<String>,
+    no_input_prompt_messages: Vec<IntentResponseParameterPromptMessage>,
 
     #[serde(rename = "outputDialogContexts")]
     output_dialog_contexts: Vec<String>,
 
     // see Smart-Home example, intent smarthome.locks.open.json.
-    // Not sure hwo to set this up in DialogFlow UI and whether we  should actuallyt ranslate it
-    // For now just adding here so that we can properly deserialize and serialize back, not translating
+    // Single value shared by all languages, literal value is translated in place only on demand
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "defaultValue")]
     default_value: Option<String>,
 
     #[serde(rename = "isList")]
     is_list: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
-impl Translate for IntentResponseParameter {
-    fn to_translation(&self) -> collections::HashMap<String, String> {
+impl IntentResponseParameter {
+    /// literal default value, i.e. default value which is not empty and does not reference
+    /// other parameter / context, e.g. $name, #context.name
+    pub fn literal_default_value(&self) -> Option<&str> {
+        self.default_value.as_deref().filter(|default_value| {
+            let default_value = default_value.trim();
+            !default_value.is_empty()
+                && !default_value.starts_with('$')
+                && !default_value.starts_with('#')
+        })
+    }
+
+    /// adds target language variants of prompts and prompt messages, source language is kept untouched
+    pub fn add_language(&mut self, lang_from: &str, lang_to: &str) {
+        if let Some(prompts) = self.prompts.as_mut() {
+            if !prompts.iter().any(|prompt| prompt.lang == lang_to) {
+                let new_prompts: Vec<IntentResponseParameterPrompt> = prompts
+                    .iter()
+                    .filter(|prompt| prompt.lang == lang_from)
+                    .map(|prompt| prompt.new_prompt(lang_to))
+                    .collect();
+                prompts.extend(new_prompts);
+            }
+        }
+
+        add_prompt_messages_language(&mut self.prompt_messages, lang_from, lang_to);
+        add_prompt_messages_language(&mut self.no_match_prompt_messages, lang_from, lang_to);
+        add_prompt_messages_language(&mut self.no_input_prompt_messages, lang_from, lang_to);
+    }
+
+    /// translation map of target language prompts and (if requested) literal default value.
+    /// Default value is single value shared by all languages (export format has no language variants of it)
+    /// so translating it overwrites the value of source (and any other) language
+    pub fn collect_translation(
+        &self,
+        lang: &str,
+        translate_default_value: bool,
+    ) -> collections::HashMap<String, String> {
         let mut map_to_translate = collections::HashMap::new();
 
-        if let Some(prompts) = &self.prompts {
-            for prompt in prompts.iter() {
+        for prompt in self.prompts.iter().flatten() {
+            if prompt.lang == lang {
                 map_to_translate.extend(prompt.to_translation());
             }
         }
 
+        for prompt_message in self
+            .prompt_messages
+            .iter()
+            .chain(self.no_match_prompt_messages.iter())
+            .chain(self.no_input_prompt_messages.iter())
+        {
+            if let IntentResponseParameterPromptMessage::Localized(prompt) = prompt_message {
+                if prompt.lang == lang {
+                    map_to_translate.extend(prompt.to_translation());
+                }
+            }
+        }
+
+        if translate_default_value {
+            if let Some(default_value) = &self.default_value {
+                if self.literal_default_value().is_some() {
+                    map_to_translate
+                        .insert(format!("{:p}", default_value), default_value.to_owned());
+                }
+            }
+        }
+
         map_to_translate
     }
 
-    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
-        if let Some(prompts) = &mut self.prompts {
-            for prompt in prompts.iter_mut() {
+    pub fn apply_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+        lang: &str,
+    ) {
+        for prompt in self.prompts.iter_mut().flatten() {
+            if prompt.lang == lang {
                 prompt.from_translation(translations_map);
             }
         }
+
+        for prompt_message in self
+            .prompt_messages
+            .iter_mut()
+            .chain(self.no_match_prompt_messages.iter_mut())
+            .chain(self.no_input_prompt_messages.iter_mut())
+        {
+            if let IntentResponseParameterPromptMessage::Localized(prompt) = prompt_message {
+                if prompt.lang == lang {
+                    prompt.from_translation(translations_map);
+                }
+            }
+        }
+
+        if let Some(default_value) = self.default_value.as_mut() {
+            if let Some(translated) = translations_map.get(&format!("{:p}", default_value)) {
+                *default_value = translated.to_owned();
+            }
+        }
     }
 }
 
@@ -119,6 +268,46 @@ pub struct IntentResponse {
     pub default_response_platforms: Option<collections::HashMap<String, bool>>,
 
     pub speech: Vec<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+/// Response of the intent used when its condition is met.
+/// Condition expression is kept as it is, only messages are translated.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConditionalResponse {
+    #[serde(default)]
+    pub condition: String,
+
+    #[serde(default)]
+    pub messages: Vec<MessageType>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FollowupEvent {
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<collections::HashMap<String, serde_json::Value>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+/// follow-up event triggered when its condition is met, nothing to translate here
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConditionalFollowupEvent {
+    #[serde(default)]
+    pub condition: String,
+
+    pub event: FollowupEvent,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -155,14 +344,46 @@ pub struct Intent {
 
     pub events: Vec<IntentEvent>,
 
+    #[serde(default)]
     #[serde(rename = "conditionalResponses")]
-    pub conditional_responses: Vec<String>,
+    pub conditional_responses: Vec<ConditionalResponse>,
+    #[serde(default)]
     pub condition: String,
+    #[serde(default)]
     #[serde(rename = "conditionalFollowupEvents")]
-    pub conditional_followup_events: Vec<String>,
+    pub conditional_followup_events: Vec<ConditionalFollowupEvent>,
     #[serde(rename = "endInteraction")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub end_interaction: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Intent {
+    /// messages of all responses, including conditional responses
+    pub fn messages(&self) -> impl Iterator<Item = &MessageType> {
+        self.responses
+            .iter()
+            .flat_map(|response| response.messages.iter())
+            .chain(
+                self.conditional_responses
+                    .iter()
+                    .flat_map(|response| response.messages.iter()),
+            )
+    }
+
+    /// message lists of all responses, including conditional responses
+    pub fn message_lists_mut(&mut self) -> Vec<&mut Vec<MessageType>> {
+        self.responses
+            .iter_mut()
+            .map(|response| &mut response.messages)
+            .chain(
+                self.conditional_responses
+                    .iter_mut()
+                    .map(|response| &mut response.messages),
+            )
+            .collect()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -184,7 +405,88 @@ impl IntentFile {
 mod tests {
     use super::*;
     use crate::errors::Result;
+    use crate::google::gcloud::translate::dummy_translate;
     use assert_json_diff::assert_json_eq;
+    use serde_json::json;
+
+    // cargo test -- --show-output test_intent_parameter_add_language
+    #[test]
+    fn test_intent_parameter_add_language() -> Result<()> {
+        let parameter_str = r#"
+        {
+            "id": "4d76bedb-c481-41ff-9f02-be699a8bee84",
+            "required": true,
+            "dataType": "@lock",
+            "name": "lock",
+            "value": "$lock",
+            "prompts": [
+              { "lang": "en", "value": "Which lock?" },
+              { "lang": "fr", "value": "Quelle serrure ?" }
+            ],
+            "promptMessages": ["Which lock do you mean?"],
+            "noMatchPromptMessages": [{ "lang": "en", "value": "Sorry, which lock?" }],
+            "noInputPromptMessages": [],
+            "outputDialogContexts": [],
+            "defaultValue": "door",
+            "isList": false
+        }
+        "#;
+        let mut parameter: IntentResponseParameter = serde_json::from_str(parameter_str)?;
+        assert_json_eq!(
+            serde_json::to_value(&parameter)?,
+            serde_json::from_str::<serde_json::Value>(parameter_str)?
+        );
+
+        parameter.add_language("en", "de");
+        // target language variants exist already, nothing is added
+        parameter.add_language("en", "de");
+        let mut translations_map = parameter.collect_translation("de", false);
+        assert_eq!(translations_map.len(), 3);
+        dummy_translate(&mut translations_map);
+        parameter.apply_translation(&translations_map, "de");
+
+        assert_json_eq!(
+            serde_json::to_value(&parameter)?,
+            json!({
+                "id": "4d76bedb-c481-41ff-9f02-be699a8bee84",
+                "required": true,
+                "dataType": "@lock",
+                "name": "lock",
+                "value": "$lock",
+                "prompts": [
+                  { "lang": "en", "value": "Which lock?" },
+                  { "lang": "fr", "value": "Quelle serrure ?" },
+                  { "lang": "de", "value": "Which lock?_translated" }
+                ],
+                "promptMessages": [
+                  "Which lock do you mean?",
+                  { "lang": "de", "value": "Which lock do you mean?_translated" }
+                ],
+                "noMatchPromptMessages": [
+                  { "lang": "en", "value": "Sorry, which lock?" },
+                  { "lang": "de", "value": "Sorry, which lock?_translated" }
+                ],
+                "noInputPromptMessages": [],
+                "outputDialogContexts": [],
+                "defaultValue": "door",
+                "isList": false
+            })
+        );
+
+        // default value is shared by all languages, literal value is translated in place on demand
+        assert_eq!(parameter.literal_default_value(), Some("door"));
+        let mut translations_map = parameter.collect_translation("de", true);
+        assert_eq!(translations_map.len(), 4);
+        dummy_translate(&mut translations_map);
+        parameter.apply_translation(&translations_map, "de");
+        assert_eq!(parameter.default_value.as_deref(), Some("door_translated"));
+
+        // references to other parameters / contexts are not translated
+        parameter.default_value = Some("#translate-text.text".to_owned());
+        assert_eq!(parameter.literal_default_value(), None);
+        assert_eq!(parameter.collect_translation("de", true).len(), 3);
+        Ok(())
+    }
 
     //
     // use this test for ad hoc troubleshooting if it is not clear
@@ -263,4 +565,80 @@ mod tests {
 
         Ok(())
     }
+
+    // cargo test -- --show-output test_intent_conditional_responses
+    #[test]
+    fn test_intent_conditional_responses() -> Result<()> {
+        let intent_json = json!({
+            "id": "b7b8f695-9250-4831-8b7f-82948774d15b",
+            "name": "order.status",
+            "auto": true,
+            "contexts": [],
+            "responses": [
+              {
+                "resetContexts": false,
+                "affectedContexts": [],
+                "parameters": [],
+                "messages": [
+                  { "type": "0", "lang": "en", "speech": "Your order is on its way." }
+                ],
+                "speech": []
+              }
+            ],
+            "priority": 500000,
+            "webhookUsed": false,
+            "webhookForSlotFilling": false,
+            "fallbackIntent": false,
+            "events": [],
+            "conditionalResponses": [
+              {
+                "condition": "$status = \"delivered\" AND #order.paid",
+                "messages": [
+                  { "type": "0", "lang": "en", "speech": "Your order was delivered." }
+                ]
+              }
+            ],
+            "condition": "$status != \"\"",
+            "conditionalFollowupEvents": [
+              {
+                "condition": "$status = \"lost\"",
+                "event": { "name": "order_lost", "parameters": { "status": "$status" }, "languageCode": "en" }
+              }
+            ]
+        });
+
+        let mut intent: Intent = serde_json::from_value(intent_json.clone())?;
+        assert_eq!(intent.messages().count(), 2);
+        assert_eq!(
+            intent.conditional_responses[0].condition,
+            "$status = \"delivered\" AND #order.paid"
+        );
+        assert_eq!(
+            intent.conditional_followup_events[0].event.name,
+            "order_lost"
+        );
+        assert_json_eq!(serde_json::to_value(&intent)?, intent_json);
+
+        // messages cloned to target language are added to the response they were cloned from
+        for messages in intent.message_lists_mut() {
+            let cloned: Vec<MessageType> = messages
+                .iter()
+                .filter_map(|message| message.new_message("de"))
+                .collect();
+            messages.extend(cloned);
+        }
+        let langs: Vec<&str> = intent
+            .messages()
+            .map(|message| message.get_message_lang().as_str())
+            .collect();
+        assert_eq!(langs, vec!["en", "de", "en", "de"]);
+        assert_json_eq!(
+            serde_json::to_value(&intent.conditional_responses[0].messages)?,
+            json!([
+                { "type": "0", "lang": "en", "speech": "Your order was delivered." },
+                { "type": "0", "lang": "de", "speech": "Your order was delivered." }
+            ])
+        );
+        Ok(())
+    }
 }
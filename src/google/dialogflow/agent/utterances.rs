@@ -1,4 +1,10 @@
 use crate::google::dialogflow::agent::{Translate, RE_INTENT_UTTERANCE_FILE};
+use crate::google::dialogflow::ids::{new_uuid, now_epoch_secs};
+use crate::google::dialogflow::options::ClonedMetadata;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::placeholders::{
+    protect_for_translation, protected_from_translation, RE_ENTITY_REFERENCE,
+};
 use regex::Captures;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -12,6 +18,8 @@ pub struct IntentUtteranceData {
     pub meta: Option<String>,
     #[serde(rename = "userDefined")]
     pub user_defined: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for IntentUtteranceData {
@@ -42,6 +50,48 @@ pub struct IntentUtterance {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub lang: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl IntentUtterance {
+    /// translation map of template utterance (e.g. @sys.date:date at @sys.time:time).
+    /// Entity tokens are protected by placeholders so that only text between them is translated.
+    pub fn template_to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for utterance_data in self.data.iter() {
+            if let Some(protected) =
+                protect_for_translation(&utterance_data.text, &RE_ENTITY_REFERENCE)
+            {
+                map_to_translate.insert(format!("{:p}", &utterance_data.text), protected);
+            }
+        }
+
+        map_to_translate
+    }
+
+    /// applies translation of template utterance. Texts whose entity tokens could not be preserved
+    /// stay untranslated, description of such problems is returned.
+    pub fn template_apply_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut problems = vec![];
+
+        for utterance_data in self.data.iter_mut() {
+            let original = utterance_data.text.to_owned();
+            for issue in protected_from_translation(
+                &mut utterance_data.text,
+                &RE_ENTITY_REFERENCE,
+                translations_map,
+            ) {
+                problems.push(format!("'{}': {}", original, issue));
+            }
+        }
+
+        problems
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -58,7 +108,9 @@ impl IntentUtterancesFile {
         }
     }
 
-    pub fn to_new_language(&self, new_lang_code: &str) -> Self {
+    /// clones utterances to new language. Unless metadata are kept cloned utterances
+    /// get new IDs, counter and timestamp given by metadata options
+    pub fn to_new_language(&self, new_lang_code: &str, metadata: &ClonedMetadata) -> Self {
         let mut cloned = self.clone();
         cloned.file_name = RE_INTENT_UTTERANCE_FILE
             .replace(&self.file_name, |caps: &Captures| {
@@ -66,10 +118,71 @@ impl IntentUtterancesFile {
             })
             .to_string();
 
+        let updated = metadata.updated.unwrap_or_else(now_epoch_secs);
         for file_content in cloned.file_content.iter_mut() {
             file_content.lang = Some(new_lang_code.to_owned());
+            if !metadata.keep {
+                if file_content.id.is_some() {
+                    file_content.id = Some(new_uuid());
+                }
+                file_content.count = metadata.count;
+                file_content.updated = updated;
+            }
         }
 
         cloned
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::Result;
+    use crate::google::gcloud::translate::dummy_translate;
+
+    // cargo test -- --show-output test_template_translation
+    #[test]
+    fn test_template_translation() -> Result<()> {
+        let mut utterance: IntentUtterance = serde_json::from_str(
+            r#"
+            {
+              "data": [
+                { "text": "remind me @sys.date:date at @sys.time:time please", "userDefined": false },
+                { "text": "switch on @device:device", "userDefined": false },
+                { "text": "@device:device", "userDefined": false }
+              ],
+              "isTemplate": true,
+              "count": 0,
+              "updated": 0
+            }
+            "#,
+        )?;
+
+        let mut translations_map = utterance.template_to_translation();
+        // entity tokens are not sent to translation, template consisting of tokens only is skipped
+        let mut texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+        texts.sort();
+        assert_eq!(
+            texts,
+            vec![
+                r#"remind me <span translate="no">0</span> at <span translate="no">1</span> please"#,
+                r#"switch on <span translate="no">0</span>"#,
+            ]
+        );
+
+        // dummy translation appends text right after the last token of the second template
+        dummy_translate(&mut translations_map);
+        let problems = utterance.template_apply_translation(&translations_map);
+        assert_eq!(
+            problems,
+            vec!["'switch on @device:device': @device:device is merged with adjacent text"]
+        );
+        assert_eq!(
+            utterance.data[0].text,
+            "remind me @sys.date:date at @sys.time:time please_translated"
+        );
+        assert_eq!(utterance.data[1].text, "switch on @device:device");
+        assert_eq!(utterance.data[2].text, "@device:device");
+        Ok(())
+    }
+}
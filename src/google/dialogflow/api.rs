@@ -0,0 +1,577 @@
+//! # Client of Dialogflow ES API V2
+//!
+//! Used to fetch the agent directly from Dialogflow instead of working with ZIP file exported manually
+//! and to deploy translated agent back to Dialogflow.
+//!
+//! See following links
+//!
+//! * [projects.agent.export](https://cloud.google.com/dialogflow/es/docs/reference/rest/v2/projects.agent/export)
+//! * [projects.agent.restore](https://cloud.google.com/dialogflow/es/docs/reference/rest/v2/projects.agent/restore)
+//! * [projects.agent.import](https://cloud.google.com/dialogflow/es/docs/reference/rest/v2/projects.agent/import)
+//! * [projects.agent.train](https://cloud.google.com/dialogflow/es/docs/reference/rest/v2/projects.agent/train)
+//! * [long running operations](https://cloud.google.com/dialogflow/es/docs/reference/rest/v2/projects.operations/get)
+//!
+//! Export is long running operation. Once finished it contains either ZIP file
+//! encoded in base64 (`agentContent`) or Cloud Storage URI (`agentUri`) where ZIP file was stored
+//! (when export was requested with `agentUri`).
+use crate::errors::{Error, Result};
+use crate::google::dialogflow::cx::is_cx_agent_zip;
+use crate::google::gcloud::http::{expect_success, HttpBody, HttpClient, HttpRequest};
+use crate::google::gcloud::storage_bucket_mgmt;
+use crate::google::gcloud::GoogleApiConfig;
+use async_std::task;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Operation {
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub done: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<Value>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+}
+
+impl Operation {
+    pub fn is_done(&self) -> bool {
+        self.done.unwrap_or(false)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportAgentResponse {
+    #[serde(rename = "agentUri")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_uri: Option<String>,
+
+    #[serde(rename = "agentContent")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub agent_content: Option<String>,
+}
+
+/// splits gs://bucket/object into (bucket, object)
+pub fn split_gs_uri(uri: &str) -> Result<(String, String)> {
+    match uri
+        .strip_prefix("gs://")
+        .and_then(|path| path.split_once('/'))
+    {
+        Some((bucket, object)) if !bucket.is_empty() && !object.is_empty() => {
+            Ok((bucket.to_owned(), object.to_owned()))
+        }
+        _ => Err(Error::new(format!(
+            "invalid Cloud Storage URI {}, expected gs://bucket/object",
+            uri
+        ))),
+    }
+}
+
+/// Retrieves the state of long running operation
+///
+/// * `name`: e.g. projects/my-project/operations/ab12cd34-...
+pub async fn get_operation(
+    http_client: &HttpClient,
+    dialogflow_url: &str,
+    name: &str,
+) -> Result<Operation> {
+    let url = format!("{}/v2/{}", dialogflow_url, name);
+    debug!("url: {}", url);
+
+    let resp = http_client.send(&HttpRequest::get(url)).await?;
+    debug!("get_operation.body_str: {}", resp.body);
+    expect_success("get_operation", &resp)?;
+
+    Ok(serde_json::from_str(&resp.body)?)
+}
+
+/// Polls long running operation until it is done. Returns error if operation failed
+/// or did not finish within `operation_timeout` of `api_config`.
+pub async fn wait_for_operation(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    mut operation: Operation,
+) -> Result<Operation> {
+    let started = Instant::now();
+    while !operation.is_done() {
+        if started.elapsed() >= api_config.operation_timeout {
+            return Err(Error::new(format!(
+                "operation {} not finished within {:?}",
+                operation.name, api_config.operation_timeout
+            )));
+        }
+        debug!("operation {} still running", operation.name);
+        task::sleep(api_config.operation_poll_interval).await;
+        operation = get_operation(http_client, &api_config.dialogflow_url, &operation.name).await?;
+    }
+
+    if let Some(error) = operation.error {
+        return Err(Error::new(format!(
+            "operation {} failed: {}",
+            operation.name, error
+        )));
+    }
+
+    Ok(operation)
+}
+
+/// Exports the agent of given project and returns content of agent ZIP file
+///
+/// * `project_id`: Google project ID the agent belongs to
+/// * `agent_uri`: optional gs://bucket/object where agent should be exported to (ZIP file is returned in the response otherwise)
+pub async fn export_agent(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    project_id: &str,
+    agent_uri: Option<&str>,
+) -> Result<Vec<u8>> {
+    let url = format!(
+        "{}/v2/projects/{}/agent:export",
+        api_config.dialogflow_url, project_id
+    );
+
+    let body = match agent_uri {
+        Some(agent_uri) => json!({ "agentUri": agent_uri }),
+        None => json!({}),
+    };
+
+    debug!("url: {}", url);
+    debug!("body: {}", body);
+
+    // export does not modify the agent, safe to retry
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)).idempotent())
+        .await?;
+    debug!("export_agent.body_str: {}", resp.body);
+    expect_success("export_agent", &resp)?;
+
+    let operation: Operation = serde_json::from_str(&resp.body)?;
+    let operation = wait_for_operation(http_client, api_config, operation).await?;
+
+    let export_response: ExportAgentResponse = match operation.response {
+        Some(response) => serde_json::from_value(response)?,
+        None => {
+            return Err(Error::new(format!(
+                "export of agent {} finished without response",
+                project_id
+            )))
+        }
+    };
+
+    match export_response {
+        ExportAgentResponse {
+            agent_content: Some(agent_content),
+            ..
+        } => base64::decode(&agent_content)
+            .map_err(|err| Error::new(format!("unable to decode agentContent: {}", err))),
+        ExportAgentResponse {
+            agent_uri: Some(agent_uri),
+            ..
+        } => {
+            let (bucket, object) = split_gs_uri(&agent_uri)?;
+            debug!("downloading exported agent from {}", agent_uri);
+            let resp = storage_bucket_mgmt::download_binary_object(
+                http_client,
+                &api_config.storage_url,
+                &bucket,
+                &object,
+            )
+            .await?;
+            if !resp.status_code.starts_with('2') {
+                return Err(Error::new(format!(
+                    "download of exported agent {} failed with HTTP {}",
+                    agent_uri, resp.status_code
+                )));
+            }
+            Ok(resp.body)
+        }
+        _ => Err(Error::new(format!(
+            "export of agent {} returned neither agentContent nor agentUri",
+            project_id
+        ))),
+    }
+}
+
+/// Exports the agent of given project and stores it as ZIP file, which can be then
+/// parsed by [`parse_gdf_agent_zip`](../agent/fn.parse_gdf_agent_zip.html)
+pub async fn export_agent_zip(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    project_id: &str,
+    agent_uri: Option<&str>,
+    zip_path: &Path,
+) -> Result<()> {
+    let content = export_agent(http_client, api_config, project_id, agent_uri).await?;
+    if let Some(folder) = zip_path.parent() {
+        fs::create_dir_all(folder)?;
+    }
+    fs::write(zip_path, content)?;
+    Ok(())
+}
+
+/// How translated agent is deployed to Dialogflow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeployMode {
+    /// replaces the agent completely, existing intents and entities are deleted
+    Restore,
+    /// adds new intents and entities, existing ones with the same name are replaced
+    Import,
+}
+
+impl DeployMode {
+    fn api_method(&self) -> &'static str {
+        match self {
+            DeployMode::Restore => "restore",
+            DeployMode::Import => "import",
+        }
+    }
+}
+
+/// Returns true if agent of given project has no intents and no entity types
+/// (or does not exist at all)
+pub async fn is_agent_empty(
+    http_client: &HttpClient,
+    dialogflow_url: &str,
+    project_id: &str,
+) -> Result<bool> {
+    for collection in ["intents", "entityTypes"].iter() {
+        if !is_agent_collection_empty(http_client, dialogflow_url, project_id, collection).await? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Returns true if agent has no item of given collection (intents, entityTypes)
+async fn is_agent_collection_empty(
+    http_client: &HttpClient,
+    dialogflow_url: &str,
+    project_id: &str,
+    collection: &str,
+) -> Result<bool> {
+    let url = format!(
+        "{}/v2/projects/{}/agent/{}?pageSize=1",
+        dialogflow_url, project_id, collection
+    );
+    debug!("url: {}", url);
+
+    let resp = http_client.send(&HttpRequest::get(url)).await?;
+    debug!("is_agent_empty.body_str: {}", resp.body);
+    if resp.status_code == "404" {
+        return Ok(true);
+    }
+    expect_success(&format!("list_{}", collection), &resp)?;
+
+    let items: Value = serde_json::from_str(&resp.body)?;
+    Ok(items[collection]
+        .as_array()
+        .map(|items| items.is_empty())
+        .unwrap_or(true))
+}
+
+/// Uploads agent ZIP file via agent:restore or agent:import and waits until operation is finished
+pub async fn upload_agent(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    project_id: &str,
+    mode: DeployMode,
+    agent_zip: &[u8],
+) -> Result<Operation> {
+    let url = format!(
+        "{}/v2/projects/{}/agent:{}",
+        api_config.dialogflow_url,
+        project_id,
+        mode.api_method()
+    );
+    debug!("url: {}", url);
+
+    let body = json!({ "agentContent": base64::encode(agent_zip) });
+
+    // modifies the agent, not idempotent
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)))
+        .await?;
+    debug!("upload_agent.body_str: {}", resp.body);
+    expect_success(&format!("{}_agent", mode.api_method()), &resp)?;
+
+    let operation: Operation = serde_json::from_str(&resp.body)?;
+    wait_for_operation(http_client, api_config, operation).await
+}
+
+/// Trains the agent and waits until training is finished
+pub async fn train_agent(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    project_id: &str,
+) -> Result<Operation> {
+    let url = format!(
+        "{}/v2/projects/{}/agent:train",
+        api_config.dialogflow_url, project_id
+    );
+    debug!("url: {}", url);
+
+    // repeated training has no other side effects, safe to retry
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(json!({}))).idempotent())
+        .await?;
+    debug!("train_agent.body_str: {}", resp.body);
+    expect_success("train_agent", &resp)?;
+
+    let operation: Operation = serde_json::from_str(&resp.body)?;
+    wait_for_operation(http_client, api_config, operation).await
+}
+
+/// Deployment of translated agent to Dialogflow project
+pub struct AgentDeployment<'a> {
+    pub project_id: &'a str,
+    pub mode: DeployMode,
+    /// translated ES agent, i.e. _packed/TranslatedAgent.zip (CX agents are refused)
+    pub agent_zip_path: &'a Path,
+    /// where current agent is exported before it is overwritten. Backup is created only if agent is not empty
+    pub backup_zip_path: &'a Path,
+    /// if true agent is trained after deployment
+    pub train: bool,
+}
+
+/// Deploys translated agent ZIP file to Dialogflow
+///
+/// * `deployment`: target project, mode and agent files, see [AgentDeployment](struct.AgentDeployment.html)
+/// * `confirm`: asked before existing non-empty agent is restored (i.e. completely replaced), deployment is cancelled if it returns false
+///
+/// Returns path to backup file if backup was created
+pub async fn deploy_agent(
+    http_client: &HttpClient,
+    api_config: &GoogleApiConfig,
+    deployment: &AgentDeployment<'_>,
+    confirm: &dyn Fn(&str) -> bool,
+) -> Result<Option<PathBuf>> {
+    let AgentDeployment {
+        project_id,
+        mode,
+        agent_zip_path,
+        backup_zip_path,
+        train,
+    } = *deployment;
+    if is_cx_agent_zip(agent_zip_path.to_str().unwrap())? {
+        return Err(Error::new(format!(
+            "{} is Dialogflow CX agent, only ES agents can be deployed",
+            agent_zip_path.display()
+        )));
+    }
+    let agent_zip = fs::read(agent_zip_path)?;
+
+    let is_empty = is_agent_empty(http_client, &api_config.dialogflow_url, project_id).await?;
+    if !is_empty && mode == DeployMode::Restore {
+        let question = format!(
+            "Agent in project {} is not empty, restore will replace all its intents and entities. Continue?",
+            project_id
+        );
+        if !confirm(&question) {
+            return Err(Error::new(format!(
+                "deployment to project {} cancelled by user",
+                project_id
+            )));
+        }
+    }
+
+    let mut backup = None;
+    if !is_empty {
+        debug!("creating backup of agent {}", project_id);
+        export_agent_zip(http_client, api_config, project_id, None, backup_zip_path).await?;
+        backup = Some(backup_zip_path.to_owned());
+    }
+
+    upload_agent(http_client, api_config, project_id, mode, &agent_zip).await?;
+
+    if train {
+        train_agent(http_client, api_config, project_id).await?;
+    }
+
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::gcloud::auth::{CredentialSource, TokenProvider};
+    use crate::google::gcloud::http::test_server::*;
+    use crate::google::gcloud::http::RetryPolicy;
+    use std::time::Duration;
+
+    const AUTHORIZED_USER_JSON: &str = r#"{
+        "client_id": "dummy-client-id.apps.googleusercontent.com",
+        "client_secret": "dummy-client-secret",
+        "refresh_token": "dummy-refresh-token",
+        "type": "authorized_user"
+    }"#;
+
+    fn http_client(token_url: &str) -> HttpClient {
+        HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_json_str(AUTHORIZED_USER_JSON).unwrap(),
+                Some(token_url),
+            ),
+            RetryPolicy::no_retry(),
+        )
+    }
+
+    // cargo test -- --show-output test_split_gs_uri
+    #[test]
+    fn test_split_gs_uri() {
+        assert_eq!(
+            split_gs_uri("gs://my-bucket/agent.zip").unwrap(),
+            ("my-bucket".to_owned(), "agent.zip".to_owned())
+        );
+        assert!(split_gs_uri("gs://my-bucket").is_err());
+        assert!(split_gs_uri("https://my-bucket/agent.zip").is_err());
+    }
+
+    // cargo test -- --show-output test_export_agent_content
+    #[test]
+    fn test_export_agent_content() {
+        let (token_url, token_server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"access_token":"t1","token_type":"Bearer","expires_in":3600}"#,
+        )]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(200, r#"{"name":"projects/p1/operations/op1"}"#),
+            ScriptedResponse::new(
+                200,
+                &format!(
+                    r#"{{"name":"projects/p1/operations/op1","done":true,"response":{{"@type":"type.googleapis.com/google.cloud.dialogflow.v2.ExportAgentResponse","agentContent":"{}"}}}}"#,
+                    base64::encode(b"PK\x03\x04zip")
+                ),
+            ),
+        ]);
+        let mut api_config = GoogleApiConfig::new(None, None, Some(&url), None, None);
+        api_config.operation_poll_interval = Duration::from_millis(1);
+
+        let content = task::block_on(export_agent(
+            &http_client(&token_url),
+            &api_config,
+            "p1",
+            None,
+        ))
+        .unwrap();
+        assert_eq!(content, b"PK\x03\x04zip".to_vec());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0]
+            .0
+            .starts_with("POST /v2/projects/p1/agent:export"));
+        assert!(requests[1]
+            .0
+            .starts_with("GET /v2/projects/p1/operations/op1"));
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_export_agent_failed
+    #[test]
+    fn test_export_agent_failed() {
+        let (token_url, token_server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"access_token":"t1","token_type":"Bearer","expires_in":3600}"#,
+        )]);
+        let (url, server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"name":"projects/p1/operations/op1","done":true,"error":{"code":7,"message":"permission denied"}}"#,
+        )]);
+        let api_config = GoogleApiConfig::new(None, None, Some(&url), None, None);
+
+        let result = task::block_on(export_agent(
+            &http_client(&token_url),
+            &api_config,
+            "p1",
+            None,
+        ));
+        assert!(format!("{:?}", result.unwrap_err()).contains("permission denied"));
+
+        server.join().unwrap();
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_export_agent_timeout
+    #[test]
+    fn test_export_agent_timeout() {
+        let (token_url, token_server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"access_token":"t1","token_type":"Bearer","expires_in":3600}"#,
+        )]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(200, r#"{"name":"projects/p1/operations/op1"}"#),
+            ScriptedResponse::new(200, r#"{"name":"projects/p1/operations/op1","done":false}"#),
+        ]);
+        let mut api_config = GoogleApiConfig::new(
+            None,
+            None,
+            Some(&url),
+            None,
+            Some(Duration::from_millis(10)),
+        );
+        // single poll, timeout elapses while sleeping before it
+        api_config.operation_poll_interval = Duration::from_millis(50);
+
+        let result = task::block_on(export_agent(
+            &http_client(&token_url),
+            &api_config,
+            "p1",
+            None,
+        ));
+        assert!(format!("{:?}", result.unwrap_err())
+            .contains("operation projects/p1/operations/op1 not finished"));
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 2);
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_is_agent_empty
+    #[test]
+    fn test_is_agent_empty() {
+        let (token_url, token_server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"access_token":"t1","token_type":"Bearer","expires_in":3600}"#,
+        )]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(
+                200,
+                r#"{"intents":[{"name":"projects/p1/agent/intents/i1"}]}"#,
+            ),
+            ScriptedResponse::new(200, "{}"),
+            ScriptedResponse::new(200, "{}"),
+            ScriptedResponse::new(404, r#"{"error":{"code":404}}"#),
+            ScriptedResponse::new(404, r#"{"error":{"code":404}}"#),
+            ScriptedResponse::new(200, r#"{"intents":[]}"#),
+            ScriptedResponse::new(
+                200,
+                r#"{"entityTypes":[{"name":"projects/p4/agent/entityTypes/e1"}]}"#,
+            ),
+        ]);
+        let http_client = http_client(&token_url);
+
+        assert!(!task::block_on(is_agent_empty(&http_client, &url, "p1")).unwrap());
+        assert!(task::block_on(is_agent_empty(&http_client, &url, "p2")).unwrap());
+        assert!(task::block_on(is_agent_empty(&http_client, &url, "p3")).unwrap());
+        // agent with entity types only is not empty, restore would overwrite them
+        assert!(!task::block_on(is_agent_empty(&http_client, &url, "p4")).unwrap());
+
+        let requests = server.join().unwrap();
+        assert!(requests[0]
+            .0
+            .starts_with("GET /v2/projects/p1/agent/intents?pageSize=1"));
+        assert!(requests[2]
+            .0
+            .starts_with("GET /v2/projects/p2/agent/entityTypes?pageSize=1"));
+        token_server.join().unwrap();
+    }
+}
@@ -0,0 +1,1094 @@
+//! # Dialogflow CX agent
+//!
+//! CX export (JSON package) has completely different structure than ES agent:
+//!
+//! * `agent.json` - agent manifest with default and supported languages
+//! * `flows/<<flow>>/<<flow>>.json` and `flows/<<flow>>/pages/*.json` - flows and pages with fulfillments
+//! * `flows/<<flow>>/transitionRouteGroups/*.json`, `transitionRouteGroups/*.json` - route groups
+//! * `intents/<<intent>>/<<intent>>.json` + `intents/<<intent>>/trainingPhrases/<<lang>>.json`
+//! * `entityTypes/<<entity>>/<<entity>>.json` + `entityTypes/<<entity>>/entities/<<lang>>.json`
+//!
+//! Training phrases and entities are stored in separate file per language (similarly to ES agent)
+//! while fulfillment messages of all languages are stored inline, distinguished by `languageCode`.
+//! All other files in the export (webhooks, test cases, ...) are copied to translated agent as they are.
+mod entity_types;
+mod flows;
+mod fulfillment;
+mod intents;
+
+pub use entity_types::*;
+pub use flows::*;
+pub use fulfillment::*;
+pub use intents::*;
+
+use crate::errors::{Error, Result};
+use crate::google::dialogflow::agent::unzip_agent_to_tmp_folder;
+use crate::google::dialogflow::ids::duplicated_ids;
+use crate::google::dialogflow::options::AgentOptions;
+use crate::google::dialogflow::unknown_fields::{
+    report_unknown_fields, unknown_fields, UnknownFields,
+};
+use crate::google::gcloud::translate::Translate;
+use crate::summary::ReportItem;
+use crate::zip::zip_directory;
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+lazy_static! {
+    pub static ref RE_CX_FLOW_FILE: Regex = Regex::new(r"^flows/[^/]+/[^/]+\.json$").unwrap();
+    pub static ref RE_CX_PAGE_FILE: Regex = Regex::new(r"^flows/[^/]+/pages/[^/]+\.json$").unwrap();
+    pub static ref RE_CX_ROUTE_GROUP_FILE: Regex =
+        Regex::new(r"^(flows/[^/]+/)?transitionRouteGroups/[^/]+\.json$").unwrap();
+    pub static ref RE_CX_INTENT_FILE: Regex = Regex::new(r"^intents/[^/]+/[^/]+\.json$").unwrap();
+    pub static ref RE_CX_TRAINING_PHRASES_FILE: Regex =
+        Regex::new(r"^(intents/[^/]+)/trainingPhrases/([^/]+)\.json$").unwrap();
+    pub static ref RE_CX_ENTITY_TYPE_FILE: Regex =
+        Regex::new(r"^entityTypes/[^/]+/[^/]+\.json$").unwrap();
+    pub static ref RE_CX_ENTITIES_FILE: Regex =
+        Regex::new(r"^(entityTypes/[^/]+)/entities/([^/]+)\.json$").unwrap();
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CxAgentManifest {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(rename = "defaultLanguageCode")]
+    pub default_language_code: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "supportedLanguageCodes")]
+    pub supported_language_codes: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "avatarUri")]
+    pub avatar_uri: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "startFlow")]
+    pub start_flow: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "securitySettings")]
+    pub security_settings: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enableStackdriverLogging")]
+    pub enable_stackdriver_logging: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enableSpellCorrection")]
+    pub enable_spell_correction: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enableMultiLanguageTraining")]
+    pub enable_multi_language_training: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locked: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "speechToTextSettings")]
+    pub speech_to_text_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "textToSpeechSettings")]
+    pub text_to_speech_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "advancedSettings")]
+    pub advanced_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "gitIntegrationSettings")]
+    pub git_integration_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "genAppBuilderSettings")]
+    pub gen_app_builder_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "answerFeedbackSettings")]
+    pub answer_feedback_settings: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for CxAgentManifest {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        if let Some(description) = &self.description {
+            if !description.trim().is_empty() {
+                map_to_translate.insert(format!("{:p}", description), description.to_owned());
+            }
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(description) = self.description.as_mut() {
+            if let Some(translated) = translations_map.get(&format!("{:p}", description)) {
+                *description = translated.to_owned();
+            }
+        }
+    }
+}
+
+/// parsed file of CX agent, file_name is path relative to agent root, e.g. flows/Default Start Flow/pages/Order.json
+#[derive(Debug, Clone, PartialEq)]
+pub struct CxAgentFile<T> {
+    pub file_name: String,
+    pub file_content: T,
+}
+
+impl<T> CxAgentFile<T> {
+    pub fn new(file_name: String, file_content: T) -> Self {
+        CxAgentFile {
+            file_name,
+            file_content,
+        }
+    }
+}
+
+/// entity type stored in given entity type folder, e.g. entityTypes/size
+fn find_entity_type<'a>(
+    entity_types: &'a [CxAgentFile<CxEntityType>],
+    entity_type_folder: &str,
+) -> Option<&'a CxEntityType> {
+    entity_types
+        .iter()
+        .find(|entity_type| {
+            entity_type
+                .file_name
+                .starts_with(&format!("{}/", entity_type_folder))
+        })
+        .map(|entity_type| &entity_type.file_content)
+}
+
+/// returns (folder, lang) for per-language files, e.g. intents/order/trainingPhrases/en.json -> (intents/order, en)
+fn language_file_parts<'a>(re: &Regex, file_name: &'a str) -> Option<(&'a str, &'a str)> {
+    re.captures(file_name)
+        .map(|caps| (caps.get(1).unwrap().as_str(), caps.get(2).unwrap().as_str()))
+}
+
+#[derive(Debug)]
+pub struct CxAgent {
+    agent: CxAgentManifest,
+    flows: Vec<CxAgentFile<CxFlow>>,
+    pages: Vec<CxAgentFile<CxPage>>,
+    route_groups: Vec<CxAgentFile<CxTransitionRouteGroup>>,
+    intents: Vec<CxAgentFile<CxIntent>>,
+    training_phrases: Vec<CxAgentFile<CxTrainingPhrases>>,
+    entity_types: Vec<CxAgentFile<CxEntityType>>,
+    entities: Vec<CxAgentFile<CxEntities>>,
+    /// files not relevant for translation, copied as they are
+    other_files: Vec<CxAgentFile<Vec<u8>>>,
+    /// findings of translation reported to the user in run summary
+    report: Vec<ReportItem>,
+}
+
+impl CxAgent {
+    /// findings collected since last call
+    pub fn take_report(&mut self) -> Vec<ReportItem> {
+        std::mem::take(&mut self.report)
+    }
+
+    /// IDs of training phrases must be unique across the agent
+    fn report_duplicated_ids(&mut self) {
+        let ids = self.training_phrases.iter().flat_map(|file| {
+            file.file_content
+                .training_phrases
+                .iter()
+                .filter_map(move |training_phrase| {
+                    training_phrase
+                        .id
+                        .as_deref()
+                        .map(|id| (id, file.file_name.as_str()))
+                })
+        });
+        for (id, files) in duplicated_ids(ids) {
+            for file in files.iter() {
+                self.report.push(ReportItem::new(
+                    "duplicated_id",
+                    file,
+                    format!("ID {} is used {} times", id, files.len()),
+                ));
+            }
+        }
+    }
+
+    /// adds language to supported languages. Time zone and spell correction are agent wide settings,
+    /// if specified they are updated as well
+    pub fn add_supported_language(&mut self, lang: &str, options: &AgentOptions) {
+        if let Some(time_zone) = &options.time_zone {
+            self.agent.time_zone = Some(time_zone.to_owned());
+        }
+        if let Some(spell_correction) = options.spell_correction {
+            self.agent.enable_spell_correction = Some(spell_correction);
+        }
+        if self.agent.default_language_code == lang {
+            return;
+        }
+        let supported_languages = self
+            .agent
+            .supported_language_codes
+            .get_or_insert_with(Vec::new);
+        if !supported_languages.iter().any(|item| item == lang) {
+            supported_languages.push(lang.to_owned());
+        }
+    }
+
+    fn is_regexp_entity_type(&self, entity_type_folder: &str) -> bool {
+        find_entity_type(&self.entity_types, entity_type_folder)
+            .map(|entity_type| entity_type.kind == KIND_REGEXP)
+            .unwrap_or(false)
+    }
+
+    fn fulfillments_mut(&mut self) -> Vec<&mut CxFulfillment> {
+        let mut fulfillments = vec![];
+        for flow in self.flows.iter_mut() {
+            fulfillments.extend(flow.file_content.fulfillments_mut());
+        }
+        for page in self.pages.iter_mut() {
+            fulfillments.extend(page.file_content.fulfillments_mut());
+        }
+        for route_group in self.route_groups.iter_mut() {
+            fulfillments.extend(route_group.file_content.fulfillments_mut());
+        }
+        fulfillments
+    }
+
+    pub fn to_translation(
+        &mut self,
+        lang_from: &str,
+        lang_to: &str,
+        skip_entities_translation: bool,
+        skip_utterances_translation: bool,
+        skip_responses_translation: bool,
+        options: &AgentOptions,
+    ) -> collections::HashMap<String, String> {
+        let mut translations_map: collections::HashMap<String, String> =
+            collections::HashMap::new();
+
+        if options.translate_manifest {
+            translations_map.extend(self.agent.to_translation());
+        }
+
+        if !skip_entities_translation {
+            // create new entities files (unless target language already exists)
+            let mut new_entities_files = vec![];
+            for entities_file in self.entities.iter() {
+                if let Some((folder, lang)) =
+                    language_file_parts(&RE_CX_ENTITIES_FILE, &entities_file.file_name)
+                {
+                    let new_file_name = format!("{}/entities/{}.json", folder, lang_to);
+                    if lang != lang_from
+                        || self
+                            .entities
+                            .iter()
+                            .any(|item| item.file_name == new_file_name)
+                    {
+                        continue;
+                    }
+                    debug!(
+                        "CxAgent::to_translation, cloning file {}",
+                        &entities_file.file_name
+                    );
+                    let entity_type = find_entity_type(&self.entity_types, folder);
+                    let keep_values = entity_type
+                        .map(|entity_type| {
+                            options.entity_values.keep_values(&entity_type.display_name)
+                        })
+                        .unwrap_or(false);
+                    // only synonym of list entity keeping values is the value itself
+                    let is_list = entity_type
+                        .map(|entity_type| entity_type.kind == KIND_LIST)
+                        .unwrap_or(false);
+                    let translate =
+                        !(self.is_regexp_entity_type(folder) || (keep_values && is_list));
+                    new_entities_files.push((
+                        translate,
+                        keep_values,
+                        CxAgentFile::new(
+                            new_file_name,
+                            entities_file.file_content.to_new_language(lang_to),
+                        ),
+                    ));
+                }
+            }
+
+            for (translate, keep_values, new_entities_file) in new_entities_files.iter() {
+                if !translate {
+                    continue; // skip regex entities and list entities keeping values!
+                }
+                for entity in new_entities_file.file_content.entities.iter() {
+                    let mut entity_translations = if entity.is_composite() {
+                        // entity references are protected by placeholders
+                        entity.composite_to_translation()
+                    } else {
+                        entity.to_translation()
+                    };
+                    if *keep_values {
+                        // only synonyms are translated, webhooks rely on entity values
+                        entity_translations.remove(&format!("{:p}", &entity.value));
+                    }
+                    translations_map.extend(entity_translations);
+                }
+            }
+
+            self.entities.extend(
+                new_entities_files
+                    .into_iter()
+                    .map(|(_, _, new_entities_file)| new_entities_file),
+            );
+        }
+
+        if !skip_utterances_translation {
+            let mut new_training_phrases_files = vec![];
+            for training_phrases_file in self.training_phrases.iter() {
+                if let Some((folder, lang)) = language_file_parts(
+                    &RE_CX_TRAINING_PHRASES_FILE,
+                    &training_phrases_file.file_name,
+                ) {
+                    let new_file_name = format!("{}/trainingPhrases/{}.json", folder, lang_to);
+                    if lang != lang_from
+                        || self
+                            .training_phrases
+                            .iter()
+                            .any(|item| item.file_name == new_file_name)
+                    {
+                        continue;
+                    }
+                    new_training_phrases_files.push(CxAgentFile::new(
+                        new_file_name,
+                        training_phrases_file
+                            .file_content
+                            .to_new_language(lang_to, &options.cloned_metadata),
+                    ));
+                }
+            }
+
+            for new_training_phrases_file in new_training_phrases_files.iter() {
+                translations_map.extend(new_training_phrases_file.file_content.to_translation());
+            }
+
+            self.training_phrases.extend(new_training_phrases_files);
+        }
+
+        if !skip_responses_translation {
+            let default_lang = self.agent.default_language_code.to_owned();
+            for fulfillment in self.fulfillments_mut() {
+                // fulfillments already having messages in target language are not translated again
+                if fulfillment.add_language(&default_lang, lang_from, lang_to) {
+                    translations_map.extend(fulfillment.to_translation(
+                        &default_lang,
+                        lang_to,
+                        &options.payload_rules,
+                    ));
+                }
+            }
+        }
+
+        self.report_duplicated_ids();
+
+        translations_map
+    }
+
+    pub fn from_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+        lang_to: &str,
+        skip_entities_translation: bool,
+        skip_utterances_translation: bool,
+        skip_responses_translation: bool,
+        options: &AgentOptions,
+    ) {
+        self.agent.from_translation(translations_map);
+
+        if !skip_entities_translation {
+            for entities_file in self.entities.iter_mut() {
+                for entity in entities_file.file_content.entities.iter_mut() {
+                    if entity.is_composite() {
+                        for problem in entity.composite_apply_translation(translations_map) {
+                            self.report.push(ReportItem::new(
+                                "composite_entity",
+                                &entities_file.file_name,
+                                format!("{}, translation not applied", problem),
+                            ));
+                        }
+                    } else {
+                        entity.from_translation(translations_map);
+                    }
+                }
+
+                match language_file_parts(&RE_CX_ENTITIES_FILE, &entities_file.file_name) {
+                    Some((folder, lang)) if lang == lang_to => {
+                        if let Some(entity_type) = find_entity_type(&self.entity_types, folder) {
+                            if entity_type.kind == KIND_REGEXP {
+                                continue;
+                            }
+                            let is_list = entity_type.kind == KIND_LIST;
+                            let keep_values =
+                                options.entity_values.keep_values(&entity_type.display_name);
+                            if keep_values {
+                                for entity in entities_file.file_content.entities.iter_mut() {
+                                    entity.adjust_synonyms_to_kept_value(is_list);
+                                }
+                            }
+                            if let Some(normalization) = &options.synonyms_normalization {
+                                for change in normalization.normalize(
+                                    &mut entities_file.file_content.entities,
+                                    !is_list,
+                                    keep_values,
+                                ) {
+                                    self.report.push(ReportItem::new(
+                                        "synonyms",
+                                        &entities_file.file_name,
+                                        change,
+                                    ));
+                                }
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if !skip_utterances_translation {
+            for training_phrases_file in self.training_phrases.iter_mut() {
+                training_phrases_file
+                    .file_content
+                    .from_translation(translations_map);
+            }
+        }
+
+        if !skip_responses_translation {
+            let default_lang = self.agent.default_language_code.to_owned();
+            for fulfillment in self.fulfillments_mut() {
+                fulfillment.from_translation(translations_map, &default_lang, lang_to);
+            }
+        }
+    }
+
+    pub fn serialize(&self, target_folder: &str) -> Result<()> {
+        let base_path = Path::new(target_folder);
+        let unpacked_folder = base_path.join("_unpacked");
+        let packed_folder = base_path.join("_packed");
+
+        fs::create_dir_all(&unpacked_folder)?;
+        fs::create_dir_all(&packed_folder)?;
+
+        let write_file = |file_name: &str, content: &[u8]| -> Result<()> {
+            let file_path = unpacked_folder.join(file_name);
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(file_path, content)?;
+            Ok(())
+        };
+
+        write_file(
+            "agent.json",
+            serde_json::to_string_pretty(&self.agent)?.as_bytes(),
+        )?;
+
+        macro_rules! serialize_cx_agent_section {
+            ($files:expr) => {
+                for item in $files.iter() {
+                    write_file(
+                        &item.file_name,
+                        serde_json::to_string_pretty(&item.file_content)?.as_bytes(),
+                    )?;
+                }
+            };
+        }
+
+        serialize_cx_agent_section!(self.flows);
+        serialize_cx_agent_section!(self.pages);
+        serialize_cx_agent_section!(self.route_groups);
+        serialize_cx_agent_section!(self.intents);
+        serialize_cx_agent_section!(self.training_phrases);
+        serialize_cx_agent_section!(self.entity_types);
+        serialize_cx_agent_section!(self.entities);
+
+        for other_file in self.other_files.iter() {
+            write_file(&other_file.file_name, &other_file.file_content)?;
+        }
+
+        zip_directory(
+            unpacked_folder.to_str().unwrap(),
+            packed_folder.join("TranslatedAgent.zip").to_str().unwrap(),
+        )?;
+        Ok(())
+    }
+} // impl CxAgent
+
+/// deserializes CX agent file and checks that serializing it back gives the same json,
+/// i.e. no information will be lost when writing translated agent. Unknown fields are
+/// reported unless in strict mode, where they fail the parsing.
+fn parse_cx_agent_file<T>(
+    file_name: &str,
+    file_str: &str,
+    strict: bool,
+    report: &mut Vec<ReportItem>,
+) -> Result<T>
+where
+    T: DeserializeOwned + Serialize,
+{
+    debug!("parse_cx_agent_file: processing file {}", file_name);
+    let deserialized_struct: T = serde_json::from_str(file_str)?;
+
+    match unknown_fields(&deserialized_struct, file_str, strict) {
+        Ok(fields) => report_unknown_fields(report, file_name, fields),
+        Err(err) => {
+            debug!(
+                "parse_cx_agent_file: unknown fields check failed for {}: {:?}",
+                file_name, err
+            );
+            return Err(err);
+        }
+    }
+    Ok(deserialized_struct)
+}
+
+/// CX agent export always contains flows folder (at least Default Start Flow)
+pub fn is_cx_agent_zip(zip_path: &str) -> Result<bool> {
+    let file = fs::File::open(zip_path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    for i in 0..archive.len() {
+        if archive.by_index(i)?.name().starts_with("flows/") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn parse_cx_agent_zip(zip_path: &str, strict: bool) -> Result<CxAgent> {
+    let tmp_working_folder_path = unzip_agent_to_tmp_folder(zip_path)?;
+
+    let mut agent_manifest = None;
+    let mut flows = vec![];
+    let mut pages = vec![];
+    let mut route_groups = vec![];
+    let mut intents = vec![];
+    let mut training_phrases = vec![];
+    let mut entity_types = vec![];
+    let mut entities = vec![];
+    let mut other_files = vec![];
+    let mut report = vec![];
+
+    for entry in WalkDir::new(&tmp_working_folder_path) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        // file name relative to agent root with forward slashes (same as in zip file)
+        let file_name = entry
+            .path()
+            .strip_prefix(&tmp_working_folder_path)
+            .unwrap()
+            .components()
+            .map(|component| component.as_os_str().to_str().unwrap())
+            .collect::<Vec<&str>>()
+            .join("/");
+
+        if !file_name.ends_with(".json") {
+            other_files.push(CxAgentFile::new(file_name, fs::read(entry.path())?));
+            continue;
+        }
+
+        let file_str = fs::read_to_string(entry.path())?;
+        if file_name == "agent.json" {
+            agent_manifest = Some(parse_cx_agent_file(
+                &file_name,
+                &file_str,
+                strict,
+                &mut report,
+            )?);
+        } else if RE_CX_PAGE_FILE.is_match(&file_name) {
+            let page = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            pages.push(CxAgentFile::new(file_name, page));
+        } else if RE_CX_ROUTE_GROUP_FILE.is_match(&file_name) {
+            let route_group = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            route_groups.push(CxAgentFile::new(file_name, route_group));
+        } else if RE_CX_FLOW_FILE.is_match(&file_name) {
+            let flow = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            flows.push(CxAgentFile::new(file_name, flow));
+        } else if RE_CX_TRAINING_PHRASES_FILE.is_match(&file_name) {
+            let phrases = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            training_phrases.push(CxAgentFile::new(file_name, phrases));
+        } else if RE_CX_INTENT_FILE.is_match(&file_name) {
+            let intent = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            intents.push(CxAgentFile::new(file_name, intent));
+        } else if RE_CX_ENTITIES_FILE.is_match(&file_name) {
+            let entities_content = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            entities.push(CxAgentFile::new(file_name, entities_content));
+        } else if RE_CX_ENTITY_TYPE_FILE.is_match(&file_name) {
+            let entity_type = parse_cx_agent_file(&file_name, &file_str, strict, &mut report)?;
+            entity_types.push(CxAgentFile::new(file_name, entity_type));
+        } else {
+            other_files.push(CxAgentFile::new(file_name, file_str.into_bytes()));
+        }
+    }
+
+    let agent = match agent_manifest {
+        Some(agent) => agent,
+        None => {
+            return Err(Error::new(format!(
+                "agent.json not found in CX agent {}",
+                zip_path
+            )))
+        }
+    };
+
+    Ok(CxAgent {
+        agent,
+        flows,
+        pages,
+        route_groups,
+        intents,
+        training_phrases,
+        entity_types,
+        entities,
+        other_files,
+        report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::dialogflow::options::{ClonedMetadata, EntityValues, SynonymsNormalization};
+    use crate::google::dialogflow::payload::PayloadRules;
+    use crate::google::dialogflow::responses::normalize_json;
+    use crate::google::gcloud::translate::dummy_translate;
+    use crate::translation_tests_assertions;
+    use std::env;
+    use std::path::PathBuf;
+
+    const CX_AGENT_FOLDER: &str = "./tests/data/cx_agent";
+
+    fn cx_agent_zip(name: &str) -> String {
+        let zip_path = env::temp_dir().join(format!("gdf_cx_{}_{}.zip", name, std::process::id()));
+        let zip_path = zip_path.to_str().unwrap().to_owned();
+        zip_directory(CX_AGENT_FOLDER, &zip_path).unwrap();
+        zip_path
+    }
+
+    // cargo test -- --show-output test_cx_page_deser_ser
+    #[test]
+    fn test_cx_page_deser_ser() -> Result<()> {
+        let page_str = fs::read_to_string(format!(
+            "{}/flows/Default Start Flow/pages/Order.json",
+            CX_AGENT_FOLDER
+        ))?;
+        let mut report = vec![];
+        let page: CxPage = parse_cx_agent_file("Order.json", &page_str, true, &mut report)?;
+        assert_eq!(page.display_name, "Order");
+        let parameters = page.form.as_ref().unwrap().parameters.as_ref().unwrap();
+        assert_eq!(parameters[0].entity_type, "@size");
+
+        assert!(report.is_empty());
+
+        // unknown fields are copied unchanged and reported, strict mode refuses them
+        let page_str = r#"{
+            "name": "p1",
+            "displayName": "Order",
+            "unknownField": true,
+            "entryFulfillment": {
+                "messages": [ { "text": { "text": [ "Hi" ] }, "newMessageField": 1 } ]
+            }
+        }"#;
+        let page: CxPage = parse_cx_agent_file("Order.json", page_str, false, &mut report)?;
+        assert_eq!(
+            serde_json::to_value(&page)?,
+            serde_json::from_str::<JsonValue>(page_str)?
+        );
+        let fields: Vec<&str> = report.iter().map(|item| item.message.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "unknown field entryFulfillment.messages[0].newMessageField is copied unchanged",
+                "unknown field unknownField is copied unchanged"
+            ]
+        );
+        assert!(parse_cx_agent_file::<CxPage>("Order.json", page_str, true, &mut vec![]).is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_cx_response_message
+    #[test]
+    fn test_translate_cx_response_message() -> Result<()> {
+        let message_str = r#"
+        {
+            "text": {
+                "text": ["Hello", "Hi there"]
+            },
+            "languageCode": "de"
+        }
+        "#;
+
+        let message_str_translated_expected = r#"
+        {
+            "text": {
+                "text": ["Hello_translated", "Hi there_translated"]
+            },
+            "languageCode": "de"
+        }
+        "#;
+
+        translation_tests_assertions!(
+            CxResponseMessage,
+            message_str,
+            message_str_translated_expected
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_fulfillment_add_language
+    #[test]
+    fn test_cx_fulfillment_add_language() -> Result<()> {
+        // messages without languageCode belong to default language
+        let fulfillment_str = r#"
+        {
+            "messages": [
+                { "text": { "text": ["Hello"] } },
+                { "payload": { "foo": "bar" } },
+                { "text": { "text": ["Bonjour"] }, "languageCode": "fr" }
+            ]
+        }
+        "#;
+        let mut fulfillment: CxFulfillment = serde_json::from_str(fulfillment_str)?;
+        assert!(fulfillment.add_language("en", "en", "de"));
+        let messages = fulfillment.messages.as_ref().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[3].language_code.as_deref(), Some("de"));
+        assert_eq!(messages[4].payload, messages[1].payload);
+
+        let payload_rules = PayloadRules::parse(&["$.foo"], &[])?;
+        let mut translations_map = fulfillment.to_translation("en", "de", &payload_rules);
+        assert_eq!(translations_map.len(), 2);
+        dummy_translate(&mut translations_map);
+        fulfillment.from_translation(&translations_map, "en", "de");
+        let messages = fulfillment.messages.as_ref().unwrap();
+        assert_eq!(messages[0].text.as_ref().unwrap().text[0], "Hello");
+        assert_eq!(
+            messages[3].text.as_ref().unwrap().text[0],
+            "Hello_translated"
+        );
+        assert_eq!(messages[1].payload.as_ref().unwrap()["foo"], "bar");
+        assert_eq!(
+            messages[4].payload.as_ref().unwrap()["foo"],
+            "bar_translated"
+        );
+
+        // already translated fulfillment is not cloned again
+        assert!(!fulfillment.add_language("en", "en", "de"));
+        assert_eq!(fulfillment.messages.as_ref().unwrap().len(), 5);
+        Ok(())
+    }
+
+    /// translates test CX agent into german and serializes it into temporary folder,
+    /// `translate` gets map of texts to translate
+    fn translate_cx_agent(
+        name: &str,
+        options: &AgentOptions,
+        translate: impl FnOnce(&mut collections::HashMap<String, String>),
+    ) -> Result<(CxAgent, PathBuf)> {
+        let zip_path = cx_agent_zip(name);
+        let mut agent = parse_cx_agent_zip(&zip_path, false)?;
+        let mut translations_map = agent.to_translation("en", "de", false, false, false, options);
+        translate(&mut translations_map);
+        agent.from_translation(&translations_map, "de", false, false, false, options);
+        agent.add_supported_language("de", options);
+
+        let folder =
+            env::temp_dir().join(format!("gdf_cx_{}_translated_{}", name, std::process::id()));
+        agent.serialize(folder.to_str().unwrap())?;
+        fs::remove_file(&zip_path)?;
+        Ok((agent, folder))
+    }
+
+    /// Order page of translated agent parsed again from TranslatedAgent.zip
+    fn translated_order_page(folder: &Path) -> Result<CxPage> {
+        let translated_zip = folder.join("_packed").join("TranslatedAgent.zip");
+        let translated_agent = parse_cx_agent_zip(translated_zip.to_str().unwrap(), false)?;
+        Ok(translated_agent
+            .pages
+            .into_iter()
+            .find(|page| page.file_content.display_name == "Order")
+            .unwrap()
+            .file_content)
+    }
+
+    fn german_messages(page: &mut CxPage) -> Vec<String> {
+        page.fulfillments_mut()
+            .into_iter()
+            .flat_map(|fulfillment| fulfillment.messages_mut())
+            .filter(|message| message.get_message_lang("en") == "de")
+            .map(|message| serde_json::to_string(message).unwrap())
+            .collect()
+    }
+
+    // cargo test -- --show-output test_cx_agent_translation
+    #[test]
+    fn test_cx_agent_translation() -> Result<()> {
+        let zip_path = cx_agent_zip("detection");
+        assert!(is_cx_agent_zip(&zip_path)?);
+        assert!(!is_cx_agent_zip(
+            "./examples/sample_agents/Currency-Converter.zip"
+        )?);
+        fs::remove_file(&zip_path)?;
+
+        let options = AgentOptions::default();
+        let (mut agent, folder) =
+            translate_cx_agent("translation", &options, |translations_map| {
+                let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+                assert!(texts.contains(&"Hi! Welcome to the coffee shop."));
+                assert!(texts.contains(&"Good choice, large is our best seller."));
+                assert!(texts.contains(&"big"));
+                assert!(texts.contains(&" coffee"));
+                // regexp entities and route group already having german messages are not translated
+                assert!(!texts.contains(&"ORD-\\d{6}"));
+                assert!(!texts.contains(&"You can order coffee here."));
+                dummy_translate(translations_map);
+            })?;
+        agent.add_supported_language("en", &options);
+        assert_eq!(
+            agent.agent.supported_language_codes,
+            Some(vec!["de".to_owned()])
+        );
+
+        let unpacked = folder.join("_unpacked");
+        assert!(unpacked.join("webhooks").join("orders.json").exists());
+
+        let phrases =
+            fs::read_to_string(unpacked.join("intents/order.coffee/trainingPhrases/de.json"))?;
+        assert!(phrases.contains(r#""text": "large_translated""#));
+        assert!(phrases.contains(r#""parameterId": "size""#));
+        assert!(phrases.contains(r#""languageCode": "de""#));
+
+        let regexp_entities =
+            fs::read_to_string(unpacked.join("entityTypes/order_id/entities/de.json"))?;
+        assert!(!regexp_entities.contains("_translated"));
+
+        // translated agent can be parsed again
+        let mut page = translated_order_page(&folder)?;
+        let entry_messages = page
+            .entry_fulfillment
+            .as_ref()
+            .unwrap()
+            .messages
+            .as_ref()
+            .unwrap();
+        assert_eq!(entry_messages.len(), 4);
+        assert_eq!(
+            entry_messages[2].text.as_ref().unwrap().text[0],
+            "Let's get your order started._translated"
+        );
+        assert_eq!(entry_messages[3].payload, entry_messages[1].payload);
+        let german_messages = german_messages(&mut page);
+        assert!(german_messages
+            .iter()
+            .any(|message| message.contains("Good choice, large is our best seller._translated")));
+        assert!(german_messages
+            .iter()
+            .any(|message| message.contains("conversationSuccess")));
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_agent_manifest_translation
+    #[test]
+    fn test_cx_agent_manifest_translation() -> Result<()> {
+        let (agent, folder) = translate_cx_agent(
+            "manifest_default",
+            &AgentOptions::default(),
+            |translations_map| {
+                let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+                assert!(!texts.contains(&"Order your coffee"));
+                dummy_translate(translations_map);
+            },
+        )?;
+        assert_eq!(
+            agent.agent.description.as_deref(),
+            Some("Order your coffee")
+        );
+        fs::remove_dir_all(&folder)?;
+
+        let options = AgentOptions {
+            translate_manifest: true,
+            time_zone: Some("Europe/Berlin".to_owned()),
+            spell_correction: Some(true),
+            ..Default::default()
+        };
+        let (agent, folder) = translate_cx_agent("manifest", &options, |translations_map| {
+            let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+            assert!(texts.contains(&"Order your coffee"));
+            dummy_translate(translations_map);
+        })?;
+        assert_eq!(
+            agent.agent.description.as_deref(),
+            Some("Order your coffee_translated")
+        );
+        assert_eq!(agent.agent.time_zone.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(agent.agent.enable_spell_correction, Some(true));
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_composite_entities_translation
+    #[test]
+    fn test_cx_composite_entities_translation() -> Result<()> {
+        let (mut agent, folder) =
+            translate_cx_agent("composite", &AgentOptions::default(), dummy_translate)?;
+
+        // entity references of composite entities are kept
+        let composite_entities =
+            fs::read_to_string(folder.join("_unpacked/entityTypes/price/entities/de.json"))?;
+        assert!(composite_entities
+            .contains("@sys.number:amount @sys.currency-name:currency per cup_translated"));
+        assert!(agent.take_report().is_empty());
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_entity_values_kept
+    #[test]
+    fn test_cx_entity_values_kept() -> Result<()> {
+        let options = AgentOptions {
+            entity_values: EntityValues::parse(&["size=keep"])?,
+            ..Default::default()
+        };
+        let (_, folder) = translate_cx_agent("entity_values", &options, dummy_translate)?;
+
+        // only synonyms of entities keeping values are translated
+        let size_entities =
+            fs::read_to_string(folder.join("_unpacked/entityTypes/size/entities/de.json"))?;
+        let size_entities: CxEntities = serde_json::from_str(&size_entities)?;
+        assert_eq!(size_entities.entities[0].value, "large");
+        assert_eq!(
+            size_entities.entities[0].synonyms,
+            vec!["large", "large_translated", "big_translated"]
+        );
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_synonyms_normalization
+    #[test]
+    fn test_cx_synonyms_normalization() -> Result<()> {
+        let options = AgentOptions {
+            synonyms_normalization: Some(SynonymsNormalization::default()),
+            ..Default::default()
+        };
+        let (mut agent, folder) = translate_cx_agent("synonyms", &options, |translations_map| {
+            for val in translations_map.values_mut() {
+                *val = match val.as_str() {
+                    "large" | "big" => "groß".to_owned(),
+                    _ => format!("{}_translated", val),
+                };
+            }
+        })?;
+
+        let size_entities =
+            fs::read_to_string(folder.join("_unpacked/entityTypes/size/entities/de.json"))?;
+        let size_entities: CxEntities = serde_json::from_str(&size_entities)?;
+        assert_eq!(size_entities.entities[0].value, "groß");
+        assert_eq!(size_entities.entities[0].synonyms, vec!["groß"]);
+        let changes: Vec<ReportItem> = agent
+            .take_report()
+            .into_iter()
+            .filter(|item| item.category == "synonyms")
+            .collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].file, "entityTypes/size/entities/de.json");
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_cloned_training_phrases
+    #[test]
+    fn test_cx_cloned_training_phrases() -> Result<()> {
+        let source_id = "b2c3d4e5-f6a7-4890-9bcd-ef0123456702";
+        let phrases_file = "_unpacked/intents/order.coffee/trainingPhrases/de.json";
+
+        // cloned training phrases get new IDs
+        let (mut agent, folder) =
+            translate_cx_agent("cloned_ids", &AgentOptions::default(), dummy_translate)?;
+        let phrases: CxTrainingPhrases =
+            serde_json::from_str(&fs::read_to_string(folder.join(phrases_file))?)?;
+        let id = phrases.training_phrases[0].id.as_deref().unwrap();
+        assert_ne!(id, source_id);
+        assert_eq!(id.len(), source_id.len());
+        assert!(agent.take_report().is_empty());
+        fs::remove_dir_all(&folder)?;
+
+        // IDs are kept on request (debugging), such IDs are reported as duplicated
+        let options = AgentOptions {
+            cloned_metadata: ClonedMetadata {
+                keep: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (mut agent, folder) = translate_cx_agent("kept_ids", &options, dummy_translate)?;
+        let phrases: CxTrainingPhrases =
+            serde_json::from_str(&fs::read_to_string(folder.join(phrases_file))?)?;
+        assert_eq!(phrases.training_phrases[0].id.as_deref(), Some(source_id));
+        assert!(agent
+            .take_report()
+            .iter()
+            .any(|item| item.category == "duplicated_id"));
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cx_ssml_translation
+    #[test]
+    fn test_cx_ssml_translation() -> Result<()> {
+        let (_, folder) =
+            translate_cx_agent("ssml", &AgentOptions::default(), |translations_map| {
+                // only text nodes of SSML are translated
+                let texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+                assert!(texts.contains(&"What size would you like?"));
+                assert!(!texts.contains(&"<speak>What size would you like?</speak>"));
+                dummy_translate(translations_map);
+            })?;
+
+        let mut page = translated_order_page(&folder)?;
+        assert!(
+            german_messages(&mut page)
+                .iter()
+                .any(|message| message
+                    .contains("<speak>What size would you like?_translated</speak>"))
+        );
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,167 @@
+use crate::google::dialogflow::agent::{RE_COMPOSITE_ENTITY, RE_COMPOSITE_ENTITY_NO_ALIAS};
+use crate::google::dialogflow::synonyms::SynonymsEntry;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use crate::placeholders::{
+    protect_for_translation, protected_from_translation, RE_ENTITY_REFERENCE,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections;
+
+pub const KIND_REGEXP: &str = "KIND_REGEXP";
+pub const KIND_LIST: &str = "KIND_LIST";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxEntityType {
+    pub name: String,
+
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    pub kind: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "autoExpansionMode")]
+    pub auto_expansion_mode: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "excludedPhrases")]
+    pub excluded_phrases: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enableFuzzyExtraction")]
+    pub enable_fuzzy_extraction: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redact: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxEntity {
+    pub value: String,
+    pub synonyms: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxEntity {
+    /// values of composite entities reference other entities, e.g. @sys.number:amount
+    pub fn is_composite(&self) -> bool {
+        RE_COMPOSITE_ENTITY.is_match(&self.value)
+            || RE_COMPOSITE_ENTITY_NO_ALIAS.is_match(&self.value)
+    }
+
+    /// synonyms of entity whose value was kept in source language. List entity has the value
+    /// as its only synonym, value of map entity must be one of its synonyms.
+    pub fn adjust_synonyms_to_kept_value(&mut self, is_list: bool) {
+        if is_list {
+            self.synonyms = vec![self.value.to_owned()];
+        } else if !self.synonyms.contains(&self.value) {
+            self.synonyms.insert(0, self.value.to_owned());
+        }
+    }
+
+    /// translation map of composite entity, entity references are protected by placeholders
+    pub fn composite_to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for text in std::iter::once(&self.value).chain(self.synonyms.iter()) {
+            if let Some(protected) = protect_for_translation(text, &RE_ENTITY_REFERENCE) {
+                map_to_translate.insert(format!("{:p}", text), protected);
+            }
+        }
+
+        map_to_translate
+    }
+
+    /// applies translation of composite entity. Texts which lost or duplicated
+    /// some entity reference stay untranslated, description of such problems is returned.
+    pub fn composite_apply_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+    ) -> Vec<String> {
+        let mut problems = vec![];
+
+        for text in std::iter::once(&mut self.value).chain(self.synonyms.iter_mut()) {
+            let original = text.to_owned();
+            for issue in protected_from_translation(text, &RE_ENTITY_REFERENCE, translations_map) {
+                problems.push(format!("'{}': {}", original, issue));
+            }
+        }
+
+        problems
+    }
+}
+
+impl Translate for CxEntity {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        map_to_translate.insert(format!("{:p}", &self.value), self.value.to_owned());
+
+        for synonym in self.synonyms.iter() {
+            map_to_translate.insert(format!("{:p}", synonym), synonym.to_owned());
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(val) = translations_map.get(&format!("{:p}", &self.value)) {
+            self.value = val.to_owned();
+        }
+
+        for synonym in self.synonyms.iter_mut() {
+            if let Some(syn) = translations_map.get(&format!("{:p}", synonym)) {
+                *synonym = syn.to_owned();
+            }
+        }
+    }
+}
+
+impl SynonymsEntry for CxEntity {
+    fn value(&self) -> &str {
+        &self.value
+    }
+
+    fn value_mut(&mut self) -> &mut String {
+        &mut self.value
+    }
+
+    fn synonyms(&self) -> &Vec<String> {
+        &self.synonyms
+    }
+
+    fn synonyms_mut(&mut self) -> &mut Vec<String> {
+        &mut self.synonyms
+    }
+
+    fn is_composite(&self) -> bool {
+        CxEntity::is_composite(self)
+    }
+}
+
+/// content of entityTypes/<<entity type>>/entities/<<lang>>.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxEntities {
+    pub entities: Vec<CxEntity>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxEntities {
+    pub fn to_new_language(&self, new_lang_code: &str) -> Self {
+        let mut cloned = self.clone();
+        for entity in cloned.entities.iter_mut() {
+            entity.language_code = Some(new_lang_code.to_owned());
+        }
+        cloned
+    }
+}
@@ -0,0 +1,261 @@
+use super::fulfillment::CxFulfillment;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxTransitionRoute {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub intent: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "triggerFulfillment")]
+    pub trigger_fulfillment: Option<CxFulfillment>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "targetPage")]
+    pub target_page: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "targetFlow")]
+    pub target_flow: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxEventHandler {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    pub event: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "triggerFulfillment")]
+    pub trigger_fulfillment: Option<CxFulfillment>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "targetPage")]
+    pub target_page: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "targetFlow")]
+    pub target_flow: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+/// fulfillments of transition routes and event handlers
+fn routes_fulfillments_mut<'a>(
+    transition_routes: &'a mut Option<Vec<CxTransitionRoute>>,
+    event_handlers: &'a mut Option<Vec<CxEventHandler>>,
+) -> Vec<&'a mut CxFulfillment> {
+    let mut fulfillments = vec![];
+    for transition_route in transition_routes.iter_mut().flatten() {
+        if let Some(fulfillment) = transition_route.trigger_fulfillment.as_mut() {
+            fulfillments.push(fulfillment);
+        }
+    }
+    for event_handler in event_handlers.iter_mut().flatten() {
+        if let Some(fulfillment) = event_handler.trigger_fulfillment.as_mut() {
+            fulfillments.push(fulfillment);
+        }
+    }
+    fulfillments
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxFlow {
+    pub name: String,
+
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "transitionRoutes")]
+    pub transition_routes: Option<Vec<CxTransitionRoute>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "eventHandlers")]
+    pub event_handlers: Option<Vec<CxEventHandler>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "transitionRouteGroups")]
+    pub transition_route_groups: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "nluSettings")]
+    pub nlu_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "advancedSettings")]
+    pub advanced_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "knowledgeConnectorSettings")]
+    pub knowledge_connector_settings: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxFlow {
+    pub fn fulfillments_mut(&mut self) -> Vec<&mut CxFulfillment> {
+        routes_fulfillments_mut(&mut self.transition_routes, &mut self.event_handlers)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxFillBehavior {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "initialPromptFulfillment")]
+    pub initial_prompt_fulfillment: Option<CxFulfillment>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "repromptEventHandlers")]
+    pub reprompt_event_handlers: Option<Vec<CxEventHandler>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxFormParameter {
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "isList")]
+    pub is_list: Option<bool>,
+
+    #[serde(rename = "fillBehavior")]
+    pub fill_behavior: CxFillBehavior,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "defaultValue")]
+    pub default_value: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redact: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "advancedSettings")]
+    pub advanced_settings: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxForm {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Vec<CxFormParameter>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxPage {
+    pub name: String,
+
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "entryFulfillment")]
+    pub entry_fulfillment: Option<CxFulfillment>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub form: Option<CxForm>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "transitionRouteGroups")]
+    pub transition_route_groups: Option<Vec<String>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "transitionRoutes")]
+    pub transition_routes: Option<Vec<CxTransitionRoute>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "eventHandlers")]
+    pub event_handlers: Option<Vec<CxEventHandler>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "advancedSettings")]
+    pub advanced_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "knowledgeConnectorSettings")]
+    pub knowledge_connector_settings: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxPage {
+    pub fn fulfillments_mut(&mut self) -> Vec<&mut CxFulfillment> {
+        let mut fulfillments = vec![];
+        if let Some(fulfillment) = self.entry_fulfillment.as_mut() {
+            fulfillments.push(fulfillment);
+        }
+        if let Some(form) = self.form.as_mut() {
+            for parameter in form.parameters.iter_mut().flatten() {
+                let fill_behavior = &mut parameter.fill_behavior;
+                if let Some(fulfillment) = fill_behavior.initial_prompt_fulfillment.as_mut() {
+                    fulfillments.push(fulfillment);
+                }
+                for event_handler in fill_behavior.reprompt_event_handlers.iter_mut().flatten() {
+                    if let Some(fulfillment) = event_handler.trigger_fulfillment.as_mut() {
+                        fulfillments.push(fulfillment);
+                    }
+                }
+            }
+        }
+        fulfillments.extend(routes_fulfillments_mut(
+            &mut self.transition_routes,
+            &mut self.event_handlers,
+        ));
+        fulfillments
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxTransitionRouteGroup {
+    pub name: String,
+
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "transitionRoutes")]
+    pub transition_routes: Option<Vec<CxTransitionRoute>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxTransitionRouteGroup {
+    pub fn fulfillments_mut(&mut self) -> Vec<&mut CxFulfillment> {
+        self.transition_routes
+            .iter_mut()
+            .flatten()
+            .filter_map(|transition_route| transition_route.trigger_fulfillment.as_mut())
+            .collect()
+    }
+}
@@ -0,0 +1,335 @@
+use crate::google::dialogflow::payload::{payload_from_translation, PayloadRules};
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use crate::ssml::{speech_from_translation, speech_to_translation};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxText {
+    pub text: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowPlaybackInterruption")]
+    pub allow_playback_interruption: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxOutputAudioText {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssml: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "allowPlaybackInterruption")]
+    pub allow_playback_interruption: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxConversationSuccess {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+/// Single fulfillment message. Exactly one of the message fields is present,
+/// only `text` and `outputAudioText` contain user facing text, the rest is cloned as is.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxResponseMessage {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<CxText>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "conversationSuccess")]
+    pub conversation_success: Option<CxConversationSuccess>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "outputAudioText")]
+    pub output_audio_text: Option<CxOutputAudioText>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "liveAgentHandoff")]
+    pub live_agent_handoff: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "endInteraction")]
+    pub end_interaction: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "playAudio")]
+    pub play_audio: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "mixedAudio")]
+    pub mixed_audio: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "telephonyTransferCall")]
+    pub telephony_transfer_call: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxResponseMessage {
+    /// messages without language code belong to agent's default language
+    pub fn get_message_lang<'a>(&'a self, default_lang: &'a str) -> &'a str {
+        self.language_code.as_deref().unwrap_or(default_lang)
+    }
+
+    pub fn new_message(&self, lang: &str) -> Self {
+        let mut cloned = self.clone();
+        cloned.language_code = Some(lang.to_owned());
+        cloned
+    }
+
+    /// translation map of custom payload texts selected by payload rules
+    pub fn payload_to_translation(
+        &self,
+        payload_rules: &PayloadRules,
+    ) -> collections::HashMap<String, String> {
+        match &self.payload {
+            Some(payload) => payload_rules.to_translation(payload),
+            None => collections::HashMap::new(),
+        }
+    }
+}
+
+impl Translate for CxResponseMessage {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        if let Some(text) = &self.text {
+            for text_item in text.text.iter() {
+                map_to_translate.insert(format!("{:p}", text_item), text_item.to_owned());
+            }
+        }
+
+        if let Some(output_audio_text) = &self.output_audio_text {
+            if let Some(text) = &output_audio_text.text {
+                map_to_translate.insert(format!("{:p}", text), text.to_owned());
+            }
+            if let Some(ssml) = &output_audio_text.ssml {
+                map_to_translate.extend(speech_to_translation(ssml));
+            }
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(text) = self.text.as_mut() {
+            for text_item in text.text.iter_mut() {
+                if let Some(translated) = translations_map.get(&format!("{:p}", text_item)) {
+                    *text_item = translated.to_owned();
+                }
+            }
+        }
+
+        if let Some(payload) = self.payload.as_mut() {
+            payload_from_translation(payload, translations_map);
+        }
+
+        if let Some(output_audio_text) = self.output_audio_text.as_mut() {
+            if let Some(text) = output_audio_text.text.as_mut() {
+                if let Some(translated) = translations_map.get(&format!("{:p}", text)) {
+                    *text = translated.to_owned();
+                }
+            }
+            if let Some(ssml) = output_audio_text.ssml.as_mut() {
+                speech_from_translation(ssml, translations_map);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxCaseContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<CxResponseMessage>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "additionalCases")]
+    pub additional_cases: Option<CxConditionalCases>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxCase {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "caseContent")]
+    pub case_content: Option<Vec<CxCaseContent>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxConditionalCases {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cases: Option<Vec<CxCase>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxConditionalCases {
+    fn messages_mut(&mut self) -> Vec<&mut CxResponseMessage> {
+        let mut messages = vec![];
+        for case in self.cases.iter_mut().flatten() {
+            for case_content in case.case_content.iter_mut().flatten() {
+                if let Some(message) = case_content.message.as_mut() {
+                    messages.push(message);
+                }
+                if let Some(additional_cases) = case_content.additional_cases.as_mut() {
+                    messages.extend(additional_cases.messages_mut());
+                }
+            }
+        }
+        messages
+    }
+
+    fn add_language(&mut self, default_lang: &str, lang_from: &str, lang_to: &str) {
+        for case in self.cases.iter_mut().flatten() {
+            if let Some(case_contents) = case.case_content.as_mut() {
+                let mut new_case_contents = vec![];
+                for case_content in case_contents.iter_mut() {
+                    if let Some(message) = &case_content.message {
+                        if message.get_message_lang(default_lang) == lang_from {
+                            new_case_contents.push(CxCaseContent {
+                                message: Some(message.new_message(lang_to)),
+                                additional_cases: None,
+                                extra: case_content.extra.clone(),
+                            });
+                        }
+                    }
+                    if let Some(additional_cases) = case_content.additional_cases.as_mut() {
+                        additional_cases.add_language(default_lang, lang_from, lang_to);
+                    }
+                }
+                case_contents.extend(new_case_contents);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxFulfillment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<CxResponseMessage>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "returnPartialResponses")]
+    pub return_partial_responses: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "setParameterActions")]
+    pub set_parameter_actions: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "conditionalCases")]
+    pub conditional_cases: Option<Vec<CxConditionalCases>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "advancedSettings")]
+    pub advanced_settings: Option<JsonValue>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "enableGenerativeFallback")]
+    pub enable_generative_fallback: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxFulfillment {
+    /// all messages of the fulfillment including messages nested in conditional cases
+    pub fn messages_mut(&mut self) -> Vec<&mut CxResponseMessage> {
+        let mut messages: Vec<&mut CxResponseMessage> =
+            self.messages.iter_mut().flatten().collect();
+        for conditional_cases in self.conditional_cases.iter_mut().flatten() {
+            messages.extend(conditional_cases.messages_mut());
+        }
+        messages
+    }
+
+    /// clones all messages in source language into target language.
+    /// Fulfillments which already have some message in target language are left untouched (returns false).
+    pub fn add_language(&mut self, default_lang: &str, lang_from: &str, lang_to: &str) -> bool {
+        if self
+            .messages_mut()
+            .iter()
+            .any(|message| message.get_message_lang(default_lang) == lang_to)
+        {
+            return false;
+        }
+
+        if let Some(messages) = self.messages.as_mut() {
+            let new_messages: Vec<CxResponseMessage> = messages
+                .iter()
+                .filter(|message| message.get_message_lang(default_lang) == lang_from)
+                .map(|message| message.new_message(lang_to))
+                .collect();
+            messages.extend(new_messages);
+        }
+
+        for conditional_cases in self.conditional_cases.iter_mut().flatten() {
+            conditional_cases.add_language(default_lang, lang_from, lang_to);
+        }
+        true
+    }
+
+    /// translation map of all messages in given language
+    pub fn to_translation(
+        &mut self,
+        default_lang: &str,
+        lang: &str,
+        payload_rules: &PayloadRules,
+    ) -> collections::HashMap<String, String> {
+        let mut translations_map = collections::HashMap::new();
+        for message in self.messages_mut() {
+            if message.get_message_lang(default_lang) == lang {
+                translations_map.extend(message.to_translation());
+                translations_map.extend(message.payload_to_translation(payload_rules));
+            }
+        }
+        translations_map
+    }
+
+    pub fn from_translation(
+        &mut self,
+        translations_map: &collections::HashMap<String, String>,
+        default_lang: &str,
+        lang: &str,
+    ) {
+        for message in self.messages_mut() {
+            if message.get_message_lang(default_lang) == lang {
+                message.from_translation(translations_map);
+            }
+        }
+    }
+}
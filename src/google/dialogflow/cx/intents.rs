@@ -0,0 +1,139 @@
+use crate::google::dialogflow::ids::new_uuid;
+use crate::google::dialogflow::options::ClonedMetadata;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxIntentParameter {
+    pub id: String,
+
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "isList")]
+    pub is_list: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redact: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxIntent {
+    pub name: String,
+
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "isFallback")]
+    pub is_fallback: Option<bool>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Vec<CxIntentParameter>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxTrainingPhrasePart {
+    pub text: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "parameterId")]
+    pub parameter_id: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto: Option<bool>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for CxTrainingPhrasePart {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+        map_to_translate.insert(format!("{:p}", &self.text), self.text.to_owned());
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(text) = translations_map.get(&format!("{:p}", &self.text)) {
+            self.text = text.to_owned()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxTrainingPhrase {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+
+    pub parts: Vec<CxTrainingPhrasePart>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "repeatCount")]
+    pub repeat_count: Option<i64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "languageCode")]
+    pub language_code: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+/// content of intents/<<intent>>/trainingPhrases/<<lang>>.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CxTrainingPhrases {
+    #[serde(rename = "trainingPhrases")]
+    pub training_phrases: Vec<CxTrainingPhrase>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl CxTrainingPhrases {
+    /// clones training phrases to new language, unless metadata are kept cloned phrases get new IDs
+    pub fn to_new_language(&self, new_lang_code: &str, metadata: &ClonedMetadata) -> Self {
+        let mut cloned = self.clone();
+        for training_phrase in cloned.training_phrases.iter_mut() {
+            training_phrase.language_code = Some(new_lang_code.to_owned());
+            if !metadata.keep && training_phrase.id.is_some() {
+                training_phrase.id = Some(new_uuid());
+            }
+        }
+        cloned
+    }
+}
+
+impl Translate for CxTrainingPhrases {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+        for training_phrase in self.training_phrases.iter() {
+            for part in training_phrase.parts.iter() {
+                map_to_translate.extend(part.to_translation());
+            }
+        }
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        for training_phrase in self.training_phrases.iter_mut() {
+            for part in training_phrase.parts.iter_mut() {
+                part.from_translation(translations_map);
+            }
+        }
+    }
+}
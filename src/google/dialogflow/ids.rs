@@ -0,0 +1,81 @@
+//! # IDs of agent objects
+//!
+//! Objects cloned to target language (e.g. training phrases) must not share IDs with
+//! source language objects. New IDs are random UUIDs (version 4) generated without pulling in
+//! extra crate.
+use crate::random_u64;
+use std::collections;
+use std::time::SystemTime;
+
+/// random UUID, e.g. 9dfa147d-d2d8-4703-a693-8edef11322a2
+pub fn new_uuid() -> String {
+    let mut bytes = [0u8; 16];
+    bytes[..8].copy_from_slice(&random_u64().to_be_bytes());
+    bytes[8..].copy_from_slice(&random_u64().to_be_bytes());
+    bytes[6] = (bytes[6] & 0x0f) | 0x40; // version 4
+    bytes[8] = (bytes[8] & 0x3f) | 0x80; // RFC 4122 variant
+
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// current time as epoch seconds
+pub fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64
+}
+
+/// IDs present more than once together with files where they are used, sorted by ID
+pub fn duplicated_ids<'a>(
+    ids: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Vec<(String, Vec<String>)> {
+    let mut files_by_id: collections::BTreeMap<&str, Vec<String>> = collections::BTreeMap::new();
+    for (id, file) in ids {
+        files_by_id.entry(id).or_default().push(file.to_owned());
+    }
+    files_by_id
+        .into_iter()
+        .filter(|(_, files)| files.len() > 1)
+        .map(|(id, files)| (id.to_owned(), files))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cargo test -- --show-output test_ids
+    #[test]
+    fn test_ids() {
+        let uuid = new_uuid();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "4");
+        assert!("89ab".contains(&uuid[19..20]));
+        assert_ne!(uuid, new_uuid());
+
+        let ids = vec![
+            ("b", "intent_usersays_en.json"),
+            ("a", "intent_usersays_en.json"),
+            ("b", "intent_usersays_de.json"),
+        ];
+        assert_eq!(
+            duplicated_ids(ids.into_iter()),
+            vec![(
+                "b".to_owned(),
+                vec![
+                    "intent_usersays_en.json".to_owned(),
+                    "intent_usersays_de.json".to_owned()
+                ]
+            )]
+        );
+    }
+}
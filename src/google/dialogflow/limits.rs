@@ -0,0 +1,254 @@
+//! # Length limits of channels
+//!
+//! Channels limit length of some texts (e.g. suggestion chips of Google Assistant or quick replies
+//! of Facebook Messenger). Dialogflow accepts longer texts silently but the channel truncates
+//! or rejects them, translations into languages like German or Finnish exceed the limits often.
+//! Translated texts are checked against the limits below and every violation is reported.
+//!
+//! Texts exceeding the limit can be shortened by abbreviations supplied by user. Abbreviation file
+//! is JSON object where keys are target languages and values are objects mapping phrases
+//! to their abbreviations, e.g.
+//!
+//! ```json
+//! {
+//!   "de": { "Informationen": "Infos", "Sendungsverfolgung": "Tracking" }
+//! }
+//! ```
+//!
+//! Abbreviations (longer phrases first) are applied one by one until the text fits the limit.
+//! Text which does not fit even with all abbreviations applied is left as translated and reported.
+//! Quick replies which Dialogflow does not accept longer than 20 characters at all can be
+//! truncated instead (see `truncate_quick_replies` of agent options).
+use crate::errors::Result;
+use regex::Regex;
+use std::collections;
+use std::fs;
+use std::path::Path;
+
+/// maximal number of characters per platform and field, `*` stands for any platform
+/// (see [length_limited_texts_mut](../responses/enum.MessageType.html#method.length_limited_texts_mut))
+const LENGTH_LIMITS: [(&str, &str, usize); 9] = [
+    ("*", "quick_reply", 20),
+    ("google", "suggestion_chip", 25),
+    ("google", "simple_response.display_text", 640),
+    ("facebook", "card.title", 80),
+    ("facebook", "card.subtitle", 80),
+    ("facebook", "card.button", 20),
+    ("rbm", "suggestion", 25),
+    ("rbm", "card.title", 200),
+    ("rbm", "card.description", 2000),
+];
+
+/// maximal number of characters of given field, None if the field is not limited
+pub fn length_limit(platform: &str, field: &str) -> Option<usize> {
+    LENGTH_LIMITS
+        .iter()
+        .find(|(limit_platform, limit_field, _)| {
+            (*limit_platform == platform || *limit_platform == "*") && *limit_field == field
+        })
+        .map(|(_, _, limit)| *limit)
+}
+
+/// true if Dialogflow itself rejects longer texts of given field, such texts can be truncated
+pub fn is_truncated(field: &str) -> bool {
+    field == "quick_reply"
+}
+
+/// first `limit` characters of the text, cut at the last word boundary if there is one
+pub fn truncate(text: &str, limit: usize) -> String {
+    let truncated: String = text.chars().take(limit).collect();
+    let next_char = text.chars().nth(limit);
+    match truncated.rfind(char::is_whitespace) {
+        Some(idx) if matches!(next_char, Some(c) if !c.is_whitespace()) => {
+            truncated[..idx].trim_end().to_owned()
+        }
+        _ => truncated.trim_end().to_owned(),
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Abbreviations {
+    abbreviations: collections::HashMap<String, Vec<(Regex, String)>>,
+}
+
+impl Abbreviations {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Abbreviations::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let abbreviations: collections::HashMap<String, collections::HashMap<String, String>> =
+            serde_json::from_str(json)?;
+        let mut result = Abbreviations::default();
+        for (lang, lang_abbreviations) in abbreviations.into_iter() {
+            let mut lang_abbreviations: Vec<(String, String)> =
+                lang_abbreviations.into_iter().collect();
+            lang_abbreviations.sort_by_key(|(phrase, _)| {
+                (std::cmp::Reverse(phrase.chars().count()), phrase.to_owned())
+            });
+            result.abbreviations.insert(
+                lang.to_lowercase(),
+                lang_abbreviations
+                    .into_iter()
+                    .map(|(phrase, abbreviation)| {
+                        // phrase is replaced only as whole word(s)
+                        let regex = format!(r"\b{}\b", regex::escape(&phrase));
+                        (Regex::new(&regex).unwrap(), abbreviation)
+                    })
+                    .collect(),
+            );
+        }
+        Ok(result)
+    }
+
+    /// text shortened to given number of characters, None if the text can't be shortened enough.
+    /// Abbreviations of specific language (e.g. pt-br) take precedence over base language (e.g. pt).
+    pub fn shorten(&self, text: &str, lang: &str, limit: usize) -> Option<String> {
+        let lang = lang.to_lowercase();
+        let abbreviations = self.abbreviations.get(&lang).or_else(|| {
+            lang.split_once('-')
+                .and_then(|(base_lang, _)| self.abbreviations.get(base_lang))
+        })?;
+
+        let mut shortened = text.to_owned();
+        for (regex, abbreviation) in abbreviations.iter() {
+            if shortened.chars().count() <= limit {
+                break;
+            }
+            shortened = regex
+                .replace_all(&shortened, regex::NoExpand(abbreviation))
+                .to_string();
+        }
+
+        if shortened.chars().count() <= limit {
+            Some(shortened)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::dialogflow::responses::MessageType;
+
+    // cargo test -- --show-output test_length_limits
+    #[test]
+    fn test_length_limits() -> Result<()> {
+        assert_eq!(length_limit("google", "suggestion_chip"), Some(25));
+        assert_eq!(length_limit("facebook", "quick_reply"), Some(20));
+        assert_eq!(length_limit("telegram", "quick_reply"), Some(20));
+        assert_eq!(length_limit("telegram", "suggestion_chip"), None);
+        assert!(is_truncated("quick_reply"));
+        assert!(!is_truncated("suggestion_chip"));
+        assert_eq!(truncate("Weitere Informationen", 8), "Weitere");
+        assert_eq!(truncate("Weitere Informationen", 12), "Weitere");
+        assert_eq!(truncate("Sendungsverfolgung", 10), "Sendungsve");
+        assert_eq!(truncate("Straße", 10), "Straße");
+
+        let abbreviations = Abbreviations::parse(
+            r#"
+            {
+              "de": {
+                "Informationen": "Infos",
+                "Sendungsverfolgung": "Tracking",
+                "Sendung": "Paket"
+              }
+            }
+            "#,
+        )?;
+
+        // longer phrase first, phrase is replaced only as whole word
+        assert_eq!(
+            abbreviations.shorten("Sendungsverfolgung starten", "de", 20),
+            Some("Tracking starten".to_owned())
+        );
+        // abbreviations are applied only until text fits
+        assert_eq!(
+            abbreviations.shorten("Informationen zur Sendungsverfolgung", "de-AT", 30),
+            Some("Informationen zur Tracking".to_owned())
+        );
+        assert_eq!(
+            abbreviations.shorten("Weitere Informationen anzeigen", "de", 10),
+            None
+        );
+        assert_eq!(
+            abbreviations.shorten("Weitere Informationen anzeigen", "fr", 25),
+            None
+        );
+        assert!(Abbreviations::parse(r#"{ "de": [ "Infos" ] }"#).is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_limit_message_texts
+    #[test]
+    fn test_limit_message_texts() -> Result<()> {
+        let abbreviations =
+            Abbreviations::parse(r#"{ "de": { "Sendungsverfolgung": "Tracking" } }"#)?;
+        let mut messages: Vec<MessageType> = serde_json::from_str(
+            r#"
+            [
+              {
+                "type": "suggestion_chips",
+                "platform": "google",
+                "lang": "de",
+                "suggestions": [
+                  { "title": "Sendungsverfolgung starten" },
+                  { "title": "Weitere Informationen zur Lieferung" },
+                  { "title": "Ja" }
+                ]
+              },
+              {
+                "type": "1",
+                "platform": "facebook",
+                "lang": "de",
+                "title": "Lampen",
+                "subtitle": "Wohnzimmer",
+                "buttons": [ { "text": "Bedienungsanleitung öffnen", "postback": "Anleitung" } ]
+              },
+              {
+                "type": "2",
+                "platform": "telegram",
+                "lang": "de",
+                "title": "Wie weiter?",
+                "replies": [ "Weitere Informationen anzeigen" ]
+              },
+              { "type": "0", "lang": "de", "speech": "Weitere Informationen zur Lieferung" }
+            ]
+            "#,
+        )?;
+
+        let mut findings = vec![];
+        for message in messages.iter_mut() {
+            let platform = message.get_platform().to_owned();
+            for (field, text) in message.length_limited_texts_mut() {
+                let limit = match length_limit(&platform, field) {
+                    Some(limit) if text.chars().count() > limit => limit,
+                    _ => continue,
+                };
+                match abbreviations.shorten(text, "de", limit) {
+                    Some(shortened) => *text = shortened,
+                    None if is_truncated(field) => *text = truncate(text, limit),
+                    None => {}
+                }
+                findings.push(format!("{} {} {}", platform, field, text));
+            }
+        }
+        assert_eq!(
+            findings,
+            vec![
+                "google suggestion_chip Tracking starten",
+                "google suggestion_chip Weitere Informationen zur Lieferung",
+                "facebook card.button Bedienungsanleitung öffnen",
+                "telegram quick_reply Weitere",
+            ]
+        );
+
+        let messages = serde_json::to_value(&messages)?;
+        assert_eq!(messages[0]["suggestions"][0]["title"], "Tracking starten");
+        assert_eq!(messages[2]["replies"][0], "Weitere");
+        assert_eq!(messages[3]["speech"], "Weitere Informationen zur Lieferung");
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+//! # Localization of image and media URLs
+//!
+//! Images and other media referenced by responses (e.g. banners with texts) are often localized.
+//! Mapping file is JSON object where keys are source URLs and values are objects mapping
+//! language codes to localized URLs, e.g.
+//!
+//! ```json
+//! {
+//!   "https://cdn.example.com/banner_en.png": { "de": "https://cdn.example.com/banner_de.png" },
+//!   "https://cdn.example.com/en/*": { "de": "https://cdn.example.com/de/*" }
+//! }
+//! ```
+//!
+//! Key containing `*` is pattern, `*` matches any text which is then substituted for `*`
+//! in the localized URL. Exact URLs take precedence over patterns, longer patterns
+//! take precedence over shorter ones.
+use crate::errors::{Error, Result};
+use regex::Regex;
+use std::collections;
+use std::fs;
+use std::path::Path;
+
+type LocalizedUrls = collections::HashMap<String, String>;
+
+#[derive(Debug, Default, Clone)]
+pub struct MediaUrls {
+    exact: collections::HashMap<String, LocalizedUrls>,
+    patterns: Vec<(Regex, LocalizedUrls)>,
+}
+
+impl MediaUrls {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        MediaUrls::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let mapping: collections::HashMap<String, LocalizedUrls> = serde_json::from_str(json)?;
+        let mut media_urls = MediaUrls::default();
+        let mut patterns = vec![];
+
+        for (url, localized) in mapping.into_iter() {
+            let localized: LocalizedUrls = localized
+                .into_iter()
+                .map(|(lang, url)| (lang.to_lowercase(), url))
+                .collect();
+            if !url.contains('*') {
+                media_urls.exact.insert(url, localized);
+                continue;
+            }
+
+            let wildcards = url.matches('*').count();
+            for localized_url in localized.values() {
+                if localized_url.contains('*') && localized_url.matches('*').count() != wildcards {
+                    return Err(Error::new(format!(
+                        "localized URL {} must contain the same number of * as {}",
+                        localized_url, url
+                    )));
+                }
+            }
+            let regex = format!(
+                "^{}$",
+                url.split('*')
+                    .map(regex::escape)
+                    .collect::<Vec<String>>()
+                    .join("(.*)")
+            );
+            patterns.push((url.len(), Regex::new(&regex).unwrap(), localized));
+        }
+
+        patterns.sort_by_key(|(len, _, _)| std::cmp::Reverse(*len));
+        media_urls.patterns = patterns
+            .into_iter()
+            .map(|(_, regex, localized)| (regex, localized))
+            .collect();
+        Ok(media_urls)
+    }
+
+    /// localized URL for given language, language specific URL (e.g. pt-br)
+    /// takes precedence over URL of base language (e.g. pt)
+    fn localized_url(localized: &LocalizedUrls, lang: &str) -> Option<String> {
+        let lang = lang.to_lowercase();
+        localized
+            .get(&lang)
+            .or_else(|| {
+                lang.split_once('-')
+                    .and_then(|(base_lang, _)| localized.get(base_lang))
+            })
+            .cloned()
+    }
+
+    /// localized counterpart of URL, None if there is no mapping for the URL and language
+    pub fn localize(&self, url: &str, lang: &str) -> Option<String> {
+        if let Some(localized) = self.exact.get(url) {
+            return MediaUrls::localized_url(localized, lang);
+        }
+
+        for (regex, localized) in self.patterns.iter() {
+            if let Some(caps) = regex.captures(url) {
+                let localized_url = MediaUrls::localized_url(localized, lang)?;
+                let mut result = String::new();
+                for (idx, part) in localized_url.split('*').enumerate() {
+                    if idx > 0 {
+                        result.push_str(&caps[idx]);
+                    }
+                    result.push_str(part);
+                }
+                return Some(result);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::dialogflow::responses::MessageType;
+
+    // cargo test -- --show-output test_media_urls
+    #[test]
+    fn test_media_urls() -> Result<()> {
+        let media_urls = MediaUrls::parse(
+            r#"
+            {
+              "https://cdn.example.com/banner_en.png": {
+                "de": "https://cdn.example.com/banner_de.png",
+                "pt": "https://cdn.example.com/banner_pt.png",
+                "pt-BR": "https://cdn.example.com/banner_br.png"
+              },
+              "https://cdn.example.com/*": { "de": "https://cdn.example.com/de/*" },
+              "https://cdn.example.com/en/*.png": { "de": "https://cdn.example.com/de/*.jpg" }
+            }
+            "#,
+        )?;
+
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/banner_en.png", "de"),
+            Some("https://cdn.example.com/banner_de.png".to_owned())
+        );
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/banner_en.png", "pt-br"),
+            Some("https://cdn.example.com/banner_br.png".to_owned())
+        );
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/banner_en.png", "pt-pt"),
+            Some("https://cdn.example.com/banner_pt.png".to_owned())
+        );
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/banner_en.png", "fr"),
+            None
+        );
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/en/logo.png", "de"),
+            Some("https://cdn.example.com/de/logo.jpg".to_owned())
+        );
+        assert_eq!(
+            media_urls.localize("https://cdn.example.com/img/logo.gif", "de"),
+            Some("https://cdn.example.com/de/img/logo.gif".to_owned())
+        );
+        assert_eq!(
+            media_urls.localize("https://example.com/logo.gif", "de"),
+            None
+        );
+
+        assert!(MediaUrls::parse(r#"{ "https://cdn.example.com/*": { "de": "*/*" } }"#).is_err());
+        assert!(MediaUrls::parse(r#"[ "https://cdn.example.com/" ]"#).is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_localize_message_media_urls
+    #[test]
+    fn test_localize_message_media_urls() -> Result<()> {
+        let media_urls = MediaUrls::parse(
+            r#"{ "https://cdn.example.com/en/*": { "de": "https://cdn.example.com/de/*" } }"#,
+        )?;
+        let mut messages: Vec<MessageType> = serde_json::from_str(
+            r#"
+            [
+              {
+                "type": "3",
+                "platform": "facebook",
+                "lang": "en",
+                "imageUrl": "https://cdn.example.com/en/banner.png"
+              },
+              {
+                "type": "media_content",
+                "platform": "google",
+                "lang": "en",
+                "mediaType": "AUDIO",
+                "mediaObjects": [
+                  {
+                    "name": "Welcome",
+                    "description": "Welcome message",
+                    "largeImage": { "url": "https://cdn.example.com/en/cover.png", "accessibilityText": "cover" },
+                    "contentUrl": "https://cdn.example.com/en/welcome.mp3"
+                  }
+                ]
+              },
+              {
+                "type": "rbm_standalone_rich_card",
+                "platform": "rbm",
+                "lang": "en",
+                "cardOrientation": "VERTICAL",
+                "cardContent": {
+                  "title": "Summer sale",
+                  "media": { "fileUri": "https://cdn.example.com/en/sale.png", "height": "MEDIUM" },
+                  "suggestions": [
+                    { "action": { "text": "Open shop", "postbackData": "OPEN_SHOP", "openUrl": { "uri": "https://cdn.example.com/en/shop" } } }
+                  ]
+                }
+              },
+              {
+                "type": "telephony_play_audio",
+                "platform": "telephony",
+                "lang": "en",
+                "audioUri": "gs://audio/welcome.wav"
+              },
+              { "type": "0", "lang": "en", "speech": "https://cdn.example.com/en/banner.png" }
+            ]
+            "#,
+        )?;
+
+        let mut not_localized = vec![];
+        for message in messages.iter_mut() {
+            for url in message.media_urls_mut() {
+                match media_urls.localize(url, "de") {
+                    Some(localized_url) => *url = localized_url,
+                    None => not_localized.push(url.to_owned()),
+                }
+            }
+        }
+        assert_eq!(not_localized, vec!["gs://audio/welcome.wav"]);
+
+        let messages = serde_json::to_value(&messages)?;
+        assert_eq!(
+            messages[0]["imageUrl"],
+            "https://cdn.example.com/de/banner.png"
+        );
+        let media_object = &messages[1]["mediaObjects"][0];
+        assert_eq!(
+            media_object["largeImage"]["url"],
+            "https://cdn.example.com/de/cover.png"
+        );
+        assert_eq!(
+            media_object["contentUrl"],
+            "https://cdn.example.com/de/welcome.mp3"
+        );
+        let card_content = &messages[2]["cardContent"];
+        assert_eq!(
+            card_content["media"]["fileUri"],
+            "https://cdn.example.com/de/sale.png"
+        );
+        // linked pages and texts are not media
+        assert_eq!(
+            card_content["suggestions"][0]["action"]["openUrl"]["uri"],
+            "https://cdn.example.com/en/shop"
+        );
+        assert_eq!(
+            messages[4]["speech"],
+            "https://cdn.example.com/en/banner.png"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+//! # Options of agent processing
+//!
+//! Options driving how agent content is handled during translation (as opposed to
+//! options of translation API). Options are same for ES and CX agents,
+//! translation providers just pass them to the agent.
+use crate::errors::{Error, Result};
+use crate::google::dialogflow::limits::Abbreviations;
+use crate::google::dialogflow::media::MediaUrls;
+use crate::google::dialogflow::payload::PayloadRules;
+use crate::google::dialogflow::url_rewrites::UrlRewrites;
+use std::collections;
+
+/// Default value means agent is translated without any extra processing.
+#[derive(Debug, Default, Clone)]
+pub struct AgentOptions {
+    /// translate agent description, short description and examples.
+    /// Agent manifest has no language variants of these texts so they are translated in place.
+    pub translate_manifest: bool,
+
+    /// translate literal default values of intent parameters. Default value has no language variants
+    /// either so it is translated in place, otherwise it is kept and reported.
+    pub translate_default_values: bool,
+
+    /// time zone of the agent set when target language is added, e.g. Europe/Berlin
+    pub time_zone: Option<String>,
+
+    /// spell correction setting of the agent set when target language is added
+    pub spell_correction: Option<bool>,
+
+    /// whether reference values of entities are translated or kept
+    pub entity_values: EntityValues,
+
+    /// normalization of translated entity synonyms, None means synonyms are left as translated
+    pub synonyms_normalization: Option<SynonymsNormalization>,
+
+    /// metadata (ID, counter, timestamp) of training phrases cloned to target language
+    pub cloned_metadata: ClonedMetadata,
+
+    /// rules selecting texts of custom payloads to translate
+    pub payload_rules: PayloadRules,
+
+    /// localized URLs of images and media of cloned messages, None means URLs are copied unchanged
+    pub media_urls: Option<MediaUrls>,
+
+    /// platforms (channels) of responses to translate
+    pub platforms: Platforms,
+
+    /// rewrite rules of URLs linked by cloned messages, None means URLs are copied unchanged
+    pub url_rewrites: Option<UrlRewrites>,
+
+    /// abbreviations shortening translated texts which exceed length limit of their channel,
+    /// None means such texts are only reported
+    pub abbreviations: Option<Abbreviations>,
+
+    /// truncate quick replies exceeding their length limit even with abbreviations applied,
+    /// false means such quick replies are only reported
+    pub truncate_quick_replies: bool,
+
+    /// fail on fields of exported agent unknown to gdf_translate instead of copying them
+    /// unchanged to translated agent (and reporting them)
+    pub strict: bool,
+}
+
+/// Platforms (channels) of ES responses to translate, e.g. default, google, facebook.
+/// Default value selects all platforms.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Platforms {
+    /// selected platforms, empty means all platforms
+    included: Vec<String>,
+
+    /// platforms never translated
+    excluded: Vec<String>,
+
+    /// responses of platforms which are not selected are copied to target language untranslated,
+    /// otherwise target language gets no responses for these platforms
+    pub copy_skipped: bool,
+}
+
+impl Platforms {
+    pub fn new(included: &[&str], excluded: &[&str], copy_skipped: bool) -> Self {
+        let normalize = |platforms: &[&str]| {
+            platforms
+                .iter()
+                .map(|platform| platform.trim().to_lowercase())
+                .filter(|platform| !platform.is_empty())
+                .collect()
+        };
+        Platforms {
+            included: normalize(included),
+            excluded: normalize(excluded),
+            copy_skipped,
+        }
+    }
+
+    /// true if responses of given platform should be translated
+    pub fn is_selected(&self, platform: &str) -> bool {
+        let platform = platform.to_lowercase();
+        (self.included.is_empty() || self.included.contains(&platform))
+            && !self.excluded.contains(&platform)
+    }
+
+    /// true if responses of given platform are cloned to target language, translated or not
+    pub fn is_cloned(&self, platform: &str) -> bool {
+        self.copy_skipped || self.is_selected(platform)
+    }
+}
+
+/// Metadata of cloned training phrases. By default cloned phrases get new IDs,
+/// zero counter and current timestamp so that they are not taken for edits of source phrases.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ClonedMetadata {
+    /// keep ID, counter and timestamp of source phrase (i.e. original behaviour), intended for debugging
+    pub keep: bool,
+
+    /// counter of cloned phrase (ES only)
+    pub count: i8,
+
+    /// timestamp (epoch seconds) of cloned phrase (ES only), None means current time
+    pub updated: Option<i64>,
+}
+
+/// how synonyms are compared when looking for duplicates
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum SynonymsDedupe {
+    Exact,
+    #[default]
+    IgnoreCase,
+    /// ignores case and diacritics, e.g. Cafe and café are the same
+    IgnoreCaseDiacritics,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SynonymsNormalization {
+    pub dedupe: SynonymsDedupe,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum EntityValuesMode {
+    /// reference value is kept as in source language, only synonyms are translated
+    Keep,
+    #[default]
+    Translate,
+}
+
+impl EntityValuesMode {
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "keep" => Some(EntityValuesMode::Keep),
+            "translate" => Some(EntityValuesMode::Translate),
+            _ => None,
+        }
+    }
+}
+
+/// Mode of entity reference values for whole run and for particular entities.
+/// Parsed from --entity-values option which is either MODE or ENTITY=MODE
+/// (MODE being keep or translate), e.g. --entity-values keep --entity-values city=translate
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EntityValues {
+    default_mode: Option<EntityValuesMode>,
+    entity_modes: collections::HashMap<String, EntityValuesMode>,
+}
+
+impl EntityValues {
+    pub fn parse(values: &[&str]) -> Result<Self> {
+        let mut entity_values = EntityValues::default();
+        for value in values.iter().map(|value| value.trim()) {
+            match value.split_once('=') {
+                Some((entity, mode)) if !entity.trim().is_empty() => {
+                    match EntityValuesMode::parse(mode) {
+                        Some(mode) => {
+                            entity_values
+                                .entity_modes
+                                .insert(entity.trim().to_owned(), mode);
+                        }
+                        None => {
+                            return Err(Error::new(format!(
+                                "invalid entity values mode {}, expected keep or translate",
+                                mode
+                            )))
+                        }
+                    }
+                }
+                None => match EntityValuesMode::parse(value) {
+                    Some(_) if entity_values.default_mode.is_some() => {
+                        return Err(Error::new(format!(
+                            "entity values mode {} specified more than once without entity name",
+                            value
+                        )));
+                    }
+                    Some(mode) => entity_values.default_mode = Some(mode),
+                    None => {
+                        return Err(Error::new(format!(
+                            "invalid entity values {}, expected MODE or ENTITY=MODE (MODE being keep or translate)",
+                            value
+                        )))
+                    }
+                },
+                _ => {
+                    return Err(Error::new(format!(
+                        "invalid entity values {}, expected MODE or ENTITY=MODE (MODE being keep or translate)",
+                        value
+                    )))
+                }
+            }
+        }
+        Ok(entity_values)
+    }
+
+    /// mode of given entity, entity specific mode takes precedence over mode of the run
+    pub fn mode_for(&self, entity_name: &str) -> EntityValuesMode {
+        self.entity_modes
+            .get(entity_name)
+            .copied()
+            .or(self.default_mode)
+            .unwrap_or_default()
+    }
+
+    pub fn keep_values(&self, entity_name: &str) -> bool {
+        self.mode_for(entity_name) == EntityValuesMode::Keep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cargo test -- --show-output test_entity_values_parse
+    #[test]
+    fn test_entity_values_parse() -> Result<()> {
+        let entity_values = EntityValues::parse(&[])?;
+        assert!(!entity_values.keep_values("delivery-type"));
+
+        let entity_values = EntityValues::parse(&["keep", "city = translate"])?;
+        assert!(entity_values.keep_values("delivery-type"));
+        assert!(!entity_values.keep_values("city"));
+
+        let entity_values = EntityValues::parse(&["delivery-type=keep"])?;
+        assert!(entity_values.keep_values("delivery-type"));
+        assert!(!entity_values.keep_values("city"));
+
+        assert!(EntityValues::parse(&["keep", "translate"]).is_err());
+        assert!(EntityValues::parse(&["city=copy"]).is_err());
+        assert!(EntityValues::parse(&["=keep"]).is_err());
+        assert!(EntityValues::parse(&["copy"]).is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_platforms
+    #[test]
+    fn test_platforms() {
+        let platforms = Platforms::default();
+        assert!(platforms.is_selected("default"));
+        assert!(platforms.is_selected("facebook"));
+
+        let platforms = Platforms::new(&["default", " Facebook"], &[], false);
+        assert!(platforms.is_selected("default"));
+        assert!(platforms.is_selected("facebook"));
+        assert!(!platforms.is_selected("google"));
+        // responses of platforms which are not selected are dropped
+        assert!(platforms.is_cloned("facebook"));
+        assert!(!platforms.is_cloned("google"));
+
+        let platforms = Platforms::new(&[], &["slack", "SKYPE"], true);
+        assert!(platforms.is_selected("google"));
+        assert!(!platforms.is_selected("slack"));
+        assert!(!platforms.is_selected("skype"));
+        assert!(platforms.copy_skipped);
+        // ... unless they are copied untranslated
+        assert!(platforms.is_cloned("slack"));
+    }
+}
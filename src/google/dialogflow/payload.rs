@@ -0,0 +1,339 @@
+//! # Translation of custom payloads
+//!
+//! Custom payloads are free form JSON (e.g. quick replies or carousels of web chat) so it is not known
+//! which strings are user facing texts. Texts to translate are selected by user configured rules,
+//! either JSONPath (subset: `$`, `.key`, `['key']`, `[0]`, `[*]`, `.*`, `..key`, `..*`),
+//! e.g. `$.richContent[*][*].options[*].text`, or JSON Pointer where `*` matches any key/index,
+//! e.g. `/richContent/*/*/options/*/text`. Selected strings stored under keys holding URLs, IDs,
+//! postbacks etc. (and strings looking like URLs) are never translated.
+use crate::errors::{Error, Result};
+use serde_json::Value as JsonValue;
+use std::collections;
+
+/// keys whose values are never translated even if selected by some rule
+const EXCLUDED_KEYS: &[&str] = &[
+    "id", "link", "href", "postback", "payload", "event", "action", "type", "platform",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    /// object key or array index
+    Child(String),
+    /// all children of object or array
+    Wildcard,
+    /// object key at any depth
+    Descendant(String),
+    /// all descendants
+    DescendantWildcard,
+}
+
+/// single rule selecting strings of custom payload
+#[derive(Debug, Clone, PartialEq)]
+pub struct PayloadRule {
+    segments: Vec<Segment>,
+}
+
+impl PayloadRule {
+    pub fn parse(rule: &str) -> Result<Self> {
+        let rule = rule.trim();
+        let segments = if rule.starts_with('$') {
+            PayloadRule::parse_json_path(rule)?
+        } else if rule.starts_with('/') {
+            PayloadRule::parse_json_pointer(rule)
+        } else {
+            return Err(Error::new(format!(
+                "payload rule '{}' must be JSONPath (starting with $) or JSON Pointer (starting with /)",
+                rule
+            )));
+        };
+        Ok(PayloadRule { segments })
+    }
+
+    fn parse_json_pointer(rule: &str) -> Vec<Segment> {
+        rule.split('/')
+            .skip(1)
+            .map(|segment| match segment {
+                "*" => Segment::Wildcard,
+                _ => Segment::Child(segment.replace("~1", "/").replace("~0", "~")),
+            })
+            .collect()
+    }
+
+    fn parse_json_path(rule: &str) -> Result<Vec<Segment>> {
+        let invalid = || Error::new(format!("invalid JSONPath payload rule '{}'", rule));
+        let mut segments = vec![];
+        let mut rest = &rule[1..];
+
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix("..") {
+                let (name, remaining) = take_name(after);
+                segments.push(match name {
+                    "" => return Err(invalid()),
+                    "*" => Segment::DescendantWildcard,
+                    _ => Segment::Descendant(name.to_owned()),
+                });
+                rest = remaining;
+            } else if let Some(after) = rest.strip_prefix('.') {
+                let (name, remaining) = take_name(after);
+                segments.push(match name {
+                    "" => return Err(invalid()),
+                    "*" => Segment::Wildcard,
+                    _ => Segment::Child(name.to_owned()),
+                });
+                rest = remaining;
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(invalid)?;
+                let selector = after[..end].trim();
+                segments.push(match selector {
+                    "*" => Segment::Wildcard,
+                    _ if selector.len() >= 2
+                        && (selector.starts_with('\'') && selector.ends_with('\'')
+                            || selector.starts_with('"') && selector.ends_with('"')) =>
+                    {
+                        Segment::Child(selector[1..selector.len() - 1].to_owned())
+                    }
+                    _ if selector.parse::<usize>().is_ok() => Segment::Child(selector.to_owned()),
+                    _ => return Err(invalid()),
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(segments)
+    }
+}
+
+/// name following . or .. in JSONPath, i.e. everything up to next . or [
+fn take_name(text: &str) -> (&str, &str) {
+    let end = text.find(['.', '[']).unwrap_or(text.len());
+    (&text[..end], &text[end..])
+}
+
+/// children of object or array together with the key they are stored under.
+/// Array items inherit key of the array, e.g. items of "urls": [...] are stored under urls.
+fn children<'a>(
+    value: &'a JsonValue,
+    key: Option<&'a str>,
+) -> Vec<(Option<&'a str>, Option<String>, &'a JsonValue)> {
+    match value {
+        JsonValue::Object(map) => map
+            .iter()
+            .map(|(child_key, child)| (Some(child_key.as_str()), Some(child_key.to_owned()), child))
+            .collect(),
+        JsonValue::Array(items) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, child)| (key, Some(idx.to_string()), child))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// all nodes selected by segments, each with key it is stored under
+fn select<'a>(
+    value: &'a JsonValue,
+    key: Option<&'a str>,
+    segments: &[Segment],
+    selected: &mut Vec<(Option<&'a str>, &'a JsonValue)>,
+) {
+    let segment = match segments.first() {
+        None => {
+            selected.push((key, value));
+            return;
+        }
+        Some(segment) => segment,
+    };
+
+    for (child_key, child_name, child) in children(value, key) {
+        match segment {
+            Segment::Child(name) if child_name.as_deref() == Some(name.as_str()) => {
+                select(child, child_key, &segments[1..], selected)
+            }
+            Segment::Wildcard => select(child, child_key, &segments[1..], selected),
+            Segment::Descendant(name) => {
+                if child_name.as_deref() == Some(name.as_str())
+                    && matches!(value, JsonValue::Object(_))
+                {
+                    select(child, child_key, &segments[1..], selected);
+                }
+                select(child, child_key, segments, selected);
+            }
+            Segment::DescendantWildcard => {
+                select(child, child_key, &segments[1..], selected);
+                select(child, child_key, segments, selected);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_excluded_key(key: &str, excluded_keys: &[String]) -> bool {
+    let lower = key.to_lowercase();
+    EXCLUDED_KEYS.contains(&lower.as_str())
+        || excluded_keys
+            .iter()
+            .any(|excluded| excluded.to_lowercase() == lower)
+        || lower.ends_with("url")
+        || lower.ends_with("uri")
+        || lower.ends_with("_id")
+        || key.ends_with("Id")
+}
+
+fn looks_like_url(text: &str) -> bool {
+    let lower = text.trim().to_lowercase();
+    ["http://", "https://", "www.", "mailto:", "tel:"]
+        .iter()
+        .any(|prefix| lower.starts_with(prefix))
+}
+
+/// Rules selecting texts of custom payloads. Parsed from --payload-text and --payload-exclude-key
+/// options. No rules means payloads are cloned to target language without translation.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PayloadRules {
+    rules: Vec<PayloadRule>,
+    excluded_keys: Vec<String>,
+}
+
+impl PayloadRules {
+    pub fn parse(rules: &[&str], excluded_keys: &[&str]) -> Result<Self> {
+        Ok(PayloadRules {
+            rules: rules
+                .iter()
+                .map(|rule| PayloadRule::parse(rule))
+                .collect::<Result<Vec<PayloadRule>>>()?,
+            excluded_keys: excluded_keys
+                .iter()
+                .map(|key| key.trim().to_owned())
+                .collect(),
+        })
+    }
+
+    /// translation map of strings selected by rules
+    pub fn to_translation(&self, payload: &JsonValue) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for rule in self.rules.iter() {
+            let mut selected = vec![];
+            select(payload, None, &rule.segments, &mut selected);
+            for (key, value) in selected {
+                if let JsonValue::String(text) = value {
+                    if key.is_some_and(|key| is_excluded_key(key, &self.excluded_keys))
+                        || looks_like_url(text)
+                        || text.trim().is_empty()
+                    {
+                        continue;
+                    }
+                    map_to_translate.insert(format!("{:p}", text), text.to_owned());
+                }
+            }
+        }
+
+        map_to_translate
+    }
+}
+
+/// applies translation of strings selected by [PayloadRules::to_translation](struct.PayloadRules.html#method.to_translation)
+pub fn payload_from_translation(
+    payload: &mut JsonValue,
+    translations_map: &collections::HashMap<String, String>,
+) {
+    match payload {
+        JsonValue::String(text) => {
+            if let Some(translated) = translations_map.get(&format!("{:p}", text)) {
+                *text = translated.to_owned();
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items.iter_mut() {
+                payload_from_translation(item, translations_map);
+            }
+        }
+        JsonValue::Object(map) => {
+            for (_, child) in map.iter_mut() {
+                payload_from_translation(child, translations_map);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::gcloud::translate::dummy_translate;
+
+    // cargo test -- --show-output test_payload_rules
+    #[test]
+    fn test_payload_rules() -> Result<()> {
+        let mut payload: JsonValue = serde_json::from_str(
+            r#"
+            {
+              "richContent": [
+                [
+                  {
+                    "type": "chips",
+                    "options": [
+                      { "text": "Track order", "link": "https://example.com/track" },
+                      { "text": "https://example.com/help" }
+                    ]
+                  },
+                  {
+                    "type": "button",
+                    "text": "Contact us",
+                    "event": { "name": "contact", "parameters": {} },
+                    "buttonId": "contact-1"
+                  }
+                ]
+              ],
+              "quick_replies": [
+                { "title": "Red", "payload": "RED" },
+                { "title": "Green", "payload": "GREEN" }
+              ]
+            }
+            "#,
+        )?;
+
+        let rules = PayloadRules::parse(
+            &[
+                "$.richContent[*][*].options[*].text",
+                "$.richContent[0][1]['text']",
+                "/quick_replies/*/title",
+                "$..payload",
+                "$..buttonId",
+            ],
+            &[],
+        )?;
+        let mut translations_map = rules.to_translation(&payload);
+        let mut texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+        texts.sort_unstable();
+        assert_eq!(texts, vec!["Contact us", "Green", "Red", "Track order"]);
+
+        dummy_translate(&mut translations_map);
+        payload_from_translation(&mut payload, &translations_map);
+        assert_eq!(
+            payload["richContent"][0][0]["options"][0]["text"],
+            "Track order_translated"
+        );
+        assert_eq!(
+            payload["richContent"][0][0]["options"][0]["link"],
+            "https://example.com/track"
+        );
+        assert_eq!(
+            payload["richContent"][0][1]["text"],
+            "Contact us_translated"
+        );
+        assert_eq!(payload["quick_replies"][1]["title"], "Green_translated");
+        assert_eq!(payload["quick_replies"][1]["payload"], "GREEN");
+
+        // user defined excluded keys
+        let rules = PayloadRules::parse(&["$..title"], &["title"])?;
+        assert!(rules.to_translation(&payload).is_empty());
+
+        assert!(PayloadRules::parse(&["richContent.text"], &[]).is_err());
+        assert!(PayloadRules::parse(&["$.richContent[x]"], &[]).is_err());
+        assert!(PayloadRules::parse(&["$.richContent["], &[]).is_err());
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use crate::google::dialogflow::payload::{payload_from_translation, PayloadRules};
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -39,6 +40,21 @@ use ga_simple::GASimpleResponseType;
 use ga_suggestions_chips::GASuggestionChipsType;
 use ga_table::GATableCardType;
 
+mod rbm_carousel_card;
+mod rbm_shared;
+mod rbm_standalone_card;
+mod rbm_text;
+mod telephony_play_audio;
+mod telephony_synthesize_speech;
+mod telephony_transfer_call;
+
+use rbm_carousel_card::RbmCarouselCardType;
+use rbm_standalone_card::RbmStandaloneCardType;
+use rbm_text::RbmTextType;
+use telephony_play_audio::TelephonyPlayAudioType;
+use telephony_synthesize_speech::TelephonySynthesizeSpeechType;
+use telephony_transfer_call::TelephonyTransferCallType;
+
 // GDF channels we need to support:
 //
 // DEFAULT (Text Response + Custom Payload)
@@ -49,11 +65,8 @@ use ga_table::GATableCardType;
 // SKYPE (Text Resoponse + Image + Card + Quick Replies + Custom Payload)
 // LINE (Text Resoponse + Image + Card + Quick Replies + Custom Payload)
 // GOOGLE_HANGOUTS (Text Resoponse + Image + Card +  Custom Payload) - for wechat
-//
-// not supported:
-//
-// GDF phone gateway (Play audio, Transfer call, Synthetize speech)
-// Telegram (Text Resoponse + Image + Card + Quick Replies + Custom Payload)
+// TELEGRAM (Text Resoponse + Image + Card + Quick Replies + Custom Payload) - generic message types
+// GDF phone gateway (Play audio + Transfer call + Synthetize speech)
 // RCS Business Messaging (Standalone Rich Card + Carousel Rich Card + Simple Response)
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -103,9 +116,58 @@ pub enum MessageType {
 
     #[serde(rename = "simple_response")]
     GASimpleResponse(GASimpleResponseType),
+
+    #[serde(rename = "rbm_text")]
+    RbmText(RbmTextType),
+
+    #[serde(rename = "rbm_standalone_rich_card")]
+    RbmStandaloneCard(RbmStandaloneCardType),
+
+    #[serde(rename = "rbm_carousel_rich_card")]
+    RbmCarouselCard(RbmCarouselCardType),
+
+    #[serde(rename = "telephony_play_audio")]
+    TelephonyPlayAudio(TelephonyPlayAudioType),
+
+    #[serde(rename = "telephony_synthesize_speech")]
+    TelephonySynthesizeSpeech(TelephonySynthesizeSpeechType),
+
+    #[serde(rename = "telephony_transfer_call")]
+    TelephonyTransferCall(TelephonyTransferCallType),
 }
 
+/// platform of messages without platform, i.e. messages of default response tab in Dialogflow console
+pub const DEFAULT_PLATFORM: &str = "default";
+
 impl MessageType {
+    /// platform (channel) of the message, e.g. google, facebook or default
+    pub fn get_platform(&self) -> &str {
+        let platform = match self {
+            MessageType::GenericCustomPayload(m) => m.platform.as_deref(),
+            MessageType::GenericQuickRepliesResponse(m) => Some(m.platform.as_str()),
+            MessageType::GenericCardResponse(m) => m.platform.as_deref(),
+            MessageType::GenericImageResponse(m) => Some(m.platform.as_str()),
+            MessageType::GATableCard(m) => Some(m.platform.as_str()),
+            MessageType::GACustomPayload(m) => Some(m.platform.as_str()),
+            MessageType::GABasicCard(m) => Some(m.platform.as_str()),
+            MessageType::GASuggestionChips(m) => Some(m.platform.as_str()),
+            MessageType::GAList(m) => Some(m.platform.as_str()),
+            MessageType::GALinkOutSuggestion(m) => Some(m.platform.as_str()),
+            MessageType::GACarouselCard(m) => Some(m.platform.as_str()),
+            MessageType::GABrowseCarouselCard(m) => Some(m.platform.as_str()),
+            MessageType::GAMediaContent(m) => Some(m.platform.as_str()),
+            MessageType::GASimpleResponse(m) => Some(m.platform.as_str()),
+            MessageType::GenericTextResponse(m) => m.platform.as_deref(),
+            MessageType::RbmText(m) => Some(m.platform.as_str()),
+            MessageType::RbmStandaloneCard(m) => Some(m.platform.as_str()),
+            MessageType::RbmCarouselCard(m) => Some(m.platform.as_str()),
+            MessageType::TelephonyPlayAudio(m) => Some(m.platform.as_str()),
+            MessageType::TelephonySynthesizeSpeech(m) => Some(m.platform.as_str()),
+            MessageType::TelephonyTransferCall(m) => Some(m.platform.as_str()),
+        };
+        platform.unwrap_or(DEFAULT_PLATFORM)
+    }
+
     pub fn get_message_lang(&self) -> &String {
         match self {
             MessageType::GenericCustomPayload(m) => &m.lang,
@@ -123,6 +185,12 @@ impl MessageType {
             MessageType::GAMediaContent(m) => &m.lang,
             MessageType::GASimpleResponse(m) => &m.lang,
             MessageType::GenericTextResponse(m) => &m.lang,
+            MessageType::RbmText(m) => &m.lang,
+            MessageType::RbmStandaloneCard(m) => &m.lang,
+            MessageType::RbmCarouselCard(m) => &m.lang,
+            MessageType::TelephonyPlayAudio(m) => &m.lang,
+            MessageType::TelephonySynthesizeSpeech(m) => &m.lang,
+            MessageType::TelephonyTransferCall(m) => &m.lang,
         }
     }
 
@@ -144,18 +212,186 @@ impl MessageType {
             MessageType::GAMediaContent(m) => col.extend(m.to_translation()),
             MessageType::GASimpleResponse(m) => col.extend(m.to_translation()),
             MessageType::GenericTextResponse(m) => col.extend(m.to_translation()),
+            MessageType::RbmText(m) => col.extend(m.to_translation()),
+            MessageType::RbmStandaloneCard(m) => col.extend(m.to_translation()),
+            MessageType::RbmCarouselCard(m) => col.extend(m.to_translation()),
+            MessageType::TelephonyPlayAudio(_) => {}
+            MessageType::TelephonySynthesizeSpeech(m) => col.extend(m.to_translation()),
+            MessageType::TelephonyTransferCall(_) => {}
         }
         col
     }
 
+    /// translation map of custom payload texts selected by payload rules
+    pub fn payload_to_translation(
+        &self,
+        payload_rules: &PayloadRules,
+    ) -> collections::HashMap<String, String> {
+        match self {
+            MessageType::GenericCustomPayload(m) => payload_rules.to_translation(&m.payload),
+            MessageType::GACustomPayload(m) => payload_rules.to_translation(&m.payload),
+            _ => collections::HashMap::new(),
+        }
+    }
+
+    /// URLs of images and other media (e.g. audio files) referenced by message.
+    /// They are localized when message is cloned to target language.
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            MessageType::GenericImageResponse(m) => vec![&mut m.image_url],
+            MessageType::GenericCardResponse(m) => m.image_url.iter_mut().collect(),
+            MessageType::GABasicCard(m) => m
+                .image
+                .iter_mut()
+                .flat_map(|image| image.media_urls_mut())
+                .collect(),
+            MessageType::GAList(m) => m
+                .items
+                .iter_mut()
+                .flat_map(|item| item.media_urls_mut())
+                .collect(),
+            MessageType::GACarouselCard(m) => m
+                .items
+                .iter_mut()
+                .flat_map(|item| item.media_urls_mut())
+                .collect(),
+            MessageType::GABrowseCarouselCard(m) => m
+                .items
+                .iter_mut()
+                .flat_map(|item| item.media_urls_mut())
+                .collect(),
+            MessageType::GAMediaContent(m) => m
+                .media_objects
+                .iter_mut()
+                .flat_map(|media_object| media_object.media_urls_mut())
+                .collect(),
+            MessageType::RbmStandaloneCard(m) => m.card_content.media_urls_mut(),
+            MessageType::RbmCarouselCard(m) => m
+                .card_contents
+                .iter_mut()
+                .flat_map(|card_content| card_content.media_urls_mut())
+                .collect(),
+            MessageType::TelephonyPlayAudio(m) => vec![&mut m.audio_uri],
+            _ => vec![],
+        }
+    }
+
+    /// URLs of web pages linked by message (link-outs, buttons, browse carousel items, postbacks).
+    /// They are rewritten when message is cloned to target language.
+    pub fn link_urls_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            MessageType::GenericCardResponse(m) => m
+                .buttons
+                .iter_mut()
+                .flatten()
+                .flat_map(|button| button.link_urls_mut())
+                .collect(),
+            MessageType::GATableCard(m) => m
+                .buttons
+                .iter_mut()
+                .map(|button| &mut button.open_url_action.url)
+                .collect(),
+            MessageType::GABasicCard(m) => m
+                .buttons
+                .iter_mut()
+                .flatten()
+                .map(|button| &mut button.open_url_action.url)
+                .collect(),
+            MessageType::GALinkOutSuggestion(m) => vec![&mut m.url],
+            MessageType::GABrowseCarouselCard(m) => m
+                .items
+                .iter_mut()
+                .map(|item| &mut item.open_url_action.url)
+                .collect(),
+            MessageType::RbmText(m) => m
+                .rbm_suggestion
+                .iter_mut()
+                .flatten()
+                .flat_map(|suggestion| suggestion.link_urls_mut())
+                .collect(),
+            MessageType::RbmStandaloneCard(m) => m.card_content.link_urls_mut(),
+            MessageType::RbmCarouselCard(m) => m
+                .card_contents
+                .iter_mut()
+                .flat_map(|card_content| card_content.link_urls_mut())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// texts whose length is limited by some channel together with their field,
+    /// see [length limits](../limits/index.html)
+    pub fn length_limited_texts_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        match self {
+            MessageType::GenericQuickRepliesResponse(m) => m
+                .replies
+                .iter_mut()
+                .map(|reply| ("quick_reply", reply))
+                .collect(),
+            MessageType::GenericCardResponse(m) => {
+                let mut texts = vec![];
+                texts.extend(m.title.iter_mut().map(|title| ("card.title", title)));
+                texts.extend(
+                    m.subtitle
+                        .iter_mut()
+                        .map(|subtitle| ("card.subtitle", subtitle)),
+                );
+                texts.extend(
+                    m.buttons
+                        .iter_mut()
+                        .flatten()
+                        .map(|button| ("card.button", &mut button.text)),
+                );
+                texts
+            }
+            MessageType::GASuggestionChips(m) => m
+                .suggestions
+                .iter_mut()
+                .map(|suggestion| ("suggestion_chip", &mut suggestion.title))
+                .collect(),
+            MessageType::GASimpleResponse(m) => m
+                .items
+                .iter_mut()
+                .flatten()
+                .map(|item| ("simple_response.display_text", &mut item.display_text))
+                .collect(),
+            MessageType::RbmText(m) => m
+                .rbm_suggestion
+                .iter_mut()
+                .flatten()
+                .flat_map(|suggestion| suggestion.length_limited_texts_mut())
+                .collect(),
+            MessageType::RbmStandaloneCard(m) => m.card_content.length_limited_texts_mut(),
+            MessageType::RbmCarouselCard(m) => m
+                .card_contents
+                .iter_mut()
+                .flat_map(|card_content| card_content.length_limited_texts_mut())
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    /// SSML texts of message (GA simple response, telephony synthesize speech)
+    pub fn ssml_texts(&self) -> Vec<&String> {
+        match self {
+            MessageType::GASimpleResponse(m) => m.ssml_texts(),
+            MessageType::TelephonySynthesizeSpeech(m) => m.ssml_texts(),
+            _ => vec![],
+        }
+    }
+
     pub fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
         match self {
-            MessageType::GenericCustomPayload(_) => {}
+            MessageType::GenericCustomPayload(m) => {
+                payload_from_translation(&mut m.payload, translations_map)
+            }
             MessageType::GenericQuickRepliesResponse(m) => m.from_translation(translations_map),
             MessageType::GenericCardResponse(m) => m.from_translation(translations_map),
             MessageType::GenericImageResponse(_) => {}
             MessageType::GATableCard(m) => m.from_translation(translations_map),
-            MessageType::GACustomPayload(_) => {}
+            MessageType::GACustomPayload(m) => {
+                payload_from_translation(&mut m.payload, translations_map)
+            }
             MessageType::GABasicCard(m) => m.from_translation(translations_map),
             MessageType::GASuggestionChips(m) => m.from_translation(translations_map),
             MessageType::GAList(m) => m.from_translation(translations_map),
@@ -165,12 +401,23 @@ impl MessageType {
             MessageType::GAMediaContent(m) => m.from_translation(translations_map),
             MessageType::GASimpleResponse(m) => m.from_translation(translations_map),
             MessageType::GenericTextResponse(m) => m.from_translation(translations_map),
+            MessageType::RbmText(m) => m.from_translation(translations_map),
+            MessageType::RbmStandaloneCard(m) => m.from_translation(translations_map),
+            MessageType::RbmCarouselCard(m) => m.from_translation(translations_map),
+            MessageType::TelephonyPlayAudio(_) => {}
+            MessageType::TelephonySynthesizeSpeech(m) => m.from_translation(translations_map),
+            MessageType::TelephonyTransferCall(_) => {}
         }
     }
 
     pub fn new_message(&self, new_lang_code: &str) -> Option<MessageType> {
         let cloned_message = match self {
-            MessageType::GenericCustomPayload(_) => None,
+            MessageType::GenericCustomPayload(m) => {
+                let mut inner_msg_clone = m.clone();
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::GenericCustomPayload(inner_msg_clone);
+                Some(outer_msg_clone)
+            }
             MessageType::GenericQuickRepliesResponse(m) => {
                 let mut inner_msg_clone = m.clone();
                 inner_msg_clone.lang = format!("{}", new_lang_code);
@@ -183,14 +430,24 @@ impl MessageType {
                 let outer_msg_clone = MessageType::GenericCardResponse(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GenericImageResponse(_) => None,
+            MessageType::GenericImageResponse(m) => {
+                let mut inner_msg_clone = m.clone();
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::GenericImageResponse(inner_msg_clone);
+                Some(outer_msg_clone)
+            }
             MessageType::GATableCard(m) => {
                 let mut inner_msg_clone = m.clone();
                 inner_msg_clone.lang = format!("{}", new_lang_code);
                 let outer_msg_clone = MessageType::GATableCard(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GACustomPayload(_) => None,
+            MessageType::GACustomPayload(m) => {
+                let mut inner_msg_clone = m.clone();
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::GACustomPayload(inner_msg_clone);
+                Some(outer_msg_clone)
+            }
             MessageType::GABasicCard(m) => {
                 let mut inner_msg_clone = m.clone();
                 inner_msg_clone.lang = format!("{}", new_lang_code);
@@ -245,108 +502,59 @@ impl MessageType {
                 let outer_msg_clone = MessageType::GenericTextResponse(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-        };
-
-        cloned_message
-    }
-
-    pub fn to_new_language(
-        &self,
-        new_lang_code: &str,
-        translations_map: &mut collections::HashMap<String, String>,
-    ) -> Option<MessageType> {
-        let cloned_message = match self {
-            MessageType::GenericCustomPayload(_) => None,
-            MessageType::GenericQuickRepliesResponse(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GenericQuickRepliesResponse(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GenericCardResponse(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GenericCardResponse(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GenericImageResponse(_) => None,
-            MessageType::GATableCard(m) => {
+            MessageType::RbmText(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GATableCard(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GACustomPayload(_) => None,
-            MessageType::GABasicCard(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GABasicCard(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::RbmText(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GASuggestionChips(m) => {
+            MessageType::RbmStandaloneCard(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GASuggestionChips(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::RbmStandaloneCard(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GAList(m) => {
+            MessageType::RbmCarouselCard(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GAList(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::RbmCarouselCard(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GALinkOutSuggestion(m) => {
+            MessageType::TelephonyPlayAudio(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GALinkOutSuggestion(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::TelephonyPlayAudio(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GACarouselCard(m) => {
+            MessageType::TelephonySynthesizeSpeech(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GACarouselCard(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::TelephonySynthesizeSpeech(inner_msg_clone);
                 Some(outer_msg_clone)
             }
-            MessageType::GABrowseCarouselCard(m) => {
+            MessageType::TelephonyTransferCall(m) => {
                 let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GABrowseCarouselCard(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GAMediaContent(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GAMediaContent(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GASimpleResponse(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GASimpleResponse(inner_msg_clone);
-                Some(outer_msg_clone)
-            }
-            MessageType::GenericTextResponse(m) => {
-                let mut inner_msg_clone = m.clone();
-                inner_msg_clone.lang = format!("{}", new_lang_code);
-                translations_map.extend(inner_msg_clone.to_translation());
-                let outer_msg_clone = MessageType::GenericTextResponse(inner_msg_clone);
+                inner_msg_clone.lang = new_lang_code.to_owned();
+                let outer_msg_clone = MessageType::TelephonyTransferCall(inner_msg_clone);
                 Some(outer_msg_clone)
             }
         };
 
         cloned_message
     }
+
+    /// clones message into new language (see [new_message](#method.new_message)) and adds its texts
+    /// into translation map. Translation map is keyed by addresses of the texts which change once
+    /// returned message is moved, i.e. translation has to be collected again where message is stored.
+    pub fn to_new_language(
+        &self,
+        new_lang_code: &str,
+        translations_map: &mut collections::HashMap<String, String>,
+    ) -> Option<MessageType> {
+        let cloned_message = self.new_message(new_lang_code)?;
+        translations_map.extend(cloned_message.to_translation());
+        Some(cloned_message)
+    }
 }
 
 // removes all whitespaces and replaces some characters (as produced by serde serialization)
@@ -1252,6 +1460,316 @@ mod tests {
         Ok(())
     }
 
+    /* Telegram */
+
+    // cargo test -- --show-output test_telegram
+    #[test]
+    fn test_telegram() -> Result<()> {
+        let messages = r#"
+        {
+          "messages": [
+            {
+              "type": "0",
+              "platform": "telegram",
+              "lang": "en",
+              "condition": "",
+              "speech": "Telegram text"
+            },
+            {
+              "type": "3",
+              "platform": "telegram",
+              "lang": "en",
+              "condition": "",
+              "imageUrl": "https://example.com/telegram.png"
+            },
+            {
+              "type": "1",
+              "platform": "telegram",
+              "lang": "en",
+              "condition": "",
+              "title": "Telegram card",
+              "subtitle": "card subtitle",
+              "imageUrl": "https://example.com/telegram.png",
+              "buttons": [
+                {
+                  "text": "Open",
+                  "postback": "https://example.com"
+                }
+              ]
+            },
+            {
+              "type": "2",
+              "platform": "telegram",
+              "lang": "en",
+              "condition": "",
+              "title": "Pick one",
+              "replies": [
+                "yes",
+                "no"
+              ]
+            },
+            {
+              "type": "4",
+              "platform": "telegram",
+              "lang": "en",
+              "condition": "",
+              "payload": {
+                "telegram": {
+                  "text": "custom telegram payload"
+                }
+              }
+            }
+          ]
+        }
+        "#;
+
+        let messages_struct: Messages = serde_json::from_str(messages)?;
+        println!("messages_struct {:#?}", messages_struct);
+
+        let back_to_str = serde_json::to_string(&messages_struct)?;
+
+        assert_json_eq!(
+            serde_json::from_str(messages)?,
+            serde_json::from_str(&back_to_str)?
+        );
+
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_telegram
+    #[test]
+    fn test_translate_telegram() -> Result<()> {
+        let messages = r#"
+        [
+          { "type": "0", "platform": "telegram", "lang": "en", "speech": "Telegram text" },
+          {
+            "type": "1",
+            "platform": "telegram",
+            "lang": "en",
+            "title": "Telegram card",
+            "subtitle": "card subtitle",
+            "buttons": [ { "text": "Open", "postback": "https://example.com" } ]
+          },
+          {
+            "type": "2",
+            "platform": "telegram",
+            "lang": "en",
+            "title": "Pick one",
+            "replies": [ "yes", "no" ]
+          }
+        ]
+        "#;
+        // Telegram has no message types of its own, generic ones are used
+        let messages: Vec<MessageType> = serde_json::from_str(messages)?;
+        assert!(matches!(messages[0], MessageType::GenericTextResponse(_)));
+        assert!(matches!(messages[1], MessageType::GenericCardResponse(_)));
+        assert!(matches!(
+            messages[2],
+            MessageType::GenericQuickRepliesResponse(_)
+        ));
+
+        let mut new_messages: Vec<MessageType> = messages
+            .iter()
+            .filter_map(|message| message.new_message("de"))
+            .collect();
+        let mut translations_map = collections::HashMap::new();
+        for message in new_messages.iter() {
+            assert_eq!(message.get_platform(), "telegram");
+            translations_map.extend(message.to_translation());
+        }
+        assert_eq!(translations_map.len(), 7);
+
+        dummy_translate(&mut translations_map);
+        for message in new_messages.iter_mut() {
+            message.from_translation(&translations_map);
+        }
+        let translated = serde_json::to_value(&new_messages)?;
+        assert_eq!(translated[0]["speech"], "Telegram text_translated");
+        assert_eq!(translated[1]["title"], "Telegram card_translated");
+        assert_eq!(translated[1]["subtitle"], "card subtitle_translated");
+        assert_eq!(
+            translated[1]["buttons"][0],
+            json!({ "text": "Open_translated", "postback": "https://example.com" })
+        );
+        assert_eq!(translated[2]["title"], "Pick one_translated");
+        assert_eq!(
+            translated[2]["replies"],
+            json!(["yes_translated", "no_translated"])
+        );
+        Ok(())
+    }
+
+    /* RCS Business Messaging */
+
+    // cargo test -- --show-output test_rbm
+    #[test]
+    fn test_rbm() -> Result<()> {
+        let messages = r#"
+        {
+          "messages": [
+            {
+              "type": "rbm_text",
+              "platform": "rbm",
+              "lang": "en",
+              "condition": "",
+              "text": "Which size do you prefer?",
+              "rbmSuggestion": [
+                {
+                  "reply": {
+                    "text": "Small",
+                    "postbackData": "SIZE_S"
+                  }
+                },
+                {
+                  "action": {
+                    "text": "Call us",
+                    "postbackData": "CALL",
+                    "dial": {
+                      "phoneNumber": "+1 650 555 0100"
+                    }
+                  }
+                }
+              ]
+            },
+            {
+              "type": "rbm_standalone_rich_card",
+              "platform": "rbm",
+              "lang": "en",
+              "condition": "",
+              "cardOrientation": "VERTICAL",
+              "thumbnailImageAlignment": "LEFT",
+              "cardContent": {
+                "title": "Summer sale",
+                "description": "All shirts 20% off",
+                "media": {
+                  "fileUri": "https://example.com/sale.png",
+                  "thumbnailUri": "https://example.com/sale_thumb.png",
+                  "height": "MEDIUM"
+                },
+                "suggestions": [
+                  {
+                    "action": {
+                      "text": "Open shop",
+                      "postbackData": "OPEN_SHOP",
+                      "openUrl": {
+                        "uri": "https://example.com/shop"
+                      }
+                    }
+                  },
+                  {
+                    "action": {
+                      "text": "Find store",
+                      "postbackData": "FIND_STORE",
+                      "shareLocation": {}
+                    }
+                  }
+                ]
+              }
+            },
+            {
+              "type": "rbm_carousel_rich_card",
+              "platform": "rbm",
+              "lang": "en",
+              "condition": "",
+              "cardWidth": "MEDIUM",
+              "cardContents": [
+                {
+                  "title": "Shirt",
+                  "description": "Cotton shirt",
+                  "media": {
+                    "fileUri": "https://example.com/shirt.png",
+                    "height": "SHORT"
+                  },
+                  "suggestions": [
+                    {
+                      "reply": {
+                        "text": "Buy shirt",
+                        "postbackData": "BUY_SHIRT"
+                      }
+                    }
+                  ]
+                },
+                {
+                  "title": "Jeans"
+                }
+              ]
+            }
+          ]
+        }
+        "#;
+
+        let messages_struct: Messages = serde_json::from_str(messages)?;
+        println!("messages_struct {:#?}", messages_struct);
+
+        let back_to_str = serde_json::to_string(&messages_struct)?;
+
+        assert_json_eq!(
+            serde_json::from_str(messages)?,
+            serde_json::from_str(&back_to_str)?
+        );
+
+        Ok(())
+    }
+
+    /* Phone gateway */
+
+    // cargo test -- --show-output test_telephony
+    #[test]
+    fn test_telephony() -> Result<()> {
+        let messages = r#"
+        {
+          "messages": [
+            {
+              "type": "telephony_play_audio",
+              "platform": "telephony",
+              "lang": "en",
+              "condition": "",
+              "audioUri": "gs://bucket/welcome.wav"
+            },
+            {
+              "type": "telephony_synthesize_speech",
+              "platform": "telephony",
+              "lang": "en",
+              "condition": "",
+              "text": "Please hold on"
+            },
+            {
+              "type": "telephony_synthesize_speech",
+              "platform": "telephony",
+              "lang": "en",
+              "condition": "",
+              "ssml": "<speak>Please hold on</speak>"
+            },
+            {
+              "type": "telephony_transfer_call",
+              "platform": "telephony",
+              "lang": "en",
+              "condition": "",
+              "phoneNumber": "+16505550100"
+            }
+          ]
+        }
+        "#;
+
+        let messages_struct: Messages = serde_json::from_str(messages)?;
+        println!("messages_struct {:#?}", messages_struct);
+
+        let back_to_str = serde_json::to_string(&messages_struct)?;
+
+        assert_json_eq!(
+            serde_json::from_str(messages)?,
+            serde_json::from_str(&back_to_str)?
+        );
+
+        // all phone gateway messages are cloned to target language
+        for message in messages_struct.messages.iter() {
+            let new_message = message.new_message("de").unwrap();
+            assert_eq!(new_message.get_message_lang(), "de");
+        }
+
+        Ok(())
+    }
+
     /* Translation tests */
 
     // cargo test -- --show-output test_translate_generic_text_response_1
@@ -2074,6 +2592,241 @@ mod tests {
         Ok(())
     }
 
+    // cargo test -- --show-output test_translate_custom_payload
+    #[test]
+    fn test_translate_custom_payload() -> Result<()> {
+        let messages = r#"
+        [
+          {
+            "type": "4",
+            "lang": "en",
+            "condition": "",
+            "payload": {
+              "quick_replies": [
+                { "content_type": "text", "title": "Red", "payload": "RED" }
+              ]
+            }
+          },
+          {
+            "type": "custom_payload",
+            "platform": "google",
+            "lang": "en",
+            "condition": "",
+            "payload": {
+              "google": { "richResponse": { "items": [{ "simpleResponse": { "textToSpeech": "Hi" } }] } }
+            }
+          }
+        ]
+        "#;
+        let messages: Vec<MessageType> = serde_json::from_str(messages)?;
+        let mut new_messages: Vec<MessageType> = messages
+            .iter()
+            .filter_map(|message| message.new_message("de"))
+            .collect();
+        assert_eq!(new_messages.len(), 2);
+        assert_eq!(new_messages[0].get_message_lang(), "de");
+        assert_eq!(new_messages[1].get_message_lang(), "de");
+
+        let payload_rules = PayloadRules::parse(
+            &[
+                "$.quick_replies[*].title",
+                "$.quick_replies[*].payload",
+                "$..textToSpeech",
+            ],
+            &[],
+        )?;
+        let mut translations_map = collections::HashMap::new();
+        for message in new_messages.iter() {
+            assert!(message.to_translation().is_empty());
+            translations_map.extend(message.payload_to_translation(&payload_rules));
+        }
+        assert_eq!(translations_map.len(), 2);
+
+        dummy_translate(&mut translations_map);
+        for message in new_messages.iter_mut() {
+            message.from_translation(&translations_map);
+        }
+        let translated = serde_json::to_value(&new_messages)?;
+        assert_eq!(
+            translated[0]["payload"]["quick_replies"][0],
+            json!({ "content_type": "text", "title": "Red_translated", "payload": "RED" })
+        );
+        assert_eq!(
+            translated[1]["payload"]["google"]["richResponse"]["items"][0]["simpleResponse"]
+                ["textToSpeech"],
+            "Hi_translated"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_rbm_text
+    #[test]
+    fn test_translate_rbm_text() -> Result<()> {
+        let str_before_translation = r#"
+        {
+          "type": "rbm_text",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "text": "Which size do you prefer?",
+          "rbmSuggestion": [
+            { "reply": { "text": "Small", "postbackData": "SIZE_S" } },
+            { "action": { "text": "Call us", "postbackData": "CALL", "dial": { "phoneNumber": "+16505550100" } } }
+          ]
+        }
+      "#;
+
+        let str_after_translation_expected = r#"
+        {
+          "type": "rbm_text",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "text": "Which size do you prefer?_translated",
+          "rbmSuggestion": [
+            { "reply": { "text": "Small_translated", "postbackData": "SIZE_S" } },
+            { "action": { "text": "Call us_translated", "postbackData": "CALL", "dial": { "phoneNumber": "+16505550100" } } }
+          ]
+        }
+        "#;
+
+        translation_tests_assertions!(
+            RbmTextType,
+            str_before_translation,
+            str_after_translation_expected,
+            "no_string_comparison"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_rbm_standalone_card
+    #[test]
+    fn test_translate_rbm_standalone_card() -> Result<()> {
+        let str_before_translation = r#"
+        {
+          "type": "rbm_standalone_rich_card",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "cardOrientation": "VERTICAL",
+          "cardContent": {
+            "title": "Summer sale",
+            "description": "All shirts 20% off",
+            "media": { "fileUri": "https://example.com/sale.png", "height": "MEDIUM" },
+            "suggestions": [
+              { "action": { "text": "Open shop", "postbackData": "OPEN_SHOP", "openUrl": { "uri": "https://example.com/shop" } } }
+            ]
+          }
+        }
+      "#;
+
+        let str_after_translation_expected = r#"
+        {
+          "type": "rbm_standalone_rich_card",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "cardOrientation": "VERTICAL",
+          "cardContent": {
+            "title": "Summer sale_translated",
+            "description": "All shirts 20% off_translated",
+            "media": { "fileUri": "https://example.com/sale.png", "height": "MEDIUM" },
+            "suggestions": [
+              { "action": { "text": "Open shop_translated", "postbackData": "OPEN_SHOP", "openUrl": { "uri": "https://example.com/shop" } } }
+            ]
+          }
+        }
+        "#;
+
+        translation_tests_assertions!(
+            RbmStandaloneCardType,
+            str_before_translation,
+            str_after_translation_expected,
+            "no_string_comparison"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_rbm_carousel_card
+    #[test]
+    fn test_translate_rbm_carousel_card() -> Result<()> {
+        let str_before_translation = r#"
+        {
+          "type": "rbm_carousel_rich_card",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "cardWidth": "MEDIUM",
+          "cardContents": [
+            {
+              "title": "Shirt",
+              "description": "Cotton shirt",
+              "suggestions": [ { "reply": { "text": "Buy shirt", "postbackData": "BUY_SHIRT" } } ]
+            },
+            { "title": "Jeans" }
+          ]
+        }
+      "#;
+
+        let str_after_translation_expected = r#"
+        {
+          "type": "rbm_carousel_rich_card",
+          "platform": "rbm",
+          "lang": "en",
+          "condition": "",
+          "cardWidth": "MEDIUM",
+          "cardContents": [
+            {
+              "title": "Shirt_translated",
+              "description": "Cotton shirt_translated",
+              "suggestions": [ { "reply": { "text": "Buy shirt_translated", "postbackData": "BUY_SHIRT" } } ]
+            },
+            { "title": "Jeans_translated" }
+          ]
+        }
+        "#;
+
+        translation_tests_assertions!(
+            RbmCarouselCardType,
+            str_before_translation,
+            str_after_translation_expected,
+            "no_string_comparison"
+        );
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_translate_telephony_synthesize_speech
+    #[test]
+    fn test_translate_telephony_synthesize_speech() -> Result<()> {
+        let str_before_translation = r#"
+        {
+          "type": "telephony_synthesize_speech",
+          "platform": "telephony",
+          "lang": "en",
+          "condition": "",
+          "ssml": "<speak>Please hold on</speak>"
+        }
+      "#;
+
+        let str_after_translation_expected = r#"
+        {
+          "type": "telephony_synthesize_speech",
+          "platform": "telephony",
+          "lang": "en",
+          "condition": "",
+          "ssml": "<speak>Please hold on_translated</speak>"
+        }
+        "#;
+
+        translation_tests_assertions!(
+            TelephonySynthesizeSpeechType,
+            str_before_translation,
+            str_after_translation_expected,
+            "no_string_comparison"
+        );
+        Ok(())
+    }
+
     // cargo test -- --show-output test_tagged_messages
     #[test]
     fn test_tagged_messages() -> Result<()> {
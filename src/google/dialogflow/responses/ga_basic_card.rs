@@ -1,5 +1,6 @@
 use crate::google::dialogflow::responses::ga_image::GAImage;
 use crate::google::dialogflow::responses::ga_shared::GACardTypeButton;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -24,6 +25,8 @@ pub struct GABasicCardType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GABasicCardType {
@@ -1,5 +1,6 @@
 use crate::google::dialogflow::responses::ga_image::GAImage;
 use crate::google::dialogflow::responses::ga_shared::GAOpenUrlAction;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -16,6 +17,17 @@ pub struct GAItemBrowseCarousel {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl GAItemBrowseCarousel {
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        self.image
+            .iter_mut()
+            .flat_map(|image| image.media_urls_mut())
+            .collect()
+    }
 }
 
 impl Translate for GAItemBrowseCarousel {
@@ -66,6 +78,8 @@ pub struct GABrowseCarouselCardType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<String>,
     pub items: Vec<GAItemBrowseCarousel>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GABrowseCarouselCardType {
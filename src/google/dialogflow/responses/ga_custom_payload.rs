@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -9,4 +10,6 @@ pub struct GACustomPayloadType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub condition: Option<String>,
     pub payload: JsonValue,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
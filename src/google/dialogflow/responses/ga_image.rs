@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -40,6 +41,14 @@ pub struct GAImage {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl GAImage {
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        self.url.iter_mut().collect()
+    }
 }
 
 impl Translate for GAImage {
@@ -1,4 +1,5 @@
 use crate::google::dialogflow::responses::ga_image::GAImage;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -7,6 +8,8 @@ use std::collections;
 pub struct GAListTypeItemOptionInfo {
     key: String,
     synonyms: Vec<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GAListTypeItemOptionInfo {
@@ -56,6 +59,14 @@ pub struct GAItem {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ssml: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl GAItem {
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        self.image.media_urls_mut()
+    }
 }
 
 impl Translate for GAItem {
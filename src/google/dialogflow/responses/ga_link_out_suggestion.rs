@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -17,6 +18,8 @@ pub struct GALinkOutSuggestionType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GALinkOutSuggestionType {
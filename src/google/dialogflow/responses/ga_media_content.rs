@@ -1,4 +1,5 @@
 use crate::google::dialogflow::responses::ga_image::GAImage;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -16,6 +17,16 @@ pub struct GAMediaObject {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl GAMediaObject {
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        let mut urls = self.large_image.media_urls_mut();
+        urls.push(&mut self.content_url);
+        urls
+    }
 }
 
 impl Translate for GAMediaObject {
@@ -58,6 +69,8 @@ pub struct GAMediaContentType {
     pub media_type: String,
     #[serde(rename = "mediaObjects")]
     pub media_objects: Vec<GAMediaObject>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GAMediaContentType {
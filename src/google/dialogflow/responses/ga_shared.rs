@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -14,6 +15,8 @@ pub struct GACardTypeButton {
     pub title: String,
     #[serde(rename = "openUrlAction")]
     pub open_url_action: GAOpenUrlAction,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GACardTypeButton {
@@ -38,4 +41,6 @@ pub struct GAOpenUrlAction {
     pub url: String,
     #[serde(rename = "urlTypeHint")]
     pub url_type_hint: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
@@ -1,4 +1,6 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
+use crate::ssml::{is_ssml, speech_from_translation, speech_to_translation};
 use serde::{Deserialize, Serialize};
 use std::collections;
 
@@ -16,6 +18,8 @@ pub struct GASimpleResponseItem {
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub footer: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 // type simple_response
@@ -43,6 +47,8 @@ pub struct GASimpleResponseType {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub items: Option<Vec<GASimpleResponseItem>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GASimpleResponseType {
@@ -52,11 +58,10 @@ impl Translate for GASimpleResponseType {
         if let Some(items) = &self.items {
             for item in items.iter() {
                 if let Some(text_to_speech) = &item.text_to_speech {
-                    map_to_translate
-                        .insert(format!("{:p}", text_to_speech), text_to_speech.to_owned());
+                    map_to_translate.extend(speech_to_translation(text_to_speech));
                 }
 
-                map_to_translate.insert(format!("{:p}", &item.ssml), item.ssml.to_owned());
+                map_to_translate.extend(speech_to_translation(&item.ssml));
                 map_to_translate.insert(
                     format!("{:p}", &item.display_text),
                     item.display_text.to_owned(),
@@ -65,7 +70,7 @@ impl Translate for GASimpleResponseType {
         }
 
         if let Some(ssml) = &self.ssml {
-            map_to_translate.insert(format!("{:p}", ssml), ssml.to_owned());
+            map_to_translate.extend(speech_to_translation(ssml));
         }
 
         if let Some(display_text) = &self.display_text {
@@ -73,7 +78,7 @@ impl Translate for GASimpleResponseType {
         }
 
         if let Some(text_to_speech) = &self.text_to_speech {
-            map_to_translate.insert(format!("{:p}", text_to_speech), text_to_speech.to_owned());
+            map_to_translate.extend(speech_to_translation(text_to_speech));
         }
 
         map_to_translate
@@ -82,19 +87,11 @@ impl Translate for GASimpleResponseType {
     fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
         if let Some(items) = &mut self.items {
             for item in items.iter_mut() {
-                if let Some(text_to_speech) = &item.text_to_speech {
-                    item.text_to_speech = Some(
-                        translations_map
-                            .get(&format!("{:p}", text_to_speech))
-                            .unwrap()
-                            .to_owned(),
-                    );
+                if let Some(text_to_speech) = item.text_to_speech.as_mut() {
+                    speech_from_translation(text_to_speech, translations_map);
                 }
 
-                item.ssml = translations_map
-                    .get(&format!("{:p}", &item.ssml))
-                    .unwrap()
-                    .to_owned();
+                speech_from_translation(&mut item.ssml, translations_map);
 
                 item.display_text = translations_map
                     .get(&format!("{:p}", &item.display_text))
@@ -103,13 +100,8 @@ impl Translate for GASimpleResponseType {
             }
         }
 
-        if let Some(ssml) = &self.ssml {
-            self.ssml = Some(
-                translations_map
-                    .get(&format!("{:p}", ssml))
-                    .unwrap()
-                    .to_owned(),
-            );
+        if let Some(ssml) = self.ssml.as_mut() {
+            speech_from_translation(ssml, translations_map);
         }
 
         if let Some(display_text) = &self.display_text {
@@ -121,14 +113,25 @@ impl Translate for GASimpleResponseType {
             );
         }
 
-        if let Some(text_to_speech) = &self.text_to_speech {
-            self.text_to_speech = Some(
-                translations_map
-                    .get(&format!("{:p}", text_to_speech))
-                    .unwrap()
-                    .to_owned(),
-            );
+        if let Some(text_to_speech) = self.text_to_speech.as_mut() {
+            speech_from_translation(text_to_speech, translations_map);
+        }
+    }
+}
+
+impl GASimpleResponseType {
+    /// SSML texts of the response, their translation is validated before it is applied
+    pub fn ssml_texts(&self) -> Vec<&String> {
+        let mut texts = vec![];
+        if let Some(items) = &self.items {
+            for item in items.iter() {
+                texts.extend(item.text_to_speech.iter());
+                texts.push(&item.ssml);
+            }
         }
+        texts.extend(self.ssml.iter());
+        texts.extend(self.text_to_speech.iter());
+        texts.into_iter().filter(|text| is_ssml(text)).collect()
     }
 }
 
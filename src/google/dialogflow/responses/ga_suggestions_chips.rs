@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -5,6 +6,8 @@ use std::collections;
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct GASuggestionChipsTypeSuggestion {
     pub title: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GASuggestionChipsTypeSuggestion {
@@ -37,6 +40,8 @@ pub struct GASuggestionChipsType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GASuggestionChipsType {
@@ -1,4 +1,5 @@
 use crate::google::dialogflow::responses::ga_shared::GACardTypeButton;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -6,6 +7,8 @@ use std::collections;
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub struct GATableCardRowCell {
     pub text: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GATableCardRowCell {
@@ -30,6 +33,8 @@ pub struct GATableCardRow {
     pub cells: Vec<GATableCardRowCell>,
     #[serde(rename = "dividerAfter")]
     pub divider_after: bool,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GATableCardRow {
@@ -66,6 +71,8 @@ pub struct GATableCardType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GATableCardType {
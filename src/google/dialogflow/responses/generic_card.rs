@@ -1,4 +1,5 @@
 use crate::google::dialogflow::responses::ga_shared::StringOrVecOfString;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -8,6 +9,18 @@ pub struct GenericCardResponseButton {
     pub text: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub postback: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl GenericCardResponseButton {
+    /// postback is linked URL only if it looks like URL, otherwise it is text sent back to the agent
+    pub fn link_urls_mut(&mut self) -> Vec<&mut String> {
+        self.postback
+            .iter_mut()
+            .filter(|postback| postback.starts_with("http://") || postback.starts_with("https://"))
+            .collect()
+    }
 }
 
 impl Translate for GenericCardResponseButton {
@@ -58,6 +71,8 @@ pub struct GenericCardResponseType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GenericCardResponseType {
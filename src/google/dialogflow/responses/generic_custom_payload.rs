@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
@@ -15,4 +16,6 @@ pub struct GenericCustomPayloadType {
     pub text_to_speech: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
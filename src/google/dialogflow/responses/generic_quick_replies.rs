@@ -1,3 +1,4 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -14,6 +15,8 @@ pub struct GenericQuickRepliesResponseType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GenericQuickRepliesResponseType {
@@ -41,15 +44,7 @@ impl Translate for GenericQuickRepliesResponseType {
                 .unwrap()
                 .to_owned();
         }
-
-        // GDF allows only 20 chars per quick reply, translation must be truncated accordingly!
-        for reply in self.replies.iter_mut() {
-            *reply = substring(reply.to_string(), 0, 20);
-        }
+        // GDF allows only 20 chars per quick reply, longer translation is reported
+        // (or truncated if requested) when checking length limits (see limits module)
     }
 }
-
-// to prevent errors like panicked at 'byte index 20 is out of bounds of ...
-fn substring(full_string: String, start: usize, len: usize) -> String {
-    full_string.chars().skip(start).take(len).collect()
-}
@@ -1,4 +1,5 @@
 use crate::google::dialogflow::responses::ga_shared::StringOrVecOfString;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
 use crate::google::gcloud::translate::Translate;
 use serde::{Deserialize, Serialize};
 use std::collections;
@@ -18,6 +19,8 @@ pub struct GenericTextResponseType {
     #[serde(rename = "textToSpeech")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text_to_speech: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
 }
 
 impl Translate for GenericTextResponseType {
@@ -0,0 +1,39 @@
+use crate::google::dialogflow::responses::rbm_shared::RbmCardContent;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use serde::{Deserialize, Serialize};
+use std::collections;
+
+// type rbm_carousel_rich_card
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmCarouselCardType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(rename = "cardWidth")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_width: Option<String>,
+    #[serde(rename = "cardContents")]
+    pub card_contents: Vec<RbmCardContent>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for RbmCarouselCardType {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        for card_content in self.card_contents.iter() {
+            map_to_translate.extend(card_content.to_translation());
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        for card_content in self.card_contents.iter_mut() {
+            card_content.from_translation(translations_map);
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections;
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmSuggestedReply {
+    pub text: String,
+    #[serde(rename = "postbackData")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postback_data: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmSuggestedActionDial {
+    #[serde(rename = "phoneNumber")]
+    pub phone_number: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmSuggestedActionOpenUri {
+    pub uri: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmSuggestedAction {
+    pub text: String,
+    #[serde(rename = "postbackData")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub postback_data: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dial: Option<RbmSuggestedActionDial>,
+    #[serde(rename = "openUrl")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_url: Option<RbmSuggestedActionOpenUri>,
+    // share location action has no attributes, i.e. it is always {}
+    #[serde(rename = "shareLocation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_location: Option<JsonValue>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+// suggestion is either suggested reply or suggested action, only text is translated
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmSuggestion {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<RbmSuggestedReply>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<RbmSuggestedAction>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl RbmSuggestion {
+    pub fn length_limited_texts_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        self.reply
+            .iter_mut()
+            .map(|reply| &mut reply.text)
+            .chain(self.action.iter_mut().map(|action| &mut action.text))
+            .map(|text| ("suggestion", text))
+            .collect()
+    }
+
+    pub fn link_urls_mut(&mut self) -> Vec<&mut String> {
+        self.action
+            .iter_mut()
+            .filter_map(|action| action.open_url.as_mut())
+            .map(|open_url| &mut open_url.uri)
+            .collect()
+    }
+}
+
+impl Translate for RbmSuggestion {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        if let Some(reply) = &self.reply {
+            map_to_translate.insert(format!("{:p}", &reply.text), reply.text.to_owned());
+        }
+
+        if let Some(action) = &self.action {
+            map_to_translate.insert(format!("{:p}", &action.text), action.text.to_owned());
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(reply) = &mut self.reply {
+            if let Some(translated) = translations_map.get(&format!("{:p}", &reply.text)) {
+                reply.text = translated.to_owned();
+            }
+        }
+
+        if let Some(action) = &mut self.action {
+            if let Some(translated) = translations_map.get(&format!("{:p}", &action.text)) {
+                action.text = translated.to_owned();
+            }
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmMedia {
+    #[serde(rename = "fileUri")]
+    pub file_uri: String,
+    #[serde(rename = "thumbnailUri")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+// content of standalone rich card or single card of carousel rich card
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmCardContent {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media: Option<RbmMedia>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<RbmSuggestion>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl RbmCardContent {
+    // media file and its thumbnail
+    pub fn link_urls_mut(&mut self) -> Vec<&mut String> {
+        self.suggestions
+            .iter_mut()
+            .flatten()
+            .flat_map(|suggestion| suggestion.link_urls_mut())
+            .collect()
+    }
+
+    pub fn length_limited_texts_mut(&mut self) -> Vec<(&'static str, &mut String)> {
+        let mut texts = vec![];
+        texts.extend(self.title.iter_mut().map(|title| ("card.title", title)));
+        texts.extend(
+            self.description
+                .iter_mut()
+                .map(|description| ("card.description", description)),
+        );
+        texts.extend(
+            self.suggestions
+                .iter_mut()
+                .flatten()
+                .flat_map(|suggestion| suggestion.length_limited_texts_mut()),
+        );
+        texts
+    }
+
+    pub fn media_urls_mut(&mut self) -> Vec<&mut String> {
+        match &mut self.media {
+            Some(media) => {
+                let mut urls = vec![&mut media.file_uri];
+                urls.extend(media.thumbnail_uri.iter_mut());
+                urls
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Translate for RbmCardContent {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        if let Some(title) = &self.title {
+            map_to_translate.insert(format!("{:p}", title), title.to_owned());
+        }
+
+        if let Some(description) = &self.description {
+            map_to_translate.insert(format!("{:p}", description), description.to_owned());
+        }
+
+        if let Some(suggestions) = &self.suggestions {
+            for suggestion in suggestions.iter() {
+                map_to_translate.extend(suggestion.to_translation());
+            }
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(title) = &self.title {
+            if let Some(translated) = translations_map.get(&format!("{:p}", title)) {
+                self.title = Some(translated.to_owned());
+            }
+        }
+
+        if let Some(description) = &self.description {
+            if let Some(translated) = translations_map.get(&format!("{:p}", description)) {
+                self.description = Some(translated.to_owned());
+            }
+        }
+
+        if let Some(suggestions) = &mut self.suggestions {
+            for suggestion in suggestions.iter_mut() {
+                suggestion.from_translation(translations_map);
+            }
+        }
+    }
+}
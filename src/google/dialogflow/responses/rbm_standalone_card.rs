@@ -0,0 +1,34 @@
+use crate::google::dialogflow::responses::rbm_shared::RbmCardContent;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use serde::{Deserialize, Serialize};
+use std::collections;
+
+// type rbm_standalone_rich_card
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmStandaloneCardType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(rename = "cardOrientation")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub card_orientation: Option<String>,
+    #[serde(rename = "thumbnailImageAlignment")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thumbnail_image_alignment: Option<String>,
+    #[serde(rename = "cardContent")]
+    pub card_content: RbmCardContent,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for RbmStandaloneCardType {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        self.card_content.to_translation()
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        self.card_content.from_translation(translations_map);
+    }
+}
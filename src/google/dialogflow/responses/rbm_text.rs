@@ -0,0 +1,48 @@
+use crate::google::dialogflow::responses::rbm_shared::RbmSuggestion;
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use serde::{Deserialize, Serialize};
+use std::collections;
+
+// type rbm_text
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct RbmTextType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    pub text: String,
+    #[serde(rename = "rbmSuggestion")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rbm_suggestion: Option<Vec<RbmSuggestion>>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl Translate for RbmTextType {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        map_to_translate.insert(format!("{:p}", &self.text), self.text.to_owned());
+
+        if let Some(suggestions) = &self.rbm_suggestion {
+            for suggestion in suggestions.iter() {
+                map_to_translate.extend(suggestion.to_translation());
+            }
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(translated) = translations_map.get(&format!("{:p}", &self.text)) {
+            self.text = translated.to_owned();
+        }
+
+        if let Some(suggestions) = &mut self.rbm_suggestion {
+            for suggestion in suggestions.iter_mut() {
+                suggestion.from_translation(translations_map);
+            }
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use serde::{Deserialize, Serialize};
+
+// type telephony_play_audio
+// audio file is cloned to target language as it is, there is nothing to translate
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TelephonyPlayAudioType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(rename = "audioUri")]
+    pub audio_uri: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
@@ -0,0 +1,55 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use crate::google::gcloud::translate::Translate;
+use crate::ssml::{is_ssml, speech_from_translation, speech_to_translation};
+use serde::{Deserialize, Serialize};
+use std::collections;
+
+// type telephony_synthesize_speech
+// speech is specified either as plain text or as SSML
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TelephonySynthesizeSpeechType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ssml: Option<String>,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
+
+impl TelephonySynthesizeSpeechType {
+    pub fn ssml_texts(&self) -> Vec<&String> {
+        self.ssml.iter().filter(|ssml| is_ssml(ssml)).collect()
+    }
+}
+
+impl Translate for TelephonySynthesizeSpeechType {
+    fn to_translation(&self) -> collections::HashMap<String, String> {
+        let mut map_to_translate = collections::HashMap::new();
+
+        if let Some(text) = &self.text {
+            map_to_translate.insert(format!("{:p}", text), text.to_owned());
+        }
+
+        if let Some(ssml) = &self.ssml {
+            map_to_translate.extend(speech_to_translation(ssml));
+        }
+
+        map_to_translate
+    }
+
+    fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>) {
+        if let Some(text) = &self.text {
+            if let Some(translated) = translations_map.get(&format!("{:p}", text)) {
+                self.text = Some(translated.to_owned());
+            }
+        }
+
+        if let Some(ssml) = self.ssml.as_mut() {
+            speech_from_translation(ssml, translations_map);
+        }
+    }
+}
@@ -0,0 +1,16 @@
+use crate::google::dialogflow::unknown_fields::UnknownFields;
+use serde::{Deserialize, Serialize};
+
+// type telephony_transfer_call
+// phone number is cloned to target language as it is, there is nothing to translate
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct TelephonyTransferCallType {
+    pub platform: String,
+    pub lang: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<String>,
+    #[serde(rename = "phoneNumber")]
+    pub phone_number: String,
+    #[serde(flatten)]
+    pub extra: UnknownFields,
+}
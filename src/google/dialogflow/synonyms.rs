@@ -0,0 +1,257 @@
+//! # Normalization of translated entity synonyms
+//!
+//! Machine translation of entity synonyms produces duplicates (two synonyms translated to the same word),
+//! empty strings, case variants and trailing punctuation. Normalization cleans translated entities
+//! of ES agents (entries files) and CX agents (entities files) the same way and describes every change.
+use crate::google::dialogflow::options::{SynonymsDedupe, SynonymsNormalization};
+use std::collections;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// punctuation removed from beginning and end of synonyms, e.g. Hallo! -> Hallo
+const STRIPPED_PUNCTUATION: &[char] = &[
+    '.', ',', ';', ':', '!', '?', '…', '。', '，', '！', '？', '、', '¿', '¡', '"', '«', '»', '„',
+    '“', '”',
+];
+
+/// entity entry (ES) or entity (CX) with value and synonyms
+pub trait SynonymsEntry {
+    fn value(&self) -> &str;
+    fn value_mut(&mut self) -> &mut String;
+    fn synonyms(&self) -> &Vec<String>;
+    fn synonyms_mut(&mut self) -> &mut Vec<String>;
+    /// composite entries reference other entities and are not normalized
+    fn is_composite(&self) -> bool;
+}
+
+fn strip_punctuation(text: &str) -> &str {
+    text.trim_matches(|c: char| c.is_whitespace() || STRIPPED_PUNCTUATION.contains(&c))
+}
+
+impl SynonymsNormalization {
+    /// key under which synonyms are compared, synonyms with the same key are duplicates
+    fn key(&self, text: &str) -> String {
+        match self.dedupe {
+            SynonymsDedupe::Exact => text.to_owned(),
+            SynonymsDedupe::IgnoreCase => text.to_lowercase(),
+            SynonymsDedupe::IgnoreCaseDiacritics => text
+                .nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .collect::<String>()
+                .to_lowercase(),
+        }
+    }
+
+    /// normalizes translated entries of single entity, returns description of all changes.
+    /// * punctuation is stripped from synonyms (and from values unless values are kept in source language)
+    /// * empty and duplicated synonyms are removed
+    /// * value of map entity is added to its synonyms (Dialogflow rejects map entries without it),
+    ///   values kept in source language included
+    /// * entry with the same value as some previous entry is removed
+    /// * synonym used already by some previous entry is removed, map entity entry
+    ///   is removed completely if its value is such synonym
+    pub fn normalize<E: SynonymsEntry>(
+        &self,
+        entries: &mut Vec<E>,
+        is_map: bool,
+        keep_values: bool,
+    ) -> Vec<String> {
+        let mut changes = vec![];
+
+        for entry in entries.iter_mut().filter(|entry| !entry.is_composite()) {
+            if !keep_values {
+                let stripped = strip_punctuation(entry.value()).to_owned();
+                if stripped != entry.value() && !stripped.is_empty() {
+                    changes.push(format!(
+                        "value '{}' changed to '{}'",
+                        entry.value(),
+                        stripped
+                    ));
+                    *entry.value_mut() = stripped;
+                }
+            }
+
+            let value = entry.value().to_owned();
+            let mut keys = collections::HashSet::new();
+            let mut synonyms = vec![];
+            for synonym in entry.synonyms().iter() {
+                let stripped = strip_punctuation(synonym);
+                if stripped.is_empty() {
+                    changes.push(format!("'{}': empty synonym '{}' removed", value, synonym));
+                    continue;
+                }
+                if stripped != synonym {
+                    changes.push(format!(
+                        "'{}': synonym '{}' changed to '{}'",
+                        value, synonym, stripped
+                    ));
+                }
+                if !keys.insert(self.key(stripped)) {
+                    changes.push(format!(
+                        "'{}': duplicated synonym '{}' removed",
+                        value, stripped
+                    ));
+                    continue;
+                }
+                synonyms.push(stripped.to_owned());
+            }
+
+            if is_map && !keys.contains(&self.key(&value)) {
+                changes.push(format!("'{}': value added to synonyms", value));
+                synonyms.insert(0, value.to_owned());
+            }
+            *entry.synonyms_mut() = synonyms;
+        }
+
+        // synonym (or value) can identify single entry only
+        let mut used_values = collections::HashSet::new();
+        let mut used_synonyms: collections::HashMap<String, String> = collections::HashMap::new();
+        let mut normalized = vec![];
+        for mut entry in entries.drain(..) {
+            if entry.is_composite() {
+                normalized.push(entry);
+                continue;
+            }
+            let value = entry.value().to_owned();
+            if !used_values.insert(self.key(&value)) {
+                changes.push(format!(
+                    "'{}': entry removed, value is used by another entry",
+                    value
+                ));
+                continue;
+            }
+
+            let mut value_collision = None;
+            let mut synonyms = vec![];
+            for synonym in entry.synonyms().iter() {
+                match used_synonyms.get(&self.key(synonym)) {
+                    Some(other_value) => {
+                        if is_map && self.key(synonym) == self.key(&value) {
+                            value_collision = Some(other_value.to_owned());
+                        } else {
+                            changes.push(format!(
+                                "'{}': synonym '{}' removed, it is used by '{}'",
+                                value, synonym, other_value
+                            ));
+                        }
+                    }
+                    None => synonyms.push(synonym.to_owned()),
+                }
+            }
+            if let Some(other_value) = value_collision {
+                changes.push(format!(
+                    "'{}': entry removed, value is synonym of '{}'",
+                    value, other_value
+                ));
+                continue;
+            }
+            if synonyms.is_empty() {
+                changes.push(format!("'{}': entry removed, no synonyms left", value));
+                continue;
+            }
+
+            for synonym in synonyms.iter() {
+                used_synonyms.insert(self.key(synonym), value.to_owned());
+            }
+            *entry.synonyms_mut() = synonyms;
+            normalized.push(entry);
+        }
+        *entries = normalized;
+
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::dialogflow::cx::CxEntity;
+    use crate::google::dialogflow::unknown_fields::UnknownFields;
+
+    fn entity(value: &str, synonyms: &[&str]) -> CxEntity {
+        CxEntity {
+            value: value.to_owned(),
+            synonyms: synonyms.iter().map(|synonym| synonym.to_string()).collect(),
+            language_code: None,
+            extra: UnknownFields::default(),
+        }
+    }
+
+    // cargo test -- --show-output test_normalize_synonyms
+    #[test]
+    fn test_normalize_synonyms() {
+        let normalization = SynonymsNormalization::default();
+        let mut entities = vec![
+            entity(
+                "Expresslieferung.",
+                &["Expresslieferung", "Express!", "", "express"],
+            ),
+            entity("Standard", &["normal", "Normal"]),
+            entity("Normal", &["gewöhnlich"]),
+            entity("Eilig", &["Express", "schnell"]),
+            entity("Expresslieferung", &["sofort"]),
+            entity("@sys.number:amount Pakete", &["@sys.number:amount Pakete"]),
+        ];
+
+        let changes = normalization.normalize(&mut entities, true, false);
+        assert_eq!(
+            changes,
+            vec![
+                "value 'Expresslieferung.' changed to 'Expresslieferung'",
+                "'Expresslieferung': synonym 'Express!' changed to 'Express'",
+                "'Expresslieferung': empty synonym '' removed",
+                "'Expresslieferung': duplicated synonym 'express' removed",
+                "'Standard': duplicated synonym 'Normal' removed",
+                "'Standard': value added to synonyms",
+                "'Normal': value added to synonyms",
+                "'Eilig': value added to synonyms",
+                "'Expresslieferung': value added to synonyms",
+                "'Normal': entry removed, value is synonym of 'Standard'",
+                "'Eilig': synonym 'Express' removed, it is used by 'Expresslieferung'",
+                "'Expresslieferung': entry removed, value is used by another entry",
+            ]
+        );
+        assert_eq!(
+            entities,
+            vec![
+                entity("Expresslieferung", &["Expresslieferung", "Express"]),
+                entity("Standard", &["Standard", "normal"]),
+                entity("Eilig", &["Eilig", "schnell"]),
+                entity("@sys.number:amount Pakete", &["@sys.number:amount Pakete"]),
+            ]
+        );
+
+        // diacritics aware deduplication, values kept in source language are not stripped
+        // but must be among synonyms of map entity as well
+        let normalization = SynonymsNormalization {
+            dedupe: SynonymsDedupe::IgnoreCaseDiacritics,
+        };
+        let mut entities = vec![
+            entity("cafe.", &["Café", "cafe", "Kaffee"]),
+            entity("tea", &["Tee"]),
+        ];
+        let changes = normalization.normalize(&mut entities, true, true);
+        assert_eq!(
+            changes,
+            vec![
+                "'cafe.': duplicated synonym 'cafe' removed",
+                "'cafe.': value added to synonyms",
+                "'tea': value added to synonyms",
+            ]
+        );
+        assert_eq!(
+            entities,
+            vec![
+                entity("cafe.", &["cafe.", "Café", "Kaffee"]),
+                entity("tea", &["tea", "Tee"]),
+            ]
+        );
+
+        // list entities have no such invariant
+        let mut entities = vec![entity("tea", &["Tee"])];
+        assert!(normalization
+            .normalize(&mut entities, false, true)
+            .is_empty());
+        assert_eq!(entities, vec![entity("tea", &["Tee"])]);
+    }
+}
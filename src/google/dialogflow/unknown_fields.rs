@@ -0,0 +1,615 @@
+//! # Unknown fields of exported agent
+//!
+//! Google adds new fields to the export format from time to time. Every struct of ES and CX
+//! agent (agent, intent, entity, response, flow, page, ...) captures fields it does not model in flattened `extra` map which is
+//! serialized back unchanged, i.e. translated agent contains such fields exactly as exported.
+//!
+//! When parsing the agent, serialized struct is compared with the original file. In strict mode
+//! any unknown field fails the parsing (like the lossless round trip check always did before),
+//! otherwise unknown fields are only listed (see [unknown_fields](fn.unknown_fields.html)).
+use crate::errors::{Error, Result};
+use crate::summary::ReportItem;
+use assert_json_diff::assert_json_eq_no_panic;
+use log::warn;
+use serde::ser::{self, Impossible, SerializeMap};
+use serde::{Deserialize, Serialize, Serializer};
+use serde_json::{Map, Value};
+
+/// name of newtype wrapping keys of unknown fields. Serializers of serde_json write such keys
+/// as plain strings, [UnknownFieldsFinder] recognizes unknown fields by it.
+const UNKNOWN_FIELD_KEY: &str = "UnknownFieldKey";
+
+/// fields of JSON object not modelled by the struct, use with `#[serde(flatten)]`
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(transparent)]
+pub struct UnknownFields(pub Map<String, Value>);
+
+impl UnknownFields {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+struct UnknownFieldKey<'a>(&'a str);
+
+impl Serialize for UnknownFieldKey<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(UNKNOWN_FIELD_KEY, self.0)
+    }
+}
+
+impl Serialize for UnknownFields {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in self.0.iter() {
+            map.serialize_entry(&UnknownFieldKey(key), value)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+/// path of the field as used in reports, e.g. responses[0].messages[1].newField
+fn path_to_string(path: &[PathSegment]) -> String {
+    let mut result = String::new();
+    for segment in path.iter() {
+        match segment {
+            PathSegment::Key(key) if result.is_empty() => result.push_str(key),
+            PathSegment::Key(key) => result.push_str(&format!(".{}", key)),
+            PathSegment::Index(idx) => result.push_str(&format!("[{}]", idx)),
+        }
+    }
+    result
+}
+
+/// removes field with given path from JSON value
+fn remove_field(value: &mut Value, path: &[PathSegment]) {
+    match (value, path) {
+        (Value::Object(map), [PathSegment::Key(key)]) => {
+            map.remove(key);
+        }
+        (Value::Object(map), [PathSegment::Key(key), rest @ ..]) => {
+            if let Some(child) = map.get_mut(key) {
+                remove_field(child, rest);
+            }
+        }
+        (Value::Array(items), [PathSegment::Index(idx), rest @ ..]) => {
+            if let Some(child) = items.get_mut(*idx) {
+                remove_field(child, rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// serializer which produces nothing, it only collects paths of unknown fields
+struct UnknownFieldsFinder<'a> {
+    path: Vec<PathSegment>,
+    found: &'a mut Vec<Vec<PathSegment>>,
+}
+
+impl<'a> UnknownFieldsFinder<'a> {
+    fn child(&mut self, segment: PathSegment) -> UnknownFieldsFinder<'_> {
+        let mut path = self.path.clone();
+        path.push(segment);
+        UnknownFieldsFinder {
+            path,
+            found: self.found,
+        }
+    }
+
+    fn compound(self) -> UnknownFieldsCompound<'a> {
+        UnknownFieldsCompound {
+            finder: self,
+            index: 0,
+            key: None,
+        }
+    }
+}
+
+struct UnknownFieldsCompound<'a> {
+    finder: UnknownFieldsFinder<'a>,
+    index: usize,
+    key: Option<FieldKey>,
+}
+
+impl UnknownFieldsCompound<'_> {
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        let index = self.index;
+        self.index += 1;
+        value.serialize(self.finder.child(PathSegment::Index(index)))
+    }
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        value.serialize(self.finder.child(PathSegment::Key(key.to_owned())))
+    }
+}
+
+macro_rules! ignore_primitives {
+    ($($method:ident: $type:ty),*) => {
+        $(fn $method(self, _: $type) -> serde_json::Result<()> {
+            Ok(())
+        })*
+    };
+}
+
+impl<'a> Serializer for UnknownFieldsFinder<'a> {
+    type Ok = ();
+    type Error = serde_json::Error;
+    type SerializeSeq = UnknownFieldsCompound<'a>;
+    type SerializeTuple = UnknownFieldsCompound<'a>;
+    type SerializeTupleStruct = UnknownFieldsCompound<'a>;
+    type SerializeTupleVariant = UnknownFieldsCompound<'a>;
+    type SerializeMap = UnknownFieldsCompound<'a>;
+    type SerializeStruct = UnknownFieldsCompound<'a>;
+    type SerializeStructVariant = UnknownFieldsCompound<'a>;
+
+    ignore_primitives!(
+        serialize_bool: bool, serialize_i8: i8, serialize_i16: i16, serialize_i32: i32,
+        serialize_i64: i64, serialize_u8: u8, serialize_u16: u16, serialize_u32: u32,
+        serialize_u64: u64, serialize_f32: f32, serialize_f64: f64, serialize_char: char,
+        serialize_str: &str, serialize_bytes: &[u8], serialize_unit_struct: &'static str
+    );
+
+    fn serialize_none(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> serde_json::Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> serde_json::Result<()> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        mut self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        value.serialize(self.child(PathSegment::Key(variant.to_owned())))
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> serde_json::Result<Self::SerializeSeq> {
+        Ok(self.compound())
+    }
+
+    fn serialize_tuple(self, _: usize) -> serde_json::Result<Self::SerializeTuple> {
+        Ok(self.compound())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeTupleStruct> {
+        Ok(self.compound())
+    }
+
+    fn serialize_tuple_variant(
+        mut self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeTupleVariant> {
+        self.path.push(PathSegment::Key(variant.to_owned()));
+        Ok(self.compound())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> serde_json::Result<Self::SerializeMap> {
+        Ok(self.compound())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeStruct> {
+        Ok(self.compound())
+    }
+
+    fn serialize_struct_variant(
+        mut self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeStructVariant> {
+        self.path.push(PathSegment::Key(variant.to_owned()));
+        Ok(self.compound())
+    }
+}
+
+impl ser::SerializeSeq for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_element(self, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTuple for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_element(self, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleStruct for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_element(self, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeTupleVariant for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_element(self, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> serde_json::Result<()> {
+        self.key = Some(key.serialize(FieldKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> serde_json::Result<()> {
+        match self.key.take() {
+            Some(FieldKey::Known(key)) => self.serialize_field(&key, value),
+            Some(FieldKey::Unknown(key)) => {
+                let mut path = self.finder.path.clone();
+                path.push(PathSegment::Key(key));
+                self.finder.found.push(path);
+                Ok(())
+            }
+            None => Err(ser::Error::custom("map value serialized before key")),
+        }
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for UnknownFieldsCompound<'_> {
+    type Ok = ();
+    type Error = serde_json::Error;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> serde_json::Result<()> {
+        UnknownFieldsCompound::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> serde_json::Result<()> {
+        Ok(())
+    }
+}
+
+enum FieldKey {
+    Known(String),
+    Unknown(String),
+}
+
+/// serializes map keys, keys of unknown fields are recognized by [UNKNOWN_FIELD_KEY] newtype
+struct FieldKeySerializer;
+
+macro_rules! primitive_keys {
+    ($($method:ident: $type:ty),*) => {
+        $(fn $method(self, key: $type) -> serde_json::Result<FieldKey> {
+            Ok(FieldKey::Known(key.to_string()))
+        })*
+    };
+}
+
+fn key_must_be_string() -> serde_json::Error {
+    ser::Error::custom("key must be a string")
+}
+
+impl Serializer for FieldKeySerializer {
+    type Ok = FieldKey;
+    type Error = serde_json::Error;
+    type SerializeSeq = Impossible<FieldKey, serde_json::Error>;
+    type SerializeTuple = Impossible<FieldKey, serde_json::Error>;
+    type SerializeTupleStruct = Impossible<FieldKey, serde_json::Error>;
+    type SerializeTupleVariant = Impossible<FieldKey, serde_json::Error>;
+    type SerializeMap = Impossible<FieldKey, serde_json::Error>;
+    type SerializeStruct = Impossible<FieldKey, serde_json::Error>;
+    type SerializeStructVariant = Impossible<FieldKey, serde_json::Error>;
+
+    primitive_keys!(
+        serialize_bool: bool, serialize_i8: i8, serialize_i16: i16, serialize_i32: i32,
+        serialize_i64: i64, serialize_u8: u8, serialize_u16: u16, serialize_u32: u32,
+        serialize_u64: u64, serialize_f32: f32, serialize_f64: f64, serialize_char: char,
+        serialize_str: &str
+    );
+
+    fn serialize_bytes(self, _: &[u8]) -> serde_json::Result<FieldKey> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_none(self) -> serde_json::Result<FieldKey> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> serde_json::Result<FieldKey> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> serde_json::Result<FieldKey> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_unit_struct(self, _: &'static str) -> serde_json::Result<FieldKey> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        variant: &'static str,
+    ) -> serde_json::Result<FieldKey> {
+        Ok(FieldKey::Known(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> serde_json::Result<FieldKey> {
+        match value.serialize(self)? {
+            FieldKey::Known(key) if name == UNKNOWN_FIELD_KEY => Ok(FieldKey::Unknown(key)),
+            key => Ok(key),
+        }
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> serde_json::Result<FieldKey> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_seq(self, _: Option<usize>) -> serde_json::Result<Self::SerializeSeq> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple(self, _: usize) -> serde_json::Result<Self::SerializeTuple> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_map(self, _: Option<usize>) -> serde_json::Result<Self::SerializeMap> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeStruct> {
+        Err(key_must_be_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> serde_json::Result<Self::SerializeStructVariant> {
+        Err(key_must_be_string())
+    }
+}
+
+/// checks that deserialized struct serializes back to the original JSON (i.e. no information
+/// will be lost when writing translated agent) and returns paths of unknown fields.
+/// In strict mode unknown fields are not allowed, the check is done without them.
+pub fn unknown_fields<T: Serialize>(
+    deserialized: &T,
+    original_str: &str,
+    strict: bool,
+) -> Result<Vec<String>> {
+    let original: Value = serde_json::from_str(original_str)?;
+    let mut serialized = serde_json::to_value(deserialized)?;
+
+    let mut found = vec![];
+    deserialized.serialize(UnknownFieldsFinder {
+        path: vec![],
+        found: &mut found,
+    })?;
+
+    if strict {
+        for path in found.iter() {
+            remove_field(&mut serialized, path);
+        }
+    }
+    assert_json_eq_no_panic(&serialized, &original).map_err(Error::new)?;
+
+    Ok(found.iter().map(|path| path_to_string(path)).collect())
+}
+
+/// unknown fields are copied to translated agent unchanged, the user is warned about them
+pub fn report_unknown_fields(report: &mut Vec<ReportItem>, file_name: &str, fields: Vec<String>) {
+    for field in fields.into_iter() {
+        warn!(
+            "unknown field {} in {} is copied unchanged",
+            field, file_name
+        );
+        report.push(ReportItem::new(
+            "unknown_field",
+            file_name,
+            format!("unknown field {} is copied unchanged", field),
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Item {
+        name: String,
+        #[serde(flatten)]
+        extra: UnknownFields,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Container {
+        id: String,
+        items: Vec<Item>,
+        #[serde(flatten)]
+        extra: UnknownFields,
+    }
+
+    // cargo test -- --show-output test_unknown_fields
+    #[test]
+    fn test_unknown_fields() -> Result<()> {
+        let json = r#"
+            {
+              "id": "1",
+              "newField": { "enabled": true },
+              "items": [ { "name": "a" }, { "name": "b", "newItemField": [1, 2] } ]
+            }
+            "#;
+        let container: Container = serde_json::from_str(json)?;
+        assert_eq!(container.extra.0["newField"]["enabled"], true);
+        assert!(container.items[0].extra.is_empty());
+
+        // unknown fields of nested structs are found regardless of JSON serialization
+        let mut found = vec![];
+        container.serialize(UnknownFieldsFinder {
+            path: vec![],
+            found: &mut found,
+        })?;
+        let mut without_unknown = serde_json::to_value(&container)?;
+        for path in found.iter() {
+            remove_field(&mut without_unknown, path);
+        }
+        assert_eq!(
+            without_unknown,
+            serde_json::json!({ "id": "1", "items": [ { "name": "a" }, { "name": "b" } ] })
+        );
+
+        // unknown fields are serialized back unchanged
+        assert_eq!(
+            serde_json::to_value(&container)?,
+            serde_json::from_str::<Value>(json)?
+        );
+        assert_eq!(
+            unknown_fields(&container, json, false)?,
+            vec!["items[1].newItemField", "newField"]
+        );
+        assert!(unknown_fields(&container, json, true).is_err());
+
+        let json = r#"{ "id": "1", "items": [ { "name": "a" } ] }"#;
+        let container: Container = serde_json::from_str(json)?;
+        assert!(unknown_fields(&container, json, true)?.is_empty());
+        Ok(())
+    }
+}
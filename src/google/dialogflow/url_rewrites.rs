@@ -0,0 +1,235 @@
+//! # Rewriting of URLs linked by responses
+//!
+//! Link-outs, card buttons, browse carousel items and postbacks usually point to pages
+//! in source language. Rewrite file is JSON object where keys are target languages and values
+//! are lists of regex replacements applied (in given order) to every linked URL of messages
+//! cloned to that language, e.g.
+//!
+//! ```json
+//! {
+//!   "de": [
+//!     { "match": "/en-us/", "replace": "/de-de/" },
+//!     { "match": "([?&]lang=)en\\b", "replace": "${1}de" }
+//!   ]
+//! }
+//! ```
+//!
+//! Replacement can reference capture groups of the regex (`$1`, `${name}`).
+use crate::errors::{Error, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Deserialize)]
+struct UrlRewriteRule {
+    #[serde(rename = "match")]
+    match_regex: String,
+    replace: String,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct UrlRewrites {
+    rules: collections::HashMap<String, Vec<(Regex, String)>>,
+}
+
+impl UrlRewrites {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        UrlRewrites::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(json: &str) -> Result<Self> {
+        let rules: collections::HashMap<String, Vec<UrlRewriteRule>> = serde_json::from_str(json)?;
+        let mut url_rewrites = UrlRewrites::default();
+        for (lang, lang_rules) in rules.into_iter() {
+            let mut compiled = vec![];
+            for rule in lang_rules.into_iter() {
+                let regex = Regex::new(&rule.match_regex).map_err(|err| {
+                    Error::new(format!(
+                        "invalid URL rewrite rule {} for {}: {}",
+                        rule.match_regex, lang, err
+                    ))
+                })?;
+                compiled.push((regex, rule.replace));
+            }
+            url_rewrites.rules.insert(lang.to_lowercase(), compiled);
+        }
+        Ok(url_rewrites)
+    }
+
+    /// rewritten URL for given language, None if no rule changed the URL.
+    /// Rules of specific language (e.g. pt-br) take precedence over rules of base language (e.g. pt).
+    pub fn rewrite(&self, url: &str, lang: &str) -> Option<String> {
+        let lang = lang.to_lowercase();
+        let rules = self.rules.get(&lang).or_else(|| {
+            lang.split_once('-')
+                .and_then(|(base_lang, _)| self.rules.get(base_lang))
+        })?;
+
+        let mut rewritten = url.to_owned();
+        for (regex, replace) in rules.iter() {
+            rewritten = regex.replace_all(&rewritten, replace.as_str()).to_string();
+        }
+
+        if rewritten == url {
+            None
+        } else {
+            Some(rewritten)
+        }
+    }
+
+    /// rewrites given URLs in place, returns description of every rewritten and untouched URL
+    pub fn rewrite_urls(&self, urls: Vec<&mut String>, lang: &str) -> Vec<String> {
+        let mut findings = vec![];
+        for url in urls.into_iter().filter(|url| !url.is_empty()) {
+            match self.rewrite(url, lang) {
+                Some(rewritten_url) => {
+                    findings.push(format!("{} rewritten to {}", url, rewritten_url));
+                    *url = rewritten_url;
+                }
+                None => findings.push(format!("no {} rewrite rule matches {}", lang, url)),
+            }
+        }
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::dialogflow::responses::MessageType;
+
+    // cargo test -- --show-output test_url_rewrites
+    #[test]
+    fn test_url_rewrites() -> Result<()> {
+        let url_rewrites = UrlRewrites::parse(
+            r#"
+            {
+              "de": [
+                { "match": "/en-us/", "replace": "/de-de/" },
+                { "match": "([?&]lang=)en\\b", "replace": "${1}de" }
+              ],
+              "pt": [ { "match": "/en/", "replace": "/pt/" } ]
+            }
+            "#,
+        )?;
+
+        assert_eq!(
+            url_rewrites.rewrite("https://example.com/en-us/help?lang=en&x=1", "de"),
+            Some("https://example.com/de-de/help?lang=de&x=1".to_owned())
+        );
+        assert_eq!(
+            url_rewrites.rewrite("https://example.com/en/help", "pt-BR"),
+            Some("https://example.com/pt/help".to_owned())
+        );
+        assert_eq!(
+            url_rewrites.rewrite("https://example.com/help?lang=english", "de"),
+            None
+        );
+        assert_eq!(
+            url_rewrites.rewrite("https://example.com/en/help", "fr"),
+            None
+        );
+
+        let mut link = "https://example.com/en-us/help".to_owned();
+        let mut postback = "https://example.com/contact".to_owned();
+        assert_eq!(
+            url_rewrites.rewrite_urls(vec![&mut link, &mut postback], "de"),
+            vec![
+                "https://example.com/en-us/help rewritten to https://example.com/de-de/help",
+                "no de rewrite rule matches https://example.com/contact"
+            ]
+        );
+        assert_eq!(link, "https://example.com/de-de/help");
+
+        assert!(UrlRewrites::parse(r#"{ "de": [ { "match": "(", "replace": "" } ] }"#).is_err());
+        assert!(UrlRewrites::parse(r#"{ "de": { "match": "/en/" } }"#).is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_rewrite_message_link_urls
+    #[test]
+    fn test_rewrite_message_link_urls() -> Result<()> {
+        let url_rewrites =
+            UrlRewrites::parse(r#"{ "de": [ { "match": "/en-us/", "replace": "/de-de/" } ] }"#)?;
+        let mut messages: Vec<MessageType> = serde_json::from_str(
+            r#"
+            [
+              {
+                "type": "1",
+                "platform": "facebook",
+                "lang": "en",
+                "title": "Lights",
+                "buttons": [
+                  { "text": "Manual", "postback": "https://example.com/en-us/manual.pdf" },
+                  { "text": "Turn off", "postback": "turn off the lights" }
+                ]
+              },
+              {
+                "type": "basic_card",
+                "platform": "google",
+                "lang": "en",
+                "formattedText": "Smart home",
+                "buttons": [
+                  {
+                    "title": "Shop",
+                    "openUrlAction": { "url": "https://example.com/en-us/shop", "urlTypeHint": "URL_TYPE_HINT_UNSPECIFIED" }
+                  }
+                ]
+              },
+              {
+                "type": "link_out_chip",
+                "platform": "google",
+                "lang": "en",
+                "destinationName": "Help",
+                "url": "https://example.com/en-us/help"
+              },
+              {
+                "type": "browse_carousel_card",
+                "platform": "google",
+                "lang": "en",
+                "items": [
+                  {
+                    "title": "Lamps",
+                    "description": "",
+                    "footer": "",
+                    "openUrlAction": { "url": "https://lamps.example.com/", "urlTypeHint": "URL_TYPE_HINT_UNSPECIFIED" },
+                    "image": { "url": "https://example.com/en-us/lamps.png", "accessibilityText": "lamps" }
+                  }
+                ]
+              },
+              { "type": "0", "lang": "en", "speech": "https://example.com/en-us/help" }
+            ]
+            "#,
+        )?;
+
+        let findings: Vec<String> = messages
+            .iter_mut()
+            .flat_map(|message| url_rewrites.rewrite_urls(message.link_urls_mut(), "de"))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                "https://example.com/en-us/manual.pdf rewritten to https://example.com/de-de/manual.pdf",
+                "https://example.com/en-us/shop rewritten to https://example.com/de-de/shop",
+                "https://example.com/en-us/help rewritten to https://example.com/de-de/help",
+                "no de rewrite rule matches https://lamps.example.com/",
+            ]
+        );
+
+        let messages = serde_json::to_value(&messages)?;
+        assert_eq!(messages[0]["buttons"][1]["postback"], "turn off the lights");
+        assert_eq!(
+            messages[1]["buttons"][0]["openUrlAction"]["url"],
+            "https://example.com/de-de/shop"
+        );
+        // images are media, not links, see media module
+        assert_eq!(
+            messages[3]["items"][0]["image"]["url"],
+            "https://example.com/en-us/lamps.png"
+        );
+        assert_eq!(messages[4]["speech"], "https://example.com/en-us/help");
+        Ok(())
+    }
+}
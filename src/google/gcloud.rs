@@ -1,11 +1,76 @@
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 pub mod auth;
+pub mod http;
+pub mod rate_limit;
 pub mod storage_bucket_mgmt;
 pub mod translate;
 
+pub const DEFAULT_STORAGE_URL: &str = "https://storage.googleapis.com";
+pub const DEFAULT_TRANSLATE_URL: &str = "https://translation.googleapis.com";
+pub const DEFAULT_DIALOGFLOW_URL: &str = "https://dialogflow.googleapis.com";
+
+/// delay between creation of temporary V3 storage buckets.
+/// Google Cloud Storage API is very sensitive when creating buckets rapidly in sequence
+pub const DEFAULT_BUCKET_CREATION_DELAY_MS: u64 = 20_000;
+
+/// delay between checks of long running operation (e.g. Dialogflow agent export or V3 batch translation)
+pub const DEFAULT_OPERATION_POLL_INTERVAL_MS: u64 = 2_000;
+
+/// maximal time to wait for long running operation to finish before giving up
+pub const DEFAULT_OPERATION_TIMEOUT_MS: u64 = 600_000;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse {
     pub status_code: String,
     pub body: String,
 }
+
+/// Google API endpoints used for translation. Base URLs can be overridden
+/// so that whole translation can run against local emulators (e.g. fake-gcs-server)
+#[derive(Debug, Clone)]
+pub struct GoogleApiConfig {
+    pub storage_url: String,
+    pub translate_url: String,
+    pub dialogflow_url: String,
+    pub bucket_creation_delay: Duration,
+    pub operation_poll_interval: Duration,
+    pub operation_timeout: Duration,
+}
+
+impl GoogleApiConfig {
+    pub fn new(
+        storage_url: Option<&str>,
+        translate_url: Option<&str>,
+        dialogflow_url: Option<&str>,
+        bucket_creation_delay: Option<Duration>,
+        operation_timeout: Option<Duration>,
+    ) -> Self {
+        GoogleApiConfig {
+            storage_url: storage_url
+                .unwrap_or(DEFAULT_STORAGE_URL)
+                .trim_end_matches('/')
+                .to_owned(),
+            translate_url: translate_url
+                .unwrap_or(DEFAULT_TRANSLATE_URL)
+                .trim_end_matches('/')
+                .to_owned(),
+            dialogflow_url: dialogflow_url
+                .unwrap_or(DEFAULT_DIALOGFLOW_URL)
+                .trim_end_matches('/')
+                .to_owned(),
+            bucket_creation_delay: bucket_creation_delay
+                .unwrap_or_else(|| Duration::from_millis(DEFAULT_BUCKET_CREATION_DELAY_MS)),
+            operation_poll_interval: Duration::from_millis(DEFAULT_OPERATION_POLL_INTERVAL_MS),
+            operation_timeout: operation_timeout
+                .unwrap_or_else(|| Duration::from_millis(DEFAULT_OPERATION_TIMEOUT_MS)),
+        }
+    }
+}
+
+impl Default for GoogleApiConfig {
+    fn default() -> Self {
+        GoogleApiConfig::new(None, None, None, None, None)
+    }
+}
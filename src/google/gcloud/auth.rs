@@ -1,11 +1,42 @@
-use crate::errors::Result;
-#[allow(unused_imports)]
-use async_std::{fs, task};
+//! # OAuth2 access token management for Google APIs
+//!
+//! Credentials can be provided in several ways, resolved in following order:
+//! 1. explicit credentials file (`--cred-file`)
+//! 2. credentials JSON passed directly in environment variable `GDF_TRANSLATE_CREDENTIALS_JSON`
+//! 3. credentials file referenced by environment variable `GOOGLE_APPLICATION_CREDENTIALS`
+//! 4. gcloud application default credentials (`gcloud auth application-default login`)
+//!
+//! Both service account keys and gcloud `authorized_user` credentials are supported.
+//! Access tokens are cached by [`TokenProvider`](struct.TokenProvider.html) and refreshed
+//! shortly before they expire so that long running translations do not fail with HTTP 401.
+use crate::errors::{Error, Result};
+use async_std::{fs, sync::Mutex};
 use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use log::debug;
 use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::SystemTime;
 use surf;
 
+/// default Google OAuth2 token endpoint
+pub const DEFAULT_TOKEN_URL: &str = "https://www.googleapis.com/oauth2/v4/token";
+
+/// environment variable which can hold whole credentials JSON (handy for CI pipelines)
+pub const ENV_CREDENTIALS_JSON: &str = "GDF_TRANSLATE_CREDENTIALS_JSON";
+
+/// standard environment variable pointing to credentials file
+pub const ENV_GOOGLE_APPLICATION_CREDENTIALS: &str = "GOOGLE_APPLICATION_CREDENTIALS";
+
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// token is refreshed when it is about to expire in less than this number of seconds
+const TOKEN_REFRESH_MARGIN_SECS: u64 = 300;
+
+/// used when token endpoint does not return expires_in
+const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 3600;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Claims {
     iss: String,
@@ -29,10 +60,196 @@ pub struct GDFCredentials {
     pub client_x509_cert_url: String,
 }
 
+/// credentials created by `gcloud auth application-default login`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorizedUserCredentials {
+    pub r#type: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_project_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum CredentialSource {
+    ServiceAccount(GDFCredentials),
+    AuthorizedUser(AuthorizedUserCredentials),
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GoogleApisOauthToken {
     pub access_token: String,
     pub token_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CredentialsType {
+    r#type: String,
+}
+
+impl CredentialSource {
+    pub fn from_json_str(json: &str) -> Result<CredentialSource> {
+        let cred_type = serde_json::from_str::<CredentialsType>(json)?;
+        match cred_type.r#type.as_str() {
+            "service_account" => Ok(CredentialSource::ServiceAccount(serde_json::from_str::<
+                GDFCredentials,
+            >(json)?)),
+            "authorized_user" => Ok(CredentialSource::AuthorizedUser(serde_json::from_str::<
+                AuthorizedUserCredentials,
+            >(json)?)),
+            other => Err(Error::new(format!(
+                "unsupported credentials type '{}', expected service_account or authorized_user",
+                other
+            ))),
+        }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file_name: P) -> Result<CredentialSource> {
+        let file_name = file_name.as_ref();
+        let file_str = std::fs::read_to_string(file_name).map_err(|e| {
+            Error::new(format!(
+                "unable to read credentials file {}: {}",
+                file_name.display(),
+                e
+            ))
+        })?;
+        CredentialSource::from_json_str(&file_str)
+    }
+
+    /// resolves credentials from explicit file or (if not provided) from environment
+    pub fn resolve(cred_file: Option<&Path>) -> Result<CredentialSource> {
+        if let Some(file_name) = cred_file {
+            debug!("using credentials file {}", file_name.display());
+            return CredentialSource::from_file(file_name);
+        }
+
+        if let Ok(json) = env::var(ENV_CREDENTIALS_JSON) {
+            if !json.trim().is_empty() {
+                debug!("using credentials from {}", ENV_CREDENTIALS_JSON);
+                return CredentialSource::from_json_str(&json);
+            }
+        }
+
+        if let Ok(file_name) = env::var(ENV_GOOGLE_APPLICATION_CREDENTIALS) {
+            if !file_name.trim().is_empty() {
+                debug!(
+                    "using credentials file {} from {}",
+                    file_name, ENV_GOOGLE_APPLICATION_CREDENTIALS
+                );
+                return CredentialSource::from_file(&file_name);
+            }
+        }
+
+        if let Some(adc_path) = application_default_credentials_path() {
+            if adc_path.exists() {
+                debug!("using application default credentials {:?}", adc_path);
+                return CredentialSource::from_file(&adc_path);
+            }
+        }
+
+        Err(Error::new(format!(
+            "no credentials found. Use --cred-file, set {} or {}, or run 'gcloud auth application-default login'",
+            ENV_CREDENTIALS_JSON, ENV_GOOGLE_APPLICATION_CREDENTIALS
+        )))
+    }
+
+    /// project id associated with credentials (if any)
+    pub fn project_id(&self) -> Option<&str> {
+        match self {
+            CredentialSource::ServiceAccount(cred) => Some(&cred.project_id),
+            CredentialSource::AuthorizedUser(cred) => cred.quota_project_id.as_deref(),
+        }
+    }
+}
+
+/// location of gcloud application default credentials file
+fn application_default_credentials_path() -> Option<PathBuf> {
+    let config_dir = if let Ok(dir) = env::var("CLOUDSDK_CONFIG") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        PathBuf::from(env::var("APPDATA").ok()?).join("gcloud")
+    } else {
+        PathBuf::from(env::var("HOME").ok()?)
+            .join(".config")
+            .join("gcloud")
+    };
+    Some(config_dir.join("application_default_credentials.json"))
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[derive(Debug, Clone)]
+struct CachedToken {
+    bearer_token: String,
+    expires_at: u64,
+}
+
+impl CachedToken {
+    fn is_fresh(&self, now: u64) -> bool {
+        now + TOKEN_REFRESH_MARGIN_SECS < self.expires_at
+    }
+}
+
+/// Caching access token provider shared by all Google API calls (storage, V2, V3).
+/// Cloning is cheap, all clones share the same token cache.
+#[derive(Debug, Clone)]
+pub struct TokenProvider {
+    credentials: Arc<CredentialSource>,
+    token_url: String,
+    cache: Arc<Mutex<Option<CachedToken>>>,
+}
+
+impl TokenProvider {
+    pub fn new(credentials: CredentialSource, token_url: Option<&str>) -> Self {
+        TokenProvider {
+            credentials: Arc::new(credentials),
+            token_url: token_url.unwrap_or(DEFAULT_TOKEN_URL).to_owned(),
+            cache: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    pub fn credentials(&self) -> &CredentialSource {
+        &self.credentials
+    }
+
+    /// returns valid bearer token (i.e. 'Bearer ya29...'), refreshing it if needed.
+    /// Lock is held while refreshing so that parallel tasks do not request token at the same time.
+    pub async fn get_token(&self) -> Result<String> {
+        let mut cache = self.cache.lock().await;
+        if let Some(cached) = cache.as_ref() {
+            if cached.is_fresh(now_secs()) {
+                return Ok(cached.bearer_token.clone());
+            }
+            debug!("access token is about to expire, refreshing");
+        }
+
+        let token = request_token(&self.credentials, &self.token_url).await?;
+        let cached = CachedToken {
+            bearer_token: format!("Bearer {}", token.access_token),
+            expires_at: now_secs() + token.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS),
+        };
+        debug!(
+            "new access token retrieved, expires at {}",
+            cached.expires_at
+        );
+        let bearer_token = cached.bearer_token.clone();
+        *cache = Some(cached);
+        Ok(bearer_token)
+    }
+
+    /// drops cached token, next call of get_token will request new one.
+    /// Intended for situation when API rejects token with HTTP 401.
+    pub async fn invalidate(&self) {
+        *self.cache.lock().await = None;
+    }
 }
 
 pub async fn file_to_gdf_credentials(file_name: &str) -> Result<GDFCredentials> {
@@ -41,15 +258,12 @@ pub async fn file_to_gdf_credentials(file_name: &str) -> Result<GDFCredentials>
     Ok(cred)
 }
 
-fn new_token_from_cred(cred: &GDFCredentials) -> Result<String> {
-    let _now = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
+fn new_token_from_cred(cred: &GDFCredentials, token_url: &str) -> Result<String> {
+    let _now = now_secs();
     let claims = Claims {
         iss: cred.client_email.clone(),
-        scope: "https://www.googleapis.com/auth/cloud-platform".to_owned(),
-        aud: "https://www.googleapis.com/oauth2/v4/token".to_owned(),
+        scope: CLOUD_PLATFORM_SCOPE.to_owned(),
+        aud: token_url.to_owned(),
         exp: _now + 3600,
         iat: _now,
     };
@@ -65,29 +279,156 @@ fn str_to_encoding_key(priv_key_str: String) -> Result<EncodingKey> {
     Ok(key)
 }
 
-pub async fn get_google_api_token(gdf_credentials_file: &str) -> Result<GoogleApisOauthToken> {
-    let cred = file_to_gdf_credentials(gdf_credentials_file).await?;
-    let token = new_token_from_cred(&cred)?;
+fn token_request_body(credentials: &CredentialSource, token_url: &str) -> Result<String> {
+    let body = match credentials {
+        CredentialSource::ServiceAccount(cred) => {
+            let token = new_token_from_cred(cred, token_url)?;
+            serde_urlencoded::to_string([
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", &token),
+            ])?
+        }
+        CredentialSource::AuthorizedUser(cred) => serde_urlencoded::to_string([
+            ("grant_type", "refresh_token"),
+            ("client_id", &cred.client_id),
+            ("client_secret", &cred.client_secret),
+            ("refresh_token", &cred.refresh_token),
+        ])?,
+    };
+    Ok(body)
+}
 
-    let body = format!(
-        "grant_type=urn:ietf:params:oauth:grant-type:jwt-bearer&assertion={}",
-        token
-    );
+async fn request_token(
+    credentials: &CredentialSource,
+    token_url: &str,
+) -> Result<GoogleApisOauthToken> {
+    let body = token_request_body(credentials, token_url)?;
 
-    let resp = surf::post("https://www.googleapis.com/oauth2/v4/token")
+    debug!("requesting access token from {}", token_url);
+    let mut resp = surf::post(token_url)
         .body_string(body)
         .set_header("Content-Type", "application/x-www-form-urlencoded")
-        .recv_string()
         .await?;
 
-    let google_apis_token = serde_json::from_str::<GoogleApisOauthToken>(&resp)?;
+    let status = resp.status();
+    let resp_body = resp.body_string().await?;
+    if !status.is_success() {
+        return Err(Error::new(format!(
+            "unable to retrieve access token from {}. HTTP status: {}, body: {}",
+            token_url, status, resp_body
+        )));
+    }
+
+    let google_apis_token = serde_json::from_str::<GoogleApisOauthToken>(&resp_body)?;
     Ok(google_apis_token)
 }
 
+pub async fn get_google_api_token(gdf_credentials_file: &str) -> Result<GoogleApisOauthToken> {
+    let credentials = CredentialSource::from_file(gdf_credentials_file)?;
+    request_token(&credentials, DEFAULT_TOKEN_URL).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::errors::Result;
+    use crate::google::gcloud::http::test_server::{scripted_server, ScriptedResponse};
+    use async_std::task;
+    use std::thread;
+
+    const AUTHORIZED_USER_JSON: &str = r#"{
+        "client_id": "dummy-client-id.apps.googleusercontent.com",
+        "client_secret": "dummy-client-secret",
+        "quota_project_id": "express-tracking",
+        "refresh_token": "1//0dummy-refresh-token",
+        "type": "authorized_user"
+    }"#;
+
+    /// fake OAuth server answering requests with tokens token0, token1, ... valid for expires_in seconds
+    fn fake_oauth_server(
+        expires_in: u64,
+        requests: usize,
+    ) -> (String, thread::JoinHandle<Vec<(String, String)>>) {
+        let responses = (0..requests)
+            .map(|idx| {
+                ScriptedResponse::new(
+                    200,
+                    &format!(
+                        r#"{{"access_token":"token{}","token_type":"Bearer","expires_in":{}}}"#,
+                        idx, expires_in
+                    ),
+                )
+            })
+            .collect();
+        let (url, handle) = scripted_server(responses);
+        (format!("{}/token", url), handle)
+    }
+
+    // cargo test -- --show-output test_credential_source_from_json_str
+    #[test]
+    fn test_credential_source_from_json_str() -> Result<()> {
+        let cred = CredentialSource::from_json_str(AUTHORIZED_USER_JSON)?;
+        match &cred {
+            CredentialSource::AuthorizedUser(user) => {
+                assert_eq!(user.refresh_token, "1//0dummy-refresh-token")
+            }
+            _ => panic!("expected authorized_user credentials"),
+        }
+        assert_eq!(cred.project_id(), Some("express-tracking"));
+
+        let unsupported = CredentialSource::from_json_str(r#"{"type": "external_account"}"#);
+        assert!(unsupported.is_err());
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_cached_token_is_fresh
+    #[test]
+    fn test_cached_token_is_fresh() {
+        let token = CachedToken {
+            bearer_token: "Bearer xyz".to_owned(),
+            expires_at: 10_000,
+        };
+        assert!(token.is_fresh(10_000 - TOKEN_REFRESH_MARGIN_SECS - 1));
+        assert!(!token.is_fresh(10_000 - TOKEN_REFRESH_MARGIN_SECS));
+        assert!(!token.is_fresh(10_001));
+    }
+
+    // cargo test -- --show-output test_token_provider_caching
+    #[test]
+    fn test_token_provider_caching() -> Result<()> {
+        let (url, server) = fake_oauth_server(3600, 1);
+        let provider = TokenProvider::new(
+            CredentialSource::from_json_str(AUTHORIZED_USER_JSON)?,
+            Some(&url),
+        );
+        let shared = provider.clone();
+        assert_eq!(task::block_on(provider.get_token())?, "Bearer token0");
+        // served from cache, fake server accepts just one request
+        assert_eq!(task::block_on(shared.get_token())?, "Bearer token0");
+
+        let requests = server.join().unwrap();
+        let body = &requests[0].1;
+        assert!(body.contains("grant_type=refresh_token"));
+        assert!(body.contains("refresh_token=1%2F%2F0dummy-refresh-token"));
+        Ok(())
+    }
+
+    // cargo test -- --show-output test_token_provider_refresh
+    #[test]
+    fn test_token_provider_refresh() -> Result<()> {
+        // token expiring within refresh margin must be requested again on every call
+        let (url, server) = fake_oauth_server(TOKEN_REFRESH_MARGIN_SECS, 3);
+        let provider = TokenProvider::new(
+            CredentialSource::from_json_str(AUTHORIZED_USER_JSON)?,
+            Some(&url),
+        );
+        assert_eq!(task::block_on(provider.get_token())?, "Bearer token0");
+        assert_eq!(task::block_on(provider.get_token())?, "Bearer token1");
+        task::block_on(provider.invalidate());
+        assert_eq!(task::block_on(provider.get_token())?, "Bearer token2");
+        server.join().unwrap();
+        Ok(())
+    }
 
     // cargo test -- --show-output test_file_to_gdf_credentials
     #[test]
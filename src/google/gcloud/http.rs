@@ -0,0 +1,712 @@
+//! # Shared HTTP layer for Google APIs
+//!
+//! All Google API calls (storage, translate V2/V3) go through [`HttpClient`](struct.HttpClient.html)
+//! which adds bearer token and applies [`RetryPolicy`](struct.RetryPolicy.html):
+//!
+//! * failed attempts are retried with exponential backoff and jitter
+//! * `Retry-After` header (in seconds) returned by the server is honoured up to max backoff
+//! * HTTP 429, 403 rateLimitExceeded and 503 are always retried, server did not process the request
+//! * network errors and HTTP 500/502/504 are retried only for idempotent requests
+//! * HTTP 401 invalidates cached access token and request is retried with the new one
+//!
+//! Optional [`RateLimiter`](../rate_limit/struct.RateLimiter.html) can be attached to the client,
+//! every attempt then waits for the limiter and rate limit responses are reported to it.
+//!
+//! Every attempt is logged (set RUST_LOG=gdf_translate::google::gcloud::http=debug).
+use crate::errors::{Error, Result};
+use crate::google::gcloud::auth::TokenProvider;
+use crate::google::gcloud::rate_limit::RateLimiter;
+use crate::google::gcloud::ApiResponse;
+use crate::random_u64;
+use async_std::task;
+use log::debug;
+use std::time::Duration;
+
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_INITIAL_BACKOFF_MS: u64 = 1_000;
+pub const DEFAULT_MAX_BACKOFF_MS: u64 = 32_000;
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// total number of attempts including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        RetryPolicy {
+            max_attempts: max_attempts.max(1),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// policy with single attempt, i.e. no retries at all
+    pub fn no_retry() -> Self {
+        RetryPolicy::new(1, Duration::from_millis(0), Duration::from_millis(0))
+    }
+
+    /// exponential backoff for given attempt (starting from 1) without jitter
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff);
+        backoff.min(self.max_backoff)
+    }
+
+    /// backoff with 'equal jitter', i.e. random value between backoff/2 and backoff
+    fn backoff_with_jitter(&self, attempt: u32) -> Duration {
+        let backoff = self.backoff(attempt);
+        let half = backoff / 2;
+        let jitter_ms = if half.as_millis() > 0 {
+            random_u64() % (half.as_millis() as u64 + 1)
+        } else {
+            0
+        };
+        half + Duration::from_millis(jitter_ms)
+    }
+
+    /// delay before next attempt, delay requested by the server is honoured up to max backoff
+    fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(retry_after) => retry_after.min(self.max_backoff),
+            None => self.backoff_with_jitter(attempt),
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::new(
+            DEFAULT_MAX_ATTEMPTS,
+            Duration::from_millis(DEFAULT_INITIAL_BACKOFF_MS),
+            Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpMethod {
+    Get,
+    Post,
+    Delete,
+}
+
+impl HttpMethod {
+    fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Delete => "DELETE",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum HttpBody {
+    Empty,
+    Json(serde_json::Value),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    pub method: HttpMethod,
+    pub url: String,
+    pub body: HttpBody,
+    /// can be safely repeated even if server already processed previous attempt
+    pub idempotent: bool,
+    /// cost of the request for rate limiter (e.g. number of translated characters)
+    pub cost: u64,
+}
+
+impl HttpRequest {
+    /// GET and DELETE requests are considered idempotent, POST is not
+    pub fn new(method: HttpMethod, url: String, body: HttpBody) -> Self {
+        HttpRequest {
+            method,
+            url,
+            body,
+            idempotent: method != HttpMethod::Post,
+            cost: 0,
+        }
+    }
+
+    pub fn get(url: String) -> Self {
+        HttpRequest::new(HttpMethod::Get, url, HttpBody::Empty)
+    }
+
+    pub fn post(url: String, body: HttpBody) -> Self {
+        HttpRequest::new(HttpMethod::Post, url, body)
+    }
+
+    pub fn delete(url: String) -> Self {
+        HttpRequest::new(HttpMethod::Delete, url, HttpBody::Empty)
+    }
+
+    /// marks POST request as idempotent, e.g. object upload to fixed name
+    pub fn idempotent(mut self) -> Self {
+        self.idempotent = true;
+        self
+    }
+
+    pub fn with_cost(mut self, cost: u64) -> Self {
+        self.cost = cost;
+        self
+    }
+}
+
+/// response with raw (not necessarily UTF-8) body, e.g. downloaded ZIP file
+#[derive(Debug)]
+pub struct BinaryResponse {
+    pub status_code: String,
+    pub body: Vec<u8>,
+}
+
+/// result of single attempt
+struct HttpAttempt {
+    response: BinaryResponse,
+    retry_after: Option<Duration>,
+}
+
+/// what to do after failed attempt
+#[derive(Debug, PartialEq)]
+enum RetryDecision {
+    Retry,
+    RetryWithNewToken,
+    GiveUp,
+}
+
+/// HTTP 429 or HTTP 403 with rateLimitExceeded/userRateLimitExceeded reason
+fn is_rate_limited(status: u16, body: &str) -> bool {
+    match status {
+        429 => true,
+        403 => body.contains("rateLimitExceeded") || body.contains("RateLimitExceeded"),
+        _ => false,
+    }
+}
+
+fn retry_decision_for_status(status: u16, body: &str, idempotent: bool) -> RetryDecision {
+    if is_rate_limited(status, body) {
+        return RetryDecision::Retry;
+    }
+    match status {
+        401 => RetryDecision::RetryWithNewToken,
+        503 => RetryDecision::Retry,
+        500 | 502 | 504 if idempotent => RetryDecision::Retry,
+        _ => RetryDecision::GiveUp,
+    }
+}
+
+/// only delay in seconds is supported, HTTP date format is ignored
+fn parse_retry_after(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|val| val.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    token_provider: TokenProvider,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl HttpClient {
+    pub fn new(token_provider: TokenProvider, retry_policy: RetryPolicy) -> Self {
+        HttpClient {
+            token_provider,
+            retry_policy,
+            rate_limiter: None,
+        }
+    }
+
+    /// returns copy of the client (sharing token provider) with rate limiter attached
+    pub fn with_rate_limiter(&self, rate_limiter: RateLimiter) -> Self {
+        HttpClient {
+            token_provider: self.token_provider.clone(),
+            retry_policy: self.retry_policy.clone(),
+            rate_limiter: Some(rate_limiter),
+        }
+    }
+
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+
+    pub fn token_provider(&self) -> &TokenProvider {
+        &self.token_provider
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    /// sends the request applying retry policy. Returns last response received,
+    /// i.e. non-2xx responses are not converted into errors, callers check status code.
+    pub async fn send(&self, request: &HttpRequest) -> Result<ApiResponse> {
+        let response = self.send_binary(request).await?;
+        Ok(ApiResponse {
+            status_code: response.status_code,
+            body: String::from_utf8(response.body).map_err(|err| {
+                Error::new(format!(
+                    "response of {} is not valid UTF-8: {}",
+                    request.url, err
+                ))
+            })?,
+        })
+    }
+
+    /// same as [`send`](#method.send) but returns response body as raw bytes
+    pub async fn send_binary(&self, request: &HttpRequest) -> Result<BinaryResponse> {
+        let max_attempts = self.retry_policy.max_attempts;
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            if let Some(rate_limiter) = &self.rate_limiter {
+                rate_limiter.acquire(request.cost).await;
+            }
+            let result = self.send_once(request).await;
+
+            let (decision, retry_after) = match &result {
+                Ok(http_attempt) => {
+                    let status = http_attempt
+                        .response
+                        .status_code
+                        .parse::<u16>()
+                        .unwrap_or(0);
+                    debug!(
+                        "{} {} attempt {}/{}: HTTP {}",
+                        request.method.as_str(),
+                        request.url,
+                        attempt,
+                        max_attempts,
+                        status
+                    );
+                    if status < 400 {
+                        if let Some(rate_limiter) = &self.rate_limiter {
+                            rate_limiter.report_success().await;
+                        }
+                        return result.map(|http_attempt| http_attempt.response);
+                    }
+                    let body = String::from_utf8_lossy(&http_attempt.response.body);
+                    if is_rate_limited(status, &body) {
+                        if let Some(rate_limiter) = &self.rate_limiter {
+                            rate_limiter.report_rate_limited().await;
+                        }
+                    }
+                    (
+                        retry_decision_for_status(status, &body, request.idempotent),
+                        http_attempt.retry_after,
+                    )
+                }
+                Err(err) => {
+                    debug!(
+                        "{} {} attempt {}/{}: error {:?}",
+                        request.method.as_str(),
+                        request.url,
+                        attempt,
+                        max_attempts,
+                        err
+                    );
+                    let decision = if request.idempotent {
+                        RetryDecision::Retry
+                    } else {
+                        RetryDecision::GiveUp
+                    };
+                    (decision, None)
+                }
+            };
+
+            if decision == RetryDecision::GiveUp || attempt >= max_attempts {
+                return result.map(|http_attempt| http_attempt.response);
+            }
+
+            if decision == RetryDecision::RetryWithNewToken {
+                self.token_provider.invalidate().await;
+            }
+
+            let delay = self.retry_policy.delay(attempt, retry_after);
+            debug!(
+                "{} {} retrying in {:?}",
+                request.method.as_str(),
+                request.url,
+                delay
+            );
+            task::sleep(delay).await;
+        }
+    }
+
+    async fn send_once(&self, request: &HttpRequest) -> Result<HttpAttempt> {
+        let token = self.token_provider.get_token().await?;
+        let url = request.url.as_str();
+
+        let mut surf_request = match request.method {
+            HttpMethod::Get => surf::get(url),
+            HttpMethod::Post => surf::post(url),
+            HttpMethod::Delete => surf::delete(url),
+        }
+        .set_header("Authorization", token);
+
+        surf_request = match &request.body {
+            HttpBody::Empty => surf_request,
+            HttpBody::Json(json) => surf_request.body_json(json)?,
+            HttpBody::Text(text) => surf_request.body_string(text.to_owned()),
+        };
+
+        let mut resp = surf_request.await?;
+        let retry_after = parse_retry_after(resp.header("Retry-After"));
+
+        Ok(HttpAttempt {
+            response: BinaryResponse {
+                status_code: resp.status().as_str().to_string(),
+                body: resp.body_bytes().await?,
+            },
+            retry_after,
+        })
+    }
+}
+
+/// converts response with unexpected status code into error
+pub fn expect_success(operation: &str, response: &ApiResponse) -> Result<()> {
+    if response.status_code.starts_with('2') {
+        Ok(())
+    } else {
+        Err(Error::new(format!(
+            "{} failed with HTTP {}: {}",
+            operation, response.status_code, response.body
+        )))
+    }
+}
+
+#[cfg(test)]
+pub mod test_server {
+    //! minimalistic scripted HTTP server used by unit tests
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    pub struct ScriptedResponse {
+        pub status: u16,
+        pub headers: Vec<(&'static str, String)>,
+        pub body: String,
+    }
+
+    impl ScriptedResponse {
+        pub fn new(status: u16, body: &str) -> Self {
+            ScriptedResponse {
+                status,
+                headers: vec![],
+                body: body.to_owned(),
+            }
+        }
+
+        pub fn with_header(mut self, name: &'static str, value: &str) -> Self {
+            self.headers.push((name, value.to_owned()));
+            self
+        }
+    }
+
+    /// reads single HTTP request and returns (request line + headers, body).
+    /// Handles 'Expect: 100-continue' and chunked transfer encoding used by surf/isahc.
+    pub fn read_request(stream: &mut TcpStream) -> (String, String) {
+        let mut buf = vec![0u8; 8192];
+        let mut request: Vec<u8> = vec![];
+        let header_end = loop {
+            let n = stream.read(&mut buf).unwrap();
+            request.extend_from_slice(&buf[..n]);
+            if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+        };
+        let headers = String::from_utf8_lossy(&request[..header_end]).to_string();
+        let headers_lc = headers.to_lowercase();
+        if headers_lc.contains("expect: 100-continue") {
+            stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").unwrap();
+        }
+        let chunked = headers_lc.contains("transfer-encoding: chunked");
+        let content_length = headers_lc
+            .lines()
+            .find_map(|l| l.strip_prefix("content-length:"))
+            .map(|v| v.trim().parse::<usize>().unwrap())
+            .unwrap_or(0);
+        let mut body = request[header_end..].to_vec();
+        let is_complete = |body: &[u8]| {
+            if chunked {
+                body.ends_with(b"0\r\n\r\n")
+            } else {
+                body.len() >= content_length
+            }
+        };
+        while !is_complete(&body) {
+            let n = stream.read(&mut buf).unwrap();
+            body.extend_from_slice(&buf[..n]);
+        }
+        let body = String::from_utf8_lossy(&body).to_string();
+        let body = if chunked {
+            // good enough for tests: keep every second line, i.e. chunk data
+            body.split("\r\n")
+                .skip(1)
+                .step_by(2)
+                .collect::<Vec<&str>>()
+                .join("")
+        } else {
+            body
+        };
+        (headers, body)
+    }
+
+    /// serves scripted responses one by one (one connection per response),
+    /// returns server URL and handle returning all received requests (headers, body)
+    pub fn scripted_server(
+        responses: Vec<ScriptedResponse>,
+    ) -> (String, thread::JoinHandle<Vec<(String, String)>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = vec![];
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                requests.push(read_request(&mut stream));
+                let mut raw = format!("HTTP/1.1 {} Scripted\r\n", response.status);
+                for (name, value) in response.headers.iter() {
+                    raw.push_str(&format!("{}: {}\r\n", name, value));
+                }
+                raw.push_str(&format!(
+                    "Content-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    response.body.len(),
+                    response.body
+                ));
+                stream.write_all(raw.as_bytes()).unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_server::*;
+    use super::*;
+    use crate::google::gcloud::auth::CredentialSource;
+    use crate::google::gcloud::rate_limit::RateLimits;
+
+    const AUTHORIZED_USER_JSON: &str = r#"{
+        "client_id": "dummy-client-id.apps.googleusercontent.com",
+        "client_secret": "dummy-client-secret",
+        "refresh_token": "dummy-refresh-token",
+        "type": "authorized_user"
+    }"#;
+
+    const RATE_LIMIT_EXCEEDED: &str = r#"{"error":{"code":403,"message":"User Rate Limit Exceeded","errors":[{"reason":"userRateLimitExceeded"}]}}"#;
+
+    fn token_response(token: &str) -> ScriptedResponse {
+        ScriptedResponse::new(
+            200,
+            &format!(
+                r#"{{"access_token":"{}","token_type":"Bearer","expires_in":3600}}"#,
+                token
+            ),
+        )
+    }
+
+    fn fast_retry_policy() -> RetryPolicy {
+        RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(4))
+    }
+
+    fn http_client(token_url: &str) -> HttpClient {
+        let token_provider = TokenProvider::new(
+            CredentialSource::from_json_str(AUTHORIZED_USER_JSON).unwrap(),
+            Some(token_url),
+        );
+        HttpClient::new(token_provider, fast_retry_policy())
+    }
+
+    // cargo test -- --show-output test_retry_policy_backoff
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(4), Duration::from_millis(800));
+        assert_eq!(policy.backoff(5), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(40), Duration::from_millis(1000));
+
+        for _ in 0..20 {
+            let delay = policy.backoff_with_jitter(3);
+            assert!(delay >= Duration::from_millis(200) && delay <= Duration::from_millis(400));
+        }
+
+        // server can not make the client wait longer than max backoff
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(86400))),
+            Duration::from_millis(1000)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(300))),
+            Duration::from_millis(300)
+        );
+        assert!(policy.delay(1, None) <= Duration::from_millis(100));
+    }
+
+    // cargo test -- --show-output test_retry_decision
+    #[test]
+    fn test_retry_decision() {
+        assert_eq!(
+            retry_decision_for_status(429, "", false),
+            RetryDecision::Retry
+        );
+        assert_eq!(
+            retry_decision_for_status(503, "", false),
+            RetryDecision::Retry
+        );
+        assert_eq!(
+            retry_decision_for_status(500, "", true),
+            RetryDecision::Retry
+        );
+        assert_eq!(
+            retry_decision_for_status(500, "", false),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            retry_decision_for_status(404, "", true),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            retry_decision_for_status(403, "", true),
+            RetryDecision::GiveUp
+        );
+        assert_eq!(
+            retry_decision_for_status(403, RATE_LIMIT_EXCEEDED, false),
+            RetryDecision::Retry
+        );
+        assert_eq!(
+            retry_decision_for_status(401, "", false),
+            RetryDecision::RetryWithNewToken
+        );
+        assert_eq!(parse_retry_after(Some(" 2 ")), Some(Duration::from_secs(2)));
+        assert_eq!(
+            parse_retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT")),
+            None
+        );
+        assert_eq!(parse_retry_after(None), None);
+    }
+
+    // cargo test -- --show-output test_send_retries_503
+    #[test]
+    fn test_send_retries_503() {
+        let (token_url, token_server) = scripted_server(vec![token_response("t1")]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(503, "{}").with_header("Retry-After", "0"),
+            ScriptedResponse::new(429, "{}"),
+            ScriptedResponse::new(200, r#"{"ok":true}"#),
+        ]);
+        let client = http_client(&token_url);
+        let request = HttpRequest::post(
+            format!("{}/v3/something", url),
+            HttpBody::Json(serde_json::json!({"a": 1})),
+        );
+        let response = task::block_on(client.send(&request)).unwrap();
+        assert_eq!(response.status_code, "200");
+        assert_eq!(response.body, r#"{"ok":true}"#);
+
+        let requests = server.join().unwrap();
+        assert_eq!(requests.len(), 3);
+        for (headers, body) in requests.iter() {
+            assert!(headers.contains("Bearer t1"));
+            assert_eq!(body, r#"{"a":1}"#);
+        }
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_send_does_not_retry_non_idempotent
+    #[test]
+    fn test_send_does_not_retry_non_idempotent() {
+        let (token_url, token_server) = scripted_server(vec![token_response("t1")]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(500, r#"{"error":"boom"}"#),
+            ScriptedResponse::new(500, r#"{"error":"boom"}"#),
+            ScriptedResponse::new(200, "{}"),
+        ]);
+        let client = http_client(&token_url);
+
+        // POST is not retried on HTTP 500
+        let request = HttpRequest::post(format!("{}/x", url), HttpBody::Empty);
+        let response = task::block_on(client.send(&request)).unwrap();
+        assert_eq!(response.status_code, "500");
+        assert!(expect_success("create x", &response).is_err());
+
+        // idempotent request is retried
+        let request = HttpRequest::get(format!("{}/x", url));
+        let response = task::block_on(client.send(&request)).unwrap();
+        assert_eq!(response.status_code, "200");
+        assert!(expect_success("get x", &response).is_ok());
+
+        assert_eq!(server.join().unwrap().len(), 3);
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_send_gives_up_after_max_attempts
+    #[test]
+    fn test_send_gives_up_after_max_attempts() {
+        let (token_url, token_server) = scripted_server(vec![token_response("t1")]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(503, "{}"),
+            ScriptedResponse::new(503, "{}"),
+            ScriptedResponse::new(503, "{}"),
+        ]);
+        let client = http_client(&token_url);
+        let response = task::block_on(client.send(&HttpRequest::delete(url))).unwrap();
+        assert_eq!(response.status_code, "503");
+        assert_eq!(server.join().unwrap().len(), 3);
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_send_refreshes_token_on_401
+    #[test]
+    fn test_send_refreshes_token_on_401() {
+        let (token_url, token_server) =
+            scripted_server(vec![token_response("t1"), token_response("t2")]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(401, "{}"),
+            ScriptedResponse::new(200, "{}"),
+        ]);
+        let client = http_client(&token_url);
+        let response = task::block_on(client.send(&HttpRequest::get(url))).unwrap();
+        assert_eq!(response.status_code, "200");
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].0.contains("Bearer t1"));
+        assert!(requests[1].0.contains("Bearer t2"));
+        token_server.join().unwrap();
+    }
+
+    // cargo test -- --show-output test_send_reports_rate_limit
+    #[test]
+    fn test_send_reports_rate_limit() {
+        let (token_url, token_server) = scripted_server(vec![token_response("t1")]);
+        let (url, server) = scripted_server(vec![
+            ScriptedResponse::new(403, RATE_LIMIT_EXCEEDED),
+            ScriptedResponse::new(429, "{}").with_header("Retry-After", "0"),
+            ScriptedResponse::new(200, "{}"),
+        ]);
+        let mut limits = RateLimits::new(Some(600_000), Some(6_000));
+        limits.initial_pause = Duration::from_millis(1);
+        let rate_limiter = RateLimiter::new(&limits);
+        let client = http_client(&token_url).with_rate_limiter(rate_limiter.clone());
+
+        // POST is retried because server rejected the request without processing it
+        let request = HttpRequest::post(format!("{}/x", url), HttpBody::Empty).with_cost(100);
+        let response = task::block_on(client.send(&request)).unwrap();
+        assert_eq!(response.status_code, "200");
+        assert_eq!(task::block_on(rate_limiter.rate_limited_count()), 2);
+
+        assert_eq!(server.join().unwrap().len(), 3);
+        token_server.join().unwrap();
+    }
+}
@@ -0,0 +1,301 @@
+//! # Quota-aware rate limiting
+//!
+//! Token bucket based limiter shared by all tasks calling the same API
+//! (e.g. parallel V2 translation tasks). Two independent limits are supported:
+//!
+//! * characters per minute (cost of request is number of translated characters)
+//! * requests per minute
+//!
+//! Limiter is attached to [`HttpClient`](../http/struct.HttpClient.html) via `with_rate_limiter`,
+//! so it can be used by any HTTP based provider. When API responds with HTTP 429 or
+//! 403 rateLimitExceeded all tasks are paused (pause is doubled with every consecutive
+//! rate limit response) and configured rates are halved. Rates recover gradually
+//! with every successful call.
+use async_std::sync::Mutex;
+use async_std::task;
+use log::debug;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// bucket can hold this many seconds worth of tokens, i.e. maximal burst
+const BUCKET_CAPACITY_SECS: f64 = 10.0;
+
+/// rates are never throttled below this fraction of configured limit
+const MIN_RATE_FACTOR: f64 = 0.1;
+
+/// rate factor increase for every successful call after rate limiting
+const RATE_FACTOR_RECOVERY_STEP: f64 = 0.05;
+
+const MAX_PAUSE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone)]
+pub struct RateLimits {
+    pub chars_per_minute: Option<u64>,
+    pub requests_per_minute: Option<u64>,
+    /// pause applied to all tasks after first rate limit response
+    pub initial_pause: Duration,
+}
+
+impl RateLimits {
+    pub fn new(chars_per_minute: Option<u64>, requests_per_minute: Option<u64>) -> Self {
+        RateLimits {
+            chars_per_minute,
+            requests_per_minute,
+            initial_pause: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(per_minute: u64, now: Instant) -> Self {
+        let refill_per_sec = per_minute as f64 / 60.0;
+        let capacity = (refill_per_sec * BUCKET_CAPACITY_SECS).max(1.0);
+        TokenBucket {
+            capacity,
+            tokens: capacity,
+            refill_per_sec,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant, rate_factor: f64) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed * self.refill_per_sec * rate_factor).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// how long to wait until request with given cost can be taken.
+    /// Requests bigger than capacity are allowed once bucket is full (bucket goes negative).
+    fn wait_time(&self, cost: f64, rate_factor: f64) -> Duration {
+        let needed = cost.min(self.capacity) - self.tokens;
+        if needed <= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(needed / (self.refill_per_sec * rate_factor))
+        }
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.tokens -= cost;
+    }
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+    chars: Option<TokenBucket>,
+    requests: Option<TokenBucket>,
+    rate_factor: f64,
+    initial_pause: Duration,
+    next_pause: Duration,
+    paused_until: Option<Instant>,
+    rate_limited_count: u64,
+}
+
+impl RateLimiterState {
+    fn new(limits: &RateLimits, now: Instant) -> Self {
+        RateLimiterState {
+            chars: limits
+                .chars_per_minute
+                .map(|per_minute| TokenBucket::new(per_minute, now)),
+            requests: limits
+                .requests_per_minute
+                .map(|per_minute| TokenBucket::new(per_minute, now)),
+            rate_factor: 1.0,
+            initial_pause: limits.initial_pause,
+            next_pause: limits.initial_pause,
+            paused_until: None,
+            rate_limited_count: 0,
+        }
+    }
+
+    /// either takes tokens and returns None or returns time to wait before next try
+    fn try_acquire(&mut self, cost: u64, now: Instant) -> Option<Duration> {
+        if let Some(paused_until) = self.paused_until {
+            if paused_until > now {
+                return Some(paused_until - now);
+            }
+            self.paused_until = None;
+        }
+
+        let rate_factor = self.rate_factor;
+        let mut wait = Duration::from_secs(0);
+        if let Some(bucket) = self.chars.as_mut() {
+            bucket.refill(now, rate_factor);
+            wait = wait.max(bucket.wait_time(cost as f64, rate_factor));
+        }
+        if let Some(bucket) = self.requests.as_mut() {
+            bucket.refill(now, rate_factor);
+            wait = wait.max(bucket.wait_time(1.0, rate_factor));
+        }
+        if wait > Duration::from_secs(0) {
+            return Some(wait);
+        }
+
+        if let Some(bucket) = self.chars.as_mut() {
+            bucket.take(cost as f64);
+        }
+        if let Some(bucket) = self.requests.as_mut() {
+            bucket.take(1.0);
+        }
+        None
+    }
+
+    fn rate_limited(&mut self, now: Instant) {
+        self.rate_limited_count += 1;
+        self.rate_factor = (self.rate_factor / 2.0).max(MIN_RATE_FACTOR);
+        let pause = self.next_pause;
+        self.paused_until = Some(now + pause);
+        self.next_pause = (pause * 2).min(MAX_PAUSE);
+        debug!(
+            "rate limit hit, pausing all tasks for {:?}, rate factor {}",
+            pause, self.rate_factor
+        );
+    }
+
+    fn succeeded(&mut self) {
+        self.rate_factor = (self.rate_factor + RATE_FACTOR_RECOVERY_STEP).min(1.0);
+        self.next_pause = self.initial_pause;
+    }
+}
+
+/// Token bucket rate limiter. Cloning is cheap, all clones share the same buckets.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    state: Arc<Mutex<RateLimiterState>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: &RateLimits) -> Self {
+        RateLimiter {
+            state: Arc::new(Mutex::new(RateLimiterState::new(limits, Instant::now()))),
+        }
+    }
+
+    /// waits until request with given cost (e.g. number of characters) can be sent
+    pub async fn acquire(&self, cost: u64) {
+        loop {
+            let wait = self.state.lock().await.try_acquire(cost, Instant::now());
+            match wait {
+                None => return,
+                Some(wait) => {
+                    debug!("rate limiter: waiting {:?}", wait);
+                    task::sleep(wait).await;
+                }
+            }
+        }
+    }
+
+    /// to be called when API responded with 429 / 403 rateLimitExceeded
+    pub async fn report_rate_limited(&self) {
+        self.state.lock().await.rate_limited(Instant::now());
+    }
+
+    pub async fn report_success(&self) {
+        self.state.lock().await.succeeded();
+    }
+
+    /// number of rate limit responses reported so far
+    pub async fn rate_limited_count(&self) -> u64 {
+        self.state.lock().await.rate_limited_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cargo test -- --show-output test_token_bucket
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        // 60 per minute => 1 per second, capacity 10
+        let mut bucket = TokenBucket::new(60, now);
+        assert_eq!(bucket.wait_time(10.0, 1.0), Duration::from_secs(0));
+        bucket.take(10.0);
+        assert_eq!(bucket.wait_time(1.0, 1.0), Duration::from_secs(1));
+        assert_eq!(bucket.wait_time(1.0, 0.5), Duration::from_secs(2));
+
+        bucket.refill(now + Duration::from_secs(4), 1.0);
+        assert_eq!(bucket.wait_time(4.0, 1.0), Duration::from_secs(0));
+
+        // refill never exceeds capacity
+        bucket.refill(now + Duration::from_secs(100), 1.0);
+        assert!((bucket.tokens - 10.0).abs() < f64::EPSILON);
+
+        // requests bigger than capacity are allowed when bucket is full
+        assert_eq!(bucket.wait_time(25.0, 1.0), Duration::from_secs(0));
+        bucket.take(25.0);
+        assert_eq!(bucket.wait_time(1.0, 1.0), Duration::from_secs(16));
+    }
+
+    // cargo test -- --show-output test_rate_limiter_state_limits
+    #[test]
+    fn test_rate_limiter_state_limits() {
+        let now = Instant::now();
+        // 600 chars/min (capacity 100), 60 requests/min (capacity 10)
+        let mut state = RateLimiterState::new(&RateLimits::new(Some(600), Some(60)), now);
+
+        assert_eq!(state.try_acquire(60, now), None);
+        // chars limit: 40 chars left, 20 more needed at 10 chars/s
+        assert_eq!(state.try_acquire(60, now), Some(Duration::from_secs(2)));
+        assert_eq!(state.try_acquire(60, now + Duration::from_secs(2)), None);
+
+        // requests limit
+        let mut state = RateLimiterState::new(&RateLimits::new(None, Some(60)), now);
+        for _ in 0..10 {
+            assert_eq!(state.try_acquire(1_000_000, now), None);
+        }
+        assert_eq!(state.try_acquire(1, now), Some(Duration::from_secs(1)));
+    }
+
+    // cargo test -- --show-output test_rate_limiter_state_backoff
+    #[test]
+    fn test_rate_limiter_state_backoff() {
+        let now = Instant::now();
+        let mut state = RateLimiterState::new(&RateLimits::new(None, Some(60)), now);
+
+        state.rate_limited(now);
+        assert_eq!(state.try_acquire(1, now), Some(Duration::from_secs(1)));
+        assert!((state.rate_factor - 0.5).abs() < f64::EPSILON);
+
+        // consecutive rate limit doubles the pause
+        state.rate_limited(now);
+        assert_eq!(state.try_acquire(1, now), Some(Duration::from_secs(2)));
+        assert!((state.rate_factor - 0.25).abs() < f64::EPSILON);
+        assert_eq!(state.rate_limited_count, 2);
+
+        // after the pause tokens refill at reduced rate
+        assert_eq!(state.try_acquire(1, now + Duration::from_secs(2)), None);
+
+        for _ in 0..100 {
+            state.succeeded();
+        }
+        assert!((state.rate_factor - 1.0).abs() < f64::EPSILON);
+        assert_eq!(state.next_pause, Duration::from_secs(1));
+    }
+
+    // cargo test -- --show-output test_rate_limiter_unlimited
+    #[test]
+    fn test_rate_limiter_unlimited() {
+        let limiter = RateLimiter::new(&RateLimits::new(None, None));
+        let shared = limiter.clone();
+        task::block_on(async {
+            for _ in 0..1000 {
+                shared.acquire(1_000_000).await;
+            }
+            limiter.report_rate_limited().await;
+            assert_eq!(shared.rate_limited_count().await, 1);
+        });
+    }
+}
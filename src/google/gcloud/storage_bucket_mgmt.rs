@@ -8,12 +8,14 @@
 // 5. https://cloud.google.com/storage/docs/deleting-objects
 
 use crate::errors::Result;
+use crate::google::gcloud::http::{BinaryResponse, HttpBody, HttpClient, HttpRequest};
 use crate::google::gcloud::ApiResponse;
 use log::debug;
 use serde_json::json;
 
 pub async fn create_bucket(
-    token: &str,
+    http_client: &HttpClient,
+    storage_url: &str,
     project_id: &str,
     bucket_name: &str,
     location: &str,
@@ -25,108 +27,104 @@ pub async fn create_bucket(
         "storageClass": storage_class
     });
 
-    let url = format!(
-        "https://storage.googleapis.com/storage/v1/b?project={}",
-        project_id
-    );
+    let url = format!("{}/storage/v1/b?project={}", storage_url, project_id);
 
     debug!("body: {}", body);
     debug!("url: {}", url);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_json(&body)?
-        .await?;
-
-    Ok(ApiResponse {
-        status_code: resp.status().as_str().to_string(),
-        body: resp.body_string().await?,
-    })
+    // not idempotent, repeated attempt might end up with 409 (bucket already exists)
+    http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)))
+        .await
 }
 
 pub async fn upload_object(
-    token: &str,
+    http_client: &HttpClient,
+    storage_url: &str,
     bucket_name: &str,
     object_name: &str,
     object_content: &str,
 ) -> Result<ApiResponse> {
     let url = format!(
-        "https://storage.googleapis.com/upload/storage/v1/b/{}/o?uploadType=media&name={}",
-        bucket_name, object_name
+        "{}/upload/storage/v1/b/{}/o?uploadType=media&name={}",
+        storage_url, bucket_name, object_name
     );
 
     debug!("url: {}", url);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_string(object_content.to_owned())
-        .await?;
-
-    Ok(ApiResponse {
-        status_code: resp.status().as_str().to_string(),
-        body: resp.body_string().await?,
-    })
+    // uploading same content under the same name can be safely repeated
+    http_client
+        .send(&HttpRequest::post(url, HttpBody::Text(object_content.to_owned())).idempotent())
+        .await
 }
 
 pub async fn download_object(
-    token: &str,
+    http_client: &HttpClient,
+    storage_url: &str,
     bucket_name: &str,
     object_name: &str,
 ) -> Result<ApiResponse> {
     let url = format!(
-        "https://storage.googleapis.com/storage/v1/b/{}/o/{}?alt=media",
-        bucket_name, object_name
+        "{}/storage/v1/b/{}/o/{}?alt=media",
+        storage_url, bucket_name, object_name
     );
 
     debug!("url: {}", url);
 
-    let mut resp = surf::get(url).set_header("Authorization", token).await?;
+    http_client.send(&HttpRequest::get(url)).await
+}
+
+/// downloads object as raw bytes, e.g. ZIP file
+pub async fn download_binary_object(
+    http_client: &HttpClient,
+    storage_url: &str,
+    bucket_name: &str,
+    object_name: &str,
+) -> Result<BinaryResponse> {
+    let url = format!(
+        "{}/storage/v1/b/{}/o/{}?alt=media",
+        storage_url, bucket_name, object_name
+    );
+
+    debug!("url: {}", url);
 
-    Ok(ApiResponse {
-        status_code: resp.status().as_str().to_string(),
-        body: resp.body_string().await?,
-    })
+    http_client.send_binary(&HttpRequest::get(url)).await
 }
 
 pub async fn delete_object(
-    token: &str,
+    http_client: &HttpClient,
+    storage_url: &str,
     bucket_name: &str,
     object_name: &str,
 ) -> Result<ApiResponse> {
     let url = format!(
-        "https://storage.googleapis.com/storage/v1/b/{}/o/{}",
-        bucket_name, object_name
+        "{}/storage/v1/b/{}/o/{}",
+        storage_url, bucket_name, object_name
     );
 
     debug!("url: {}", url);
 
-    let mut resp = surf::delete(url).set_header("Authorization", token).await?;
-
-    Ok(ApiResponse {
-        status_code: resp.status().as_str().to_string(),
-        body: resp.body_string().await?,
-    })
+    http_client.send(&HttpRequest::delete(url)).await
 }
 
-pub async fn delete_bucket(token: &str, bucket_name: &str) -> Result<ApiResponse> {
-    let url = format!(
-        "https://storage.googleapis.com/storage/v1/b/{}",
-        bucket_name
-    );
+pub async fn delete_bucket(
+    http_client: &HttpClient,
+    storage_url: &str,
+    bucket_name: &str,
+) -> Result<ApiResponse> {
+    let url = format!("{}/storage/v1/b/{}", storage_url, bucket_name);
     debug!("url: {}", url);
-    let mut resp = surf::delete(url).set_header("Authorization", token).await?;
 
-    Ok(ApiResponse {
-        status_code: resp.status().as_str().to_string(),
-        body: resp.body_string().await?,
-    })
+    http_client.send(&HttpRequest::delete(url)).await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::google::gcloud::auth::*;
+    use crate::google::gcloud::http::{HttpClient, RetryPolicy};
     use crate::google::gcloud::translate::v3::{map_to_string, string_to_map};
+    use crate::google::gcloud::DEFAULT_STORAGE_URL;
     use crate::init_logging; // set RUST_LOG=gdf_translate::google::gcloud::storage_bucket_mgmt=debug
     use async_std::task;
     use std::collections;
@@ -136,13 +134,16 @@ mod tests {
     #[ignore]
     fn test_create_bucket() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<ApiResponse> = task::block_on(create_bucket(
-            &token,
+            &http_client,
+            DEFAULT_STORAGE_URL,
             "express-tracking",
             "translate_v3_test",
             "EUROPE-WEST3",
@@ -157,13 +158,18 @@ mod tests {
     #[ignore]
     fn test_delete_bucket() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
-        let api_response: Result<ApiResponse> =
-            task::block_on(delete_bucket(&token, "translate_v3_test"));
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
+        let api_response: Result<ApiResponse> = task::block_on(delete_bucket(
+            &http_client,
+            DEFAULT_STORAGE_URL,
+            "translate_v3_test",
+        ));
         println!("api_response {:#?}", api_response?);
         Ok(())
     }
@@ -173,18 +179,21 @@ mod tests {
     #[ignore]
     fn test_upload_object() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
 
         let mut translation_map: collections::HashMap<String, String> = collections::HashMap::new();
         translation_map.insert("1".to_owned(), "translate me".to_owned());
         translation_map.insert("2".to_owned(), "translate me as well".to_owned());
         translation_map.insert("3".to_owned(), "just forget it".to_owned());
-
-        println!("access_token {:#?}", token);
         let api_response: Result<ApiResponse> = task::block_on(upload_object(
-            &token,
+            &http_client,
+            DEFAULT_STORAGE_URL,
             "translate_v3_test",
             "translation_map.tsv",
             &map_to_string(&translation_map),
@@ -198,13 +207,16 @@ mod tests {
     #[ignore]
     fn test_download_object() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<ApiResponse> = task::block_on(download_object(
-            &token,
+            &http_client,
+            DEFAULT_STORAGE_URL,
             "translate_v3_test",
             "translation_map",
         ));
@@ -219,13 +231,16 @@ mod tests {
     #[ignore]
     fn test_delete_object() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<ApiResponse> = task::block_on(delete_object(
-            &token,
+            &http_client,
+            DEFAULT_STORAGE_URL,
             "translate_v3_test",
             "translation_map",
         ));
@@ -1,13 +1,19 @@
 use crate::errors::{Error, Result};
-use crate::google::dialogflow::agent::parse_gdf_agent_zip;
+use crate::google::dialogflow::options::AgentOptions;
+use crate::google::dialogflow::parse_dialogflow_agent_zip;
+use crate::google::gcloud::http::HttpClient;
+use crate::google::gcloud::rate_limit::{RateLimiter, RateLimits};
 use crate::google::gcloud::storage_bucket_mgmt;
+use crate::google::gcloud::translate::v3::TranslationModels;
+use crate::summary::{ReportItem, RunSummary};
 use crate::ui::ProgressMessageType;
 use async_std::task;
 use std::fs;
 // while StreamExt is not used directly without it this line will not compile:
 // while let Some(future_value) = futures.next().await
-use crate::google::gcloud::ApiResponse;
+use crate::google::gcloud::{ApiResponse, GoogleApiConfig};
 use crate::html;
+use crate::placeholders;
 use futures::stream::{FuturesUnordered, StreamExt};
 use lazy_static::lazy_static;
 use log::debug;
@@ -16,15 +22,16 @@ use std::collections;
 use std::fs::File;
 use std::io::prelude::*;
 use std::sync::mpsc::Sender;
-use std::time::Duration;
-use std::time::SystemTime;
-use std::{thread, time};
+use std::thread;
+use std::time::{Instant, SystemTime};
 
 pub mod v2;
 pub mod v3;
 
 // see https://rustexp.lpil.uk/
 lazy_static! {
+    /// parameter of response, e.g. $edd or $edd.original. Superseded by
+    /// [RE_REFERENCE](../../../placeholders/struct.RE_REFERENCE.html) covering contexts and expressions as well
     pub static ref RE_RESPONSE_PARAMETER: Regex = Regex::new(r"(\$\w*\.original|\$\w*)").unwrap();
 }
 
@@ -38,24 +45,41 @@ pub trait Translate {
     fn from_translation(&mut self, translations_map: &collections::HashMap<String, String>);
 }
 
-/// checks the translation map and retrieves all parameters, e.g. $edd, $edd.original
-/// these parameters will be then added to translation glossary since we cannot translate them!
+/// checks the translation map and retrieves all parameters, e.g. $edd, $edd.original.
+/// Parameters are the same references [protect_references](../../../placeholders/fn.protect_references.html)
+/// replaces with placeholders (contexts, system entities and expressions excluded)
 pub fn get_all_parameters(
     translation_map: &collections::HashMap<String, String>,
 ) -> collections::HashSet<&str> {
-    let mut parameters: collections::HashSet<&str> = collections::HashSet::new();
+    translation_map
+        .values()
+        .flat_map(|val| placeholders::RE_REFERENCE.find_iter(val))
+        .map(|mat| mat.as_str())
+        .filter(|reference| reference.starts_with('$') && !reference.starts_with("${"))
+        .collect()
+}
 
-    for (_, val) in translation_map.iter() {
-        let params: collections::HashSet<&str> = RE_RESPONSE_PARAMETER
-            .find_iter(val)
-            .map(|mat| mat.as_str())
-            .collect();
-        if params.len() > 0 {
-            parameters.extend(params);
-        }
+/// applies translation of references protected by [protect_references](../../../placeholders/fn.protect_references.html),
+/// texts with lost, duplicated or merged references are left untranslated. Issues are added to the report of the run.
+fn restore_references(
+    translation_map: &mut collections::HashMap<String, String>,
+    references: &collections::HashMap<String, placeholders::References>,
+    run_summary: &mut RunSummary,
+) {
+    for (text_references, issue) in placeholders::restore_references(translation_map, references) {
+        let message = match issue {
+            placeholders::PlaceholderIssue::Reordered(_) => {
+                format!("{} in \"{}\"", issue, text_references.source)
+            }
+            _ => format!(
+                "{} in \"{}\", translation not applied",
+                issue, text_references.source
+            ),
+        };
+        run_summary
+            .report
+            .push(ReportItem::new("reference", "", message));
     }
-
-    parameters
 }
 
 /// dummy translation method which just adds _translated postfix to every text that should be translated
@@ -103,34 +127,84 @@ impl TranslationGlossary {
     }
 }
 
+/// agent to be translated and where its translation is stored
+pub struct TranslationJob<'a> {
+    pub gdf_agent_path: &'a str,
+    pub translated_gdf_agent_folder: &'a str,
+    pub source_lang: &'a str,
+    pub target_lang: &'a str,
+    pub skip_entities_translation: bool,
+    pub skip_utterances_translation: bool,
+    pub skip_responses_translation: bool,
+    pub agent_options: &'a AgentOptions,
+}
+
+/// options specific to Google Translate V2 API
+pub struct V2Options<'a> {
+    /// number of concurrent translation tasks
+    pub task_count: usize,
+    pub rate_limits: &'a RateLimits,
+}
+
+/// options specific to Google Translate V3 API
+pub struct V3Options<'a> {
+    pub project_id: &'a str,
+    pub create_output_tsv: bool,
+    pub glossary_path: Option<&'a str>,
+    pub models: &'a TranslationModels,
+}
+
+/// part of translation map translated by single V3 batch translation
+struct V3Batch<'a> {
+    iter_idx: usize,
+    translation_map: collections::HashMap<String, String>,
+    storage_bucket_name_in: String,
+    storage_bucket_name_out: String,
+    glossary: Option<&'a str>,
+    model: Option<&'a str>,
+}
+
 pub struct GoogleTranslateV2;
 pub struct GoogleTranslateV3;
 pub struct DummyTranslate;
 
 impl GoogleTranslateV2 {
     pub fn execute_translation(
-        gdf_agent_path: &str,
-        translated_gdf_agent_folder: &str,
-        token: &str,
-        source_lang: &str,
-        target_lang: &str,
+        job: &TranslationJob,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
+        options: &V2Options,
         mpsc_sender: Sender<ProgressMessageType>,
-        task_count: usize,
-        skip_entities_translation: bool,
-        skip_utterances_translation: bool,
-        skip_responses_translation: bool,
-    ) -> Result<()> {
+    ) -> Result<RunSummary> {
+        let TranslationJob {
+            gdf_agent_path,
+            translated_gdf_agent_folder,
+            source_lang,
+            target_lang,
+            skip_entities_translation,
+            skip_utterances_translation,
+            skip_responses_translation,
+            agent_options,
+        } = *job;
+        let task_count = options.task_count;
         debug!("processing agent {}", gdf_agent_path);
-        let mut agent = parse_gdf_agent_zip(gdf_agent_path)?;
+        let mut run_summary = RunSummary::new("v2", source_lang, target_lang);
+        // single limiter shared by all translation tasks
+        let rate_limiter = RateLimiter::new(options.rate_limits);
+        let http_client = &http_client.with_rate_limiter(rate_limiter.clone());
+        let mut agent = parse_dialogflow_agent_zip(gdf_agent_path, agent_options.strict)?;
         let mut translation_map = agent.to_translation(
             source_lang,
             target_lang,
             skip_entities_translation,
             skip_utterances_translation,
             skip_responses_translation,
+            agent_options,
         );
+        let references = placeholders::protect_references(&mut translation_map);
 
         let translation_count = translation_map.len();
+        run_summary.translated_items = translation_count;
         send_progress(
             ProgressMessageType::CountSpecified(translation_count as u64),
             &mpsc_sender,
@@ -162,7 +236,8 @@ impl GoogleTranslateV2 {
         while let Some(map) = translation_maps.pop() {
             iter_idx = iter_idx + 1;
             let future = GoogleTranslateV2::execute_translation_impl(
-                token,
+                http_client,
+                api_config,
                 source_lang,
                 target_lang,
                 map,
@@ -187,8 +262,21 @@ impl GoogleTranslateV2 {
             }
         });
 
+        let rate_limited_count = task::block_on(rate_limiter.rate_limited_count());
+        run_summary.rate_limited_responses = Some(rate_limited_count);
+        if rate_limited_count > 0 {
+            send_progress(
+                ProgressMessageType::TextMessage(format!(
+                    "translation API rate limit hit {} times, consider lowering task count or rate limits",
+                    rate_limited_count
+                )),
+                &mpsc_sender,
+            );
+        }
+
         debug!("translation finished. updated translation map");
         debug!("{:#?}", translation_map);
+        restore_references(&mut translation_map, &references, &mut run_summary);
 
         debug!("applying translated map to agent");
         agent.from_translation(
@@ -197,19 +285,23 @@ impl GoogleTranslateV2 {
             skip_entities_translation,
             skip_utterances_translation,
             skip_responses_translation,
+            agent_options,
         );
-        agent.add_supported_language(target_lang);
+        agent.add_supported_language(target_lang, agent_options);
+        run_summary.report.extend(agent.take_report());
         debug!("serializing agent");
         agent.serialize(translated_gdf_agent_folder)?;
         debug!("agent serialized!");
+        run_summary.write(translated_gdf_agent_folder)?;
 
         send_progress(ProgressMessageType::Exit, &mpsc_sender);
 
-        Ok(())
+        Ok(run_summary)
     }
 
     async fn execute_translation_impl(
-        token: &str,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
         source_lang: &str,
         target_lang: &str,
         mut translation_map: collections::HashMap<String, String>,
@@ -226,76 +318,49 @@ impl GoogleTranslateV2 {
             );
             send_progress(ProgressMessageType::ItemProcessed, &mpsc_sender);
 
+            // placeholders are respected only by HTML translation, HTML entities
+            // of plain text translated as HTML are decoded back once translated
+            let has_placeholders = !html::is_html(val) && placeholders::has_placeholders(val);
             let translation_format;
-            if html::is_html(val) == true {
+            if has_placeholders || html::is_html(val) {
                 translation_format = v2::TranslateFormat::Html;
             } else {
                 translation_format = v2::TranslateFormat::Plain;
             }
 
-            let mut translation_response;
-            let mut translation_result =
-                v2::translate(token, source_lang, target_lang, val, &translation_format).await;
-
-            if let Err(translation_error) = translation_result {
-                debug!(
-                    "error while translating value {}/{} for sub-batch: {}. Attempting one more time. Error detail: {:#?}",
-                    translated_item_idx, translation_count, iter_idx, translation_error
-                );
-                task::sleep(Duration::from_secs(2)).await; // wait with this task execution before next try!
-                translation_result =
-                    v2::translate(token, source_lang, target_lang, val, &translation_format).await;
-            }
-
-            if let Err(translation_error) = translation_result {
-                debug!(
-                    "2nd error while translating value {}/{} for sub-batch: {}. Skipping translating of this item. Error detail: {:#?}",
-                    translated_item_idx, translation_count, iter_idx, translation_error
-                );
-                continue;
-            } else {
-                debug!(
-                    "call translation api for item {}/{} for sub-batch: {} succeeded!",
-                    translated_item_idx, translation_count, iter_idx
-                );
-                translation_response = translation_result.unwrap();
-            }
-
-            debug!("translation_response {:#?}", translation_response);
-
-            if translation_response.status != "200" {
-                debug!(
-                    "error while translating value {}/{} for sub-batch {}. HTTP code is not 200. Attempting one more time. Error detail: {:#?}",
-                    translated_item_idx, translation_count, iter_idx, translation_response
-                );
-                task::sleep(Duration::from_secs(2)).await; // wait with this task execution before next try!
-                translation_result =
-                    v2::translate(token, source_lang, target_lang, val, &translation_format).await;
+            // retries (if any) are handled by http client according to retry policy
+            let translation_result = v2::translate(
+                http_client,
+                &api_config.translate_url,
+                source_lang,
+                target_lang,
+                val,
+                &translation_format,
+            )
+            .await;
 
-                if let Err(translation_error) = translation_result {
+            let translation_response = match translation_result {
+                Err(translation_error) => {
                     debug!(
-                        "2nd error while translating value {}/{} for sub-batch {}. Skipping translating of this item. Error detail: {:#?}",
+                        "error while translating value {}/{} for sub-batch: {}. Skipping translating of this item. Error detail: {:#?}",
                         translated_item_idx, translation_count, iter_idx, translation_error
                     );
                     continue;
                 }
+                Ok(translation_response) => translation_response,
+            };
 
-                if translation_response.status != "200" {
-                    debug!(
-                        "2nd error while translating value {}/{} for sub-batch {}. HTTP code is not 200. Skipping translating of this item. Error detail: {:#?}",
-                        translated_item_idx, translation_count, iter_idx, translation_response
-                    );
-                    continue;
-                }
+            debug!("translation_response {:#?}", translation_response);
 
+            if translation_response.status != "200" {
                 debug!(
-                    "2nd attempt to translate item {}/{} for sub-batch {} succeeded!",
-                    translated_item_idx, translation_count, iter_idx
+                    "error while translating value {}/{} for sub-batch {}. HTTP code is not 200. Skipping translating of this item. Error detail: {:#?}",
+                    translated_item_idx, translation_count, iter_idx, translation_response
                 );
-                translation_response = translation_result.unwrap();
+                continue;
             }
 
-            *val = translation_response
+            let translated_text = translation_response
                 .body
                 .data
                 .translations
@@ -303,6 +368,11 @@ impl GoogleTranslateV2 {
                 .map(|x| x.translated_text.to_owned())
                 .collect::<Vec<String>>()
                 .join("");
+            if has_placeholders {
+                *val = html::decode_entities(&translated_text);
+            } else {
+                *val = translated_text;
+            }
         }
         Ok(translation_map)
     }
@@ -310,20 +380,34 @@ impl GoogleTranslateV2 {
 
 impl GoogleTranslateV3 {
     pub fn execute_translation(
-        gdf_agent_path: &str,
-        translated_gdf_agent_folder: &str,
-        token: &str,
-        source_lang: &str,
-        target_lang: &str,
-        project_id: &str,
+        job: &TranslationJob,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
+        options: &V3Options,
         mpsc_sender: Sender<ProgressMessageType>,
-        create_output_tsv: bool,
-        skip_entities_translation: bool,
-        skip_utterances_translation: bool,
-        skip_responses_translation: bool,
-        glossary_path: Option<&str>,
-    ) -> Result<()> {
+    ) -> Result<RunSummary> {
+        let TranslationJob {
+            gdf_agent_path,
+            translated_gdf_agent_folder,
+            source_lang,
+            target_lang,
+            skip_entities_translation,
+            skip_utterances_translation,
+            skip_responses_translation,
+            agent_options,
+        } = *job;
+        let V3Options {
+            project_id,
+            glossary_path,
+            models,
+            ..
+        } = *options;
         debug!("processing agent {}", gdf_agent_path);
+        let mut run_summary = RunSummary::new("v3", source_lang, target_lang);
+        // same model (if any) is used for all batches, glossary is applied on top of it
+        let model = models.model_for(project_id, target_lang);
+        run_summary.model = model.clone();
+        run_summary.glossary = glossary_path.map(|path| path.to_owned());
 
         let progress = |msg: &str| {
             send_progress(
@@ -333,7 +417,7 @@ impl GoogleTranslateV3 {
         };
 
         progress("parsing zip file");
-        let mut agent = parse_gdf_agent_zip(gdf_agent_path)?;
+        let mut agent = parse_dialogflow_agent_zip(gdf_agent_path, agent_options.strict)?;
 
         progress("preparing translation map");
         let mut translation_map = agent.to_translation(
@@ -342,21 +426,11 @@ impl GoogleTranslateV3 {
             skip_entities_translation,
             skip_utterances_translation,
             skip_responses_translation,
+            agent_options,
         );
+        let references = placeholders::protect_references(&mut translation_map);
         debug!("translation_map {:#?}", translation_map);
-
-        let glossary_parameters = get_all_parameters(&translation_map);
-        let mut glossary_parameters_str: String = "".to_owned();
-        for item in glossary_parameters.iter() {
-            glossary_parameters_str = format!(
-                "{}{}\t{}\n",
-                glossary_parameters_str,
-                item.to_owned(),
-                item.to_owned()
-            );
-        }
-
-        debug!("glossary_parameters_str {:#?}", glossary_parameters_str);
+        run_summary.translated_items = translation_map.len();
 
         // partitioning translation map into subsets due to limitation / quotas of Google Translate V3 API
         let mut translation_maps: Vec<collections::HashMap<String, String>> = Vec::new();
@@ -412,9 +486,13 @@ impl GoogleTranslateV3 {
 
         progress(&format!("creating bucket {}", glossary_bucket_name));
 
-        let bucket_creation_result_glossary = task::block_on(
-            GoogleTranslateV3::create_glossary_bucket(&glossary_bucket_name, token, project_id),
-        )?;
+        let bucket_creation_result_glossary =
+            task::block_on(GoogleTranslateV3::create_glossary_bucket(
+                &glossary_bucket_name,
+                http_client,
+                api_config,
+                project_id,
+            ))?;
 
         debug!(
             "bucket {} result {:?}",
@@ -424,7 +502,6 @@ impl GoogleTranslateV3 {
         progress(&format!("bucket {} created", glossary_bucket_name));
 
         let mut translation_glossary = TranslationGlossary::new(&glossary_bucket_name); // glossary name will be same as the bucket name
-        translation_glossary.add(glossary_parameters_str);
 
         if let Some(glossary) = glossary_path {
             progress("loading glossary file");
@@ -433,7 +510,8 @@ impl GoogleTranslateV3 {
         }
 
         let bucket_upload_result = task::block_on(storage_bucket_mgmt::upload_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &glossary_bucket_name,
             &format!("{}.tsv", &translation_glossary.glossary_name),
             &translation_glossary.content,
@@ -452,7 +530,8 @@ impl GoogleTranslateV3 {
 
         progress("creating glossary");
         task::block_on(v3::create_glossary(
-            token,
+            http_client,
+            &api_config.translate_url,
             project_id,
             source_lang,
             target_lang,
@@ -475,7 +554,7 @@ impl GoogleTranslateV3 {
             // wait 2s to ensure:
             // - next iteration will get unique bucket names!
             // - buckets will be created OK. It seems Google Cloud API is very sensitive when creating buckets rapidly in sequence
-            thread::sleep(time::Duration::from_millis(20_000));
+            thread::sleep(api_config.bucket_creation_delay);
 
             let storage_bucket_name_in = format!("gdf_translate_input_{}", ts_millis.to_string());
             let storage_bucket_name_out = format!("gdf_translate_output_{}", ts_millis.to_string());
@@ -487,7 +566,8 @@ impl GoogleTranslateV3 {
                 task::block_on(GoogleTranslateV3::create_translation_buckets(
                     &storage_bucket_name_in,
                     &storage_bucket_name_out,
-                    token,
+                    http_client,
+                    api_config,
                     project_id,
                 ))?;
             debug!(
@@ -512,19 +592,21 @@ impl GoogleTranslateV3 {
                 storage_bucket_name_out, bucket_creation_result_out
             )); */
 
+            let batch = V3Batch {
+                iter_idx,
+                translation_map: map,
+                storage_bucket_name_in: storage_bucket_name_in.to_owned(),
+                storage_bucket_name_out: storage_bucket_name_out.to_owned(),
+                glossary: Some(&translation_glossary.glossary_name),
+                model: model.as_deref(),
+            };
             let future = GoogleTranslateV3::execute_translation_impl(
-                translated_gdf_agent_folder,
-                token,
-                source_lang,
-                target_lang,
-                project_id,
-                map,
-                create_output_tsv,
+                job,
+                http_client,
+                api_config,
+                options,
                 &mpsc_sender,
-                iter_idx,
-                storage_bucket_name_in.to_owned(),
-                storage_bucket_name_out.to_owned(),
-                Some(&translation_glossary.glossary_name),
+                batch,
             );
             futures.push(future);
         } // while let Some(map) = translation_maps.pop()
@@ -533,23 +615,29 @@ impl GoogleTranslateV3 {
         // https://users.rust-lang.org/t/futuresunordered/39461/4
         // uses asynchronous streams (see next() method), unfortunatelly this is still not described in async-std documentation (see https://book.async.rs/concepts/streams.html)
         // so it required little bit of investigation, not sure whether this is optimal way, probably tokio has better (and better documented) capabilities when it comes to joining the futures
+        // failed batch (e.g. batch translation not finished in time) fails the translation once glossary is cleaned up
+        let mut batch_error = None;
         task::block_on(async {
             while let Some(future_value) = futures.next().await {
                 // for this to compile StreamExt must be used, see use futures::stream::{FuturesUnordered, StreamExt}; !
                 match future_value {
                     Ok(translated_submap) => translation_map.extend(translated_submap),
-                    Err(e) => debug!(" Error when resolving future returned by GoogleTranslateV3::execute_translation_impl : {:#?}", e),
-                    // TBD: emit some text to CLI, maybe terminate the processing?
+                    Err(e) => {
+                        debug!(" Error when resolving future returned by GoogleTranslateV3::execute_translation_impl : {:#?}", e);
+                        batch_error.get_or_insert(e);
+                    }
                 }
             }
         });
 
         debug!("translation finished. updated translation map");
         debug!("{:#?}", translation_map);
+        restore_references(&mut translation_map, &references, &mut run_summary);
 
         progress("deleting glossary");
         let glossary_deletion_result = task::block_on(v3::delete_glossary(
-            token,
+            http_client,
+            &api_config.translate_url,
             project_id,
             &glossary_bucket_name,
         ));
@@ -564,7 +652,8 @@ impl GoogleTranslateV3 {
 
         debug!("deleting {}.tsv", &translation_glossary.glossary_name);
         let delete_object_result = task::block_on(storage_bucket_mgmt::delete_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &glossary_bucket_name,
             &format!("{}.tsv", &translation_glossary.glossary_name),
         ));
@@ -575,7 +664,8 @@ impl GoogleTranslateV3 {
 
         debug!("deleting {}", &glossary_bucket_name);
         let delete_glossary_bucket_result = task::block_on(storage_bucket_mgmt::delete_bucket(
-            token,
+            http_client,
+            &api_config.storage_url,
             &glossary_bucket_name,
         ));
         if let Err(glossary_bucket_deletion_error) = delete_glossary_bucket_result {
@@ -591,6 +681,10 @@ impl GoogleTranslateV3 {
             );
         }
 
+        if let Some(err) = batch_error {
+            return Err(err);
+        }
+
         progress("translation finished, updating DialogFlow agent");
 
         debug!("applying translated map to agent");
@@ -600,27 +694,32 @@ impl GoogleTranslateV3 {
             skip_entities_translation,
             skip_utterances_translation,
             skip_responses_translation,
+            agent_options,
         );
-        agent.add_supported_language(target_lang);
+        agent.add_supported_language(target_lang, agent_options);
+        run_summary.report.extend(agent.take_report());
         debug!("serializing agent");
         progress("storing agent to file system");
         agent.serialize(translated_gdf_agent_folder)?;
         debug!("agent serialized!");
+        run_summary.write(translated_gdf_agent_folder)?;
         progress("all good! exiting.");
 
         send_progress(ProgressMessageType::Exit, &mpsc_sender);
 
-        Ok(())
+        Ok(run_summary)
     }
 
     async fn create_translation_buckets(
         storage_bucket_name_in: &str,
         storage_bucket_name_out: &str,
-        token: &str,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
         project_id: &str,
     ) -> Result<(ApiResponse, ApiResponse)> {
         let bucket_creation_result_in = storage_bucket_mgmt::create_bucket(
-            token,
+            http_client,
+            &api_config.storage_url,
             project_id,
             &storage_bucket_name_in,
             "EUROPE-WEST3",
@@ -637,7 +736,8 @@ impl GoogleTranslateV3 {
         }
 
         let bucket_creation_result_out = storage_bucket_mgmt::create_bucket(
-            token,
+            http_client,
+            &api_config.storage_url,
             project_id,
             &storage_bucket_name_out,
             "EUROPE-WEST3",
@@ -661,11 +761,13 @@ impl GoogleTranslateV3 {
 
     async fn create_glossary_bucket(
         glossary_bucket_name: &str,
-        token: &str,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
         project_id: &str,
     ) -> Result<ApiResponse> {
         let bucket_creation_result_glossary = storage_bucket_mgmt::create_bucket(
-            token,
+            http_client,
+            &api_config.storage_url,
             project_id,
             &glossary_bucket_name,
             "EUROPE-WEST3",
@@ -688,19 +790,22 @@ impl GoogleTranslateV3 {
     }
 
     async fn execute_translation_impl(
-        translated_gdf_agent_folder: &str,
-        token: &str,
-        source_lang: &str,
-        target_lang: &str,
-        project_id: &str,
-        translation_map: collections::HashMap<String, String>,
-        create_output_tsv: bool,
+        job: &TranslationJob<'_>,
+        http_client: &HttpClient,
+        api_config: &GoogleApiConfig,
+        options: &V3Options<'_>,
         mpsc_sender: &Sender<ProgressMessageType>,
-        iter_idx: usize,
-        storage_bucket_name_in: String,
-        storage_bucket_name_out: String,
-        glossary: Option<&str>,
+        batch: V3Batch<'_>,
     ) -> Result<collections::HashMap<String, String>> {
+        let V3Batch {
+            iter_idx,
+            translation_map,
+            storage_bucket_name_in,
+            storage_bucket_name_out,
+            glossary,
+            model,
+        } = batch;
+        let (source_lang, target_lang) = (job.source_lang, job.target_lang);
         let progress = |msg: String| {
             send_progress(ProgressMessageType::TextMessage(msg), &mpsc_sender);
         };
@@ -710,7 +815,8 @@ impl GoogleTranslateV3 {
 
         progress(format!("uploading translation map {}", iter_idx));
         let bucket_upload_result = storage_bucket_mgmt::upload_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &storage_bucket_name_in,
             "translation_map.tsv",
             &map_str,
@@ -727,14 +833,18 @@ impl GoogleTranslateV3 {
 
         progress(format!("triggering batch translation request {}", iter_idx));
         let translation_result = v3::batch_translate_text(
-            token,
-            project_id,
-            source_lang,
-            target_lang,
-            "text/html", // always HTML, we are wrapping text to translate in <span> tag
-            &format!("gs://{}/translation_map.tsv", storage_bucket_name_in),
-            &format!("gs://{}/", storage_bucket_name_out),
-            glossary,
+            http_client,
+            &api_config.translate_url,
+            options.project_id,
+            &v3::BatchTranslateRequest {
+                source_lang,
+                target_lang,
+                mime_type: "text/html", // always HTML, we are wrapping text to translate in <span> tag
+                input_uri: &format!("gs://{}/translation_map.tsv", storage_bucket_name_in),
+                output_uri_prefix: &format!("gs://{}/", storage_bucket_name_out),
+                glossary,
+                model,
+            },
         )
         .await?;
         debug!("translation_result {:#?}", translation_result);
@@ -746,10 +856,16 @@ impl GoogleTranslateV3 {
             )));
         }
 
+        // batch translation is polled same way as Dialogflow operations
+        let started = Instant::now();
         loop {
             // progress("checking for translation result");
-            let translation_operation_result =
-                v3::batch_translate_text_check_status(token, &translation_result.body.name).await?;
+            let translation_operation_result = v3::batch_translate_text_check_status(
+                http_client,
+                &api_config.translate_url,
+                &translation_result.body.name,
+            )
+            .await?;
 
             debug!(
                 "translation_operation_result {:#?}",
@@ -783,6 +899,14 @@ impl GoogleTranslateV3 {
                 progress(format!("batch translation still running {}", iter_idx));
                 debug!("still running, checking the state again...")
             }
+
+            if started.elapsed() >= api_config.operation_timeout {
+                return Err(Error::new(format!(
+                    "GoogleTranslateV3.execute_translation batch translation {} not finished within {:?}",
+                    translation_result.body.name, api_config.operation_timeout
+                )));
+            }
+            task::sleep(api_config.operation_poll_interval).await;
         }
 
         // e.g. gs://gdf_translate_output_1594998623/gdf_translate_input_1594998623_translation_map_de_translations.tsv
@@ -795,7 +919,8 @@ impl GoogleTranslateV3 {
 
         progress(format!("downloading translation result {}", iter_idx));
         let bucket_download_result = storage_bucket_mgmt::download_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &storage_bucket_name_out,
             &translated_object_name,
         )
@@ -809,10 +934,10 @@ impl GoogleTranslateV3 {
             )));
         }
 
-        if create_output_tsv == true {
+        if options.create_output_tsv == true {
             let mut file_handle = File::create(format!(
                 "{}/bucket_download_result_{}.txt",
-                translated_gdf_agent_folder, iter_idx
+                job.translated_gdf_agent_folder, iter_idx
             ))?;
             file_handle.write_all(bucket_download_result.body.as_bytes())?;
         }
@@ -834,14 +959,19 @@ impl GoogleTranslateV3 {
             iter_idx
         ));
         debug!("deleting index.csv");
-        delete_object_result =
-            storage_bucket_mgmt::delete_object(token, &storage_bucket_name_out, "index.csv")
-                .await?;
+        delete_object_result = storage_bucket_mgmt::delete_object(
+            http_client,
+            &api_config.storage_url,
+            &storage_bucket_name_out,
+            "index.csv",
+        )
+        .await?;
         debug!("delete_object_result {:#?}", delete_object_result);
 
         debug!("deleting {}", translated_object_name);
         delete_object_result = storage_bucket_mgmt::delete_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &storage_bucket_name_out,
             &translated_object_name,
         )
@@ -850,7 +980,8 @@ impl GoogleTranslateV3 {
 
         debug!("deleting translation_map.tsv");
         delete_object_result = storage_bucket_mgmt::delete_object(
-            token,
+            http_client,
+            &api_config.storage_url,
             &storage_bucket_name_in,
             "translation_map.tsv",
         )
@@ -858,13 +989,21 @@ impl GoogleTranslateV3 {
         debug!("delete_object_result {:#?}", delete_object_result);
 
         debug!("deleting {}", &storage_bucket_name_in);
-        let delete_bucket_result_in =
-            storage_bucket_mgmt::delete_bucket(token, &storage_bucket_name_in).await?;
+        let delete_bucket_result_in = storage_bucket_mgmt::delete_bucket(
+            http_client,
+            &api_config.storage_url,
+            &storage_bucket_name_in,
+        )
+        .await?;
         debug!("delete_bucket_result_in {:#?}", delete_bucket_result_in);
 
         debug!("deleting {}", &storage_bucket_name_out);
-        let delete_bucket_result_out =
-            storage_bucket_mgmt::delete_bucket(token, &storage_bucket_name_out).await?;
+        let delete_bucket_result_out = storage_bucket_mgmt::delete_bucket(
+            http_client,
+            &api_config.storage_url,
+            &storage_bucket_name_out,
+        )
+        .await?;
         debug!("delete_bucket_result_out {:#?}", delete_bucket_result_out);
 
         // progress(format!("returning translation map {}", iter_idx));
@@ -884,12 +1023,15 @@ impl DummyTranslate {
         target_lang: &str,
     ) -> Result<()> {
         debug!("processing agent {}", gdf_agent_path);
-        let mut agent = parse_gdf_agent_zip(gdf_agent_path)?;
+        let options = AgentOptions::default();
+        let mut agent = parse_dialogflow_agent_zip(gdf_agent_path, options.strict)?;
         let mut translation_map =
-            agent.to_translation(source_lang, target_lang, false, false, false);
+            agent.to_translation(source_lang, target_lang, false, false, false, &options);
+        let references = placeholders::protect_references(&mut translation_map);
         dummy_translate(&mut translation_map);
-        agent.from_translation(&translation_map, target_lang, false, false, false);
-        agent.add_supported_language(target_lang);
+        placeholders::restore_references(&mut translation_map, &references);
+        agent.from_translation(&translation_map, target_lang, false, false, false, &options);
+        agent.add_supported_language(target_lang, &options);
         agent.serialize(translated_gdf_agent_folder)?;
         Ok(())
     }
@@ -899,9 +1041,19 @@ impl DummyTranslate {
 mod tests {
     use super::*;
     use crate::google::gcloud::auth::*;
+    use crate::google::gcloud::http::test_server::{scripted_server, ScriptedResponse};
+    use crate::google::gcloud::http::RetryPolicy;
     use crate::init_logging; // set RUST_LOG=gdf_translate::google::gcloud::translate=debug
+    use crate::ssml;
     use std::sync::mpsc::channel;
 
+    const AUTHORIZED_USER_JSON: &str = r#"{
+        "client_id": "dummy-client-id.apps.googleusercontent.com",
+        "client_secret": "dummy-client-secret",
+        "refresh_token": "dummy-refresh-token",
+        "type": "authorized_user"
+    }"#;
+
     const SAMPLE_AGENTS_FOLDER: &str =
         "C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/sample_agents/";
 
@@ -949,6 +1101,93 @@ mod tests {
         println!("s is \n{}", s);
     }
 
+    // cargo test -- --show-output test_restore_references
+    #[test]
+    fn test_restore_references() {
+        let mut translation_map = collections::HashMap::new();
+        translation_map.insert("addr1".to_owned(), "Lights in $room are on.".to_owned());
+        translation_map.insert("addr2".to_owned(), "$first then $last".to_owned());
+        let references = placeholders::protect_references(&mut translation_map);
+
+        // translation lost the placeholder of $room and swapped $first and $last
+        translation_map.insert("addr1".to_owned(), "Licht in ist an.".to_owned());
+        translation_map.insert(
+            "addr2".to_owned(),
+            r#"<span translate="no">1</span> nach <span translate="no">0</span>"#.to_owned(),
+        );
+
+        let mut run_summary = RunSummary::new("v2", "en", "de");
+        restore_references(&mut translation_map, &references, &mut run_summary);
+
+        // text with lost reference keeps its source text
+        assert_eq!(translation_map["addr1"], "Lights in $room are on.");
+        assert_eq!(translation_map["addr2"], "$last nach $first");
+        let mut messages: Vec<&str> = run_summary
+            .report
+            .iter()
+            .map(|item| item.message.as_str())
+            .collect();
+        messages.sort_unstable();
+        assert_eq!(
+            messages,
+            vec![
+                "$first is reordered in \"$first then $last\"",
+                "$room is missing in \"Lights in $room are on.\", translation not applied",
+            ]
+        );
+        assert!(run_summary
+            .report
+            .iter()
+            .all(|item| item.category == "reference"));
+    }
+
+    // cargo test -- --show-output test_translate_ssml_with_entities_v2
+    #[test]
+    fn test_translate_ssml_with_entities_v2() {
+        let (token_url, token_server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"access_token":"t1","token_type":"Bearer","expires_in":3600}"#,
+        )]);
+        // segment with placeholder is translated as HTML, entities come back escaped
+        let (url, server) = scripted_server(vec![ScriptedResponse::new(
+            200,
+            r#"{"data":{"translations":[{"translatedText":"A &amp; B <span translate=\"no\">0</span> DE"}]}}"#,
+        )]);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_json_str(AUTHORIZED_USER_JSON).unwrap(),
+                Some(&token_url),
+            ),
+            RetryPolicy::no_retry(),
+        );
+        let api_config = GoogleApiConfig::new(None, Some(&url), None, None, None);
+
+        let ssml = "<speak>A &amp; B $param</speak>".to_owned();
+        let mut translation_map = ssml::ssml_to_translation(&ssml);
+        let references = placeholders::protect_references(&mut translation_map);
+        let (tx, _rx) = channel::<ProgressMessageType>();
+        let mut translation_map = task::block_on(GoogleTranslateV2::execute_translation_impl(
+            &http_client,
+            &api_config,
+            "en",
+            "de",
+            translation_map,
+            tx,
+            0,
+        ))
+        .unwrap();
+        let mut run_summary = RunSummary::new("v2", "en", "de");
+        restore_references(&mut translation_map, &references, &mut run_summary);
+        assert!(run_summary.report.is_empty());
+
+        assert_eq!(
+            ssml::translated_ssml(&ssml, &translation_map),
+            Ok(Some("<speak>A &amp; B $param DE</speak>".to_owned()))
+        );
+        assert!(server.join().unwrap()[0].0.contains("format=html"));
+        token_server.join().unwrap();
+    }
+
     // cargo test -- --show-output test_execute_translation_dummy
     #[test]
     #[ignore]
@@ -972,23 +1211,32 @@ mod tests {
     fn test_execute_translation_google_v2() -> Result<()> {
         init_logging();
         let agent_path = format!("{}{}", SAMPLE_AGENTS_FOLDER, "Currency-Converter.zip");
-        debug!("getting bearer token...");
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-        debug!("bearer token retrieved {}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let (tx, _) = channel::<ProgressMessageType>();
         let _ = GoogleTranslateV2::execute_translation(
-            &agent_path,
-            "c:/tmp/out_translated",
-            &token,
-            "en",
-            "de",
+            &TranslationJob {
+                gdf_agent_path: &agent_path,
+                translated_gdf_agent_folder: "c:/tmp/out_translated",
+                source_lang: "en",
+                target_lang: "de",
+                skip_entities_translation: false,
+                skip_utterances_translation: false,
+                skip_responses_translation: false,
+                agent_options: &AgentOptions::default(),
+            },
+            &http_client,
+            &GoogleApiConfig::default(),
+            &V2Options {
+                task_count: 1,
+                rate_limits: &RateLimits::new(None, None),
+            },
             tx,
-            1,
-            false,
-            false,
-            false,
         );
 
         Ok(())
@@ -1002,26 +1250,34 @@ mod tests {
         // let agent_path = format!("c:/tmp/Express_CS_AP_PRD.zip");
         // let agent_path = format!("c:/tmp/Currency-Converter.zip");
         let agent_path = format!("{}{}", SAMPLE_AGENTS_FOLDER, "Currency-Converter.zip");
-        debug!("getting bearer token...");
-        let token: Result<GoogleApisOauthToken> = task::block_on(get_google_api_token(
-            "./examples/testdata/credentials_v3.json",
-        ));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-        debug!("bearer token retrieved {}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials_v3.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let (tx, _) = channel::<ProgressMessageType>();
         let translation_result = GoogleTranslateV3::execute_translation(
-            &agent_path,
-            "c:/tmp/out_translated",
-            &token,
-            "en",
-            "de",
-            "express-tracking",
+            &TranslationJob {
+                gdf_agent_path: &agent_path,
+                translated_gdf_agent_folder: "c:/tmp/out_translated",
+                source_lang: "en",
+                target_lang: "de",
+                skip_entities_translation: false,
+                skip_utterances_translation: false,
+                skip_responses_translation: false,
+                agent_options: &AgentOptions::default(),
+            },
+            &http_client,
+            &GoogleApiConfig::default(),
+            &V3Options {
+                project_id: "express-tracking",
+                create_output_tsv: false,
+                glossary_path: None,
+                models: &TranslationModels::default(),
+            },
             tx,
-            false,
-            false,
-            false,
-            false,
-            None,
         );
 
         debug!("translation_result: {:#?}", translation_result);
@@ -1,6 +1,7 @@
 //! # implementation of google translation api v2
 //!
 use crate::errors::Result;
+use crate::google::gcloud::http::{HttpBody, HttpClient, HttpRequest};
 #[allow(unused_imports)]
 use async_std::{fs, task};
 use log::debug;
@@ -49,33 +50,40 @@ pub struct TranslateResponseBody {
 
 // see https://cloud.google.com/translate/docs/reference/rest/v2/translate
 pub async fn translate(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     source_lang: &str,
     target_lang: &str,
     text: &str,
     format: &TranslateFormat,
 ) -> Result<TranslateResponse> {
-    let api_url = "https://translation.googleapis.com/language/translate/v2";
+    let api_url = format!("{}/language/translate/v2", translate_url);
 
     let format_str = match format {
         TranslateFormat::Html => "html",
         TranslateFormat::Plain => "text",
     };
 
+    let query = serde_urlencoded::to_string(&TranslateQuery {
+        q: text.to_owned(),
+        target: target_lang.to_owned(),
+        format: format_str.to_owned(),
+        source: source_lang.to_owned(),
+    })?;
+
     debug!("going to translate text {}", text);
-    let mut resp = surf::post(api_url)
-        .set_header("Authorization", token)
-        .set_query(&TranslateQuery {
-            q: text.to_owned(),
-            target: target_lang.to_owned(),
-            format: format_str.to_owned(),
-            source: source_lang.to_owned(),
-        })?
+    // translation has no side effects, safe to retry
+    let resp = http_client
+        .send(
+            &HttpRequest::post(format!("{}?{}", api_url, query), HttpBody::Empty)
+                .idempotent()
+                .with_cost(text.chars().count() as u64),
+        )
         .await?;
 
     Ok(TranslateResponse {
-        status: resp.status().as_str().to_string(),
-        body: resp.body_json::<TranslateResponseBody>().await?,
+        status: resp.status_code,
+        body: serde_json::from_str::<TranslateResponseBody>(&resp.body)?,
     })
 }
 
@@ -89,9 +97,11 @@ async fn sample_http_call() -> Result<String> {
 mod tests {
     use super::*;
     use crate::google::gcloud::auth::*;
+    use crate::google::gcloud::http::{HttpClient, RetryPolicy};
+    use crate::google::gcloud::DEFAULT_TRANSLATE_URL;
 
     async fn translate_v2_dummy_wrapper(
-        token: &str,
+        http_client: &HttpClient,
         source_lang: &str,
         target_lang: &str,
         text: &str,
@@ -100,7 +110,8 @@ mod tests {
         for idx in iter_vec.iter() {
             println!("iteration {}", idx);
             let resp = translate(
-                token,
+                http_client,
+                DEFAULT_TRANSLATE_URL,
                 source_lang,
                 target_lang,
                 text,
@@ -135,12 +146,17 @@ mod tests {
     #[test]
     #[ignore]
     fn test_translate_v2() -> Result<()> {
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
 
         let result: Result<TranslateResponse> = task::block_on(translate(
-            &token,
+            &http_client,
+            DEFAULT_TRANSLATE_URL,
             "en",
             "de",
             "Rust is wonderfull programming language",
@@ -154,12 +170,16 @@ mod tests {
     #[test]
     #[ignore]
     fn test_translate_v2_wrapped() -> Result<()> {
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
 
         let _ = task::block_on(translate_v2_dummy_wrapper(
-            &token,
+            &http_client,
             "en",
             "de",
             "Rust is wonderfull programming language. This is wrapped translation!",
@@ -53,6 +53,7 @@
 //! ```
 //!
 use crate::errors::{Error, Result};
+use crate::google::gcloud::http::{expect_success, HttpBody, HttpClient, HttpRequest};
 use lazy_static::lazy_static;
 use log::debug;
 use regex::Regex;
@@ -360,110 +361,181 @@ fn parse_tsv_line(line: &str) -> Result<TsvLine> {
     })
 }
 
+/// Translation models selected by user for target languages, see
+/// [AutoML Translation](https://cloud.google.com/translate/automl/docs)
+/// and [models](https://cloud.google.com/translate/docs/advanced/translating-text-v3#comparing-models).
+///
+/// Every value is either `MODEL` (used for all target languages) or `LANG=MODEL`.
+/// MODEL can be:
+/// * fully qualified model, e.g. projects/my-project/locations/us-central1/models/TRL123
+/// * general model, e.g. general/nmt or general/base
+/// * ID of AutoML model, e.g. TRL123
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TranslationModels {
+    default_model: Option<String>,
+    language_models: collections::HashMap<String, String>,
+}
+
+impl TranslationModels {
+    pub fn parse(values: &[&str]) -> Result<Self> {
+        let mut models = TranslationModels::default();
+        for value in values.iter().map(|value| value.trim()) {
+            match value.split_once('=') {
+                Some((lang, model)) if !lang.trim().is_empty() && !model.trim().is_empty() => {
+                    models
+                        .language_models
+                        .insert(lang.trim().to_lowercase(), model.trim().to_owned());
+                }
+                None if !value.is_empty() => {
+                    if models.default_model.is_some() {
+                        return Err(Error::new(format!(
+                            "model {} specified more than once without target language",
+                            value
+                        )));
+                    }
+                    models.default_model = Some(value.to_owned());
+                }
+                _ => {
+                    return Err(Error::new(format!(
+                        "invalid model {}, expected MODEL or LANG=MODEL",
+                        value
+                    )))
+                }
+            }
+        }
+        Ok(models)
+    }
+
+    /// returns fully qualified model for target language or None if API default should be used
+    pub fn model_for(&self, project_id: &str, target_lang: &str) -> Option<String> {
+        self.language_models
+            .get(&target_lang.to_lowercase())
+            .or(self.default_model.as_ref())
+            .map(|model| qualified_model_name(project_id, model))
+    }
+}
+
+fn qualified_model_name(project_id: &str, model: &str) -> String {
+    if model.starts_with("projects/") {
+        model.to_owned()
+    } else {
+        format!(
+            "projects/{}/locations/us-central1/models/{}",
+            project_id, model
+        )
+    }
+}
+
+/// Batch translation of single csv/tsv file
+pub struct BatchTranslateRequest<'a> {
+    /// e.g. 'en'
+    pub source_lang: &'a str,
+    /// e.g. 'de'
+    pub target_lang: &'a str,
+    /// text/html or text/plain
+    pub mime_type: &'a str,
+    /// e.g. gs://translate_v3_test_in/input.tsv
+    pub input_uri: &'a str,
+    /// e.g. gs://translate_v3_test_out/
+    pub output_uri_prefix: &'a str,
+    /// name of the glossary, same as glossary bucket name
+    pub glossary: Option<&'a str>,
+    /// fully qualified model, e.g. projects/my-project/locations/us-central1/models/general/nmt. None means API default
+    pub model: Option<&'a str>,
+}
+
 /// Translates csv/tsv file using Google Translate V3 REST API
 ///
 /// See: https://cloud.google.com/translate/docs/reference/rest/v3/projects/translateText
 ///
 /// Arguments:
 ///
-/// * `token`: Bearer token
+/// * `http_client`: HTTP client adding bearer token and applying retry policy
+/// * `translate_url`: base URL of translation API, e.g. https://translation.googleapis.com
 /// * `project_id`: Google project ID
-/// * `source_lang`: e.g. 'en'
-/// * `target_lang`: e.g. 'de'
-/// * `mime_type`: text/html or text/plain
-/// * `input_uri`: e.g. gs://translate_v3_test_in/input.tsv
-/// * `output_uri_prefix`: e.g. gs://translate_v3_test_out/
-/// * `glossary`: name of the glossary, same as glossary bucket name
+/// * `request`: languages, input and output of the translation
 pub async fn batch_translate_text(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     project_id: &str,
-    source_lang: &str,
-    target_lang: &str,
-    mime_type: &str,
-    input_uri: &str,
-    output_uri_prefix: &str,
-    glossary: Option<&str>,
+    request: &BatchTranslateRequest<'_>,
 ) -> Result<GoogleTranslateV3ApiResponse> {
+    let BatchTranslateRequest {
+        source_lang,
+        target_lang,
+        mime_type,
+        input_uri,
+        output_uri_prefix,
+        glossary,
+        model,
+    } = *request;
     let url = format!(
-        "https://translation.googleapis.com/v3/projects/{}/locations/us-central1:batchTranslateText",
-        project_id
+        "{}/v3/projects/{}/locations/us-central1:batchTranslateText",
+        translate_url, project_id
     );
 
-    let body;
-    if let Some(glossary_name) = glossary {
-        body = json!({
-            "sourceLanguageCode": source_lang,
-            "targetLanguageCodes": target_lang,
-            "inputConfigs": [{
-                "mimeType":  mime_type,
-                "gcsSource": {
-                    "inputUri": input_uri
-                }
-            }],
-            "glossaries": {
-                target_lang: {
-                    "glossary": format!("projects/{}/locations/us-central1/glossaries/{}", project_id, glossary_name),
-                    "ignoreCase": true
-                }
-            },
-            "outputConfig": {
-                "gcsDestination": {
-                    "outputUriPrefix": output_uri_prefix
-                }
+    let mut body = json!({
+        "sourceLanguageCode": source_lang,
+        "targetLanguageCodes": target_lang,
+        "inputConfigs": [{
+            "mimeType":  mime_type,
+            "gcsSource": {
+                "inputUri": input_uri
             }
-        });
-    } else {
-        body = json!({
-            "sourceLanguageCode": source_lang,
-            "targetLanguageCodes": target_lang,
-            "inputConfigs": [{
-                "mimeType":  mime_type,
-                "gcsSource": {
-                    "inputUri": input_uri
-                }
-            }],
-            "outputConfig": {
-                "gcsDestination": {
-                    "outputUriPrefix": output_uri_prefix
-                }
+        }],
+        "outputConfig": {
+            "gcsDestination": {
+                "outputUriPrefix": output_uri_prefix
+            }
+        }
+    });
+
+    if let Some(glossary_name) = glossary {
+        body["glossaries"] = json!({
+            target_lang: {
+                "glossary": format!("projects/{}/locations/us-central1/glossaries/{}", project_id, glossary_name),
+                "ignoreCase": true
             }
         });
     }
 
+    if let Some(model) = model {
+        body["models"] = json!({ target_lang: model });
+    }
+
     debug!("body: {}", body);
     debug!("url: {}", url);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_json(&body)?
+    // starts new long running operation, not idempotent
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)))
         .await?;
 
-    let body_str = resp.body_string().await?;
-    debug!("batch_translate_text.body_str: {}", body_str);
+    debug!("batch_translate_text.body_str: {}", resp.body);
+    expect_success("batch_translate_text", &resp)?;
 
-    let response_body: GoogleTranslateV3Response = serde_json::from_str(&body_str)?;
+    let response_body: GoogleTranslateV3Response = serde_json::from_str(&resp.body)?;
 
     debug!("batch_translate_text.response_body: {:#?}", response_body);
 
     Ok(GoogleTranslateV3ApiResponse {
-        status_code: resp.status().as_str().to_string(),
+        status_code: resp.status_code,
         body: response_body,
     })
 }
 
 /// Check the status of long running operation representing batch translation request
 ///
-/// * `token`: Bearer token
+/// * `http_client`: HTTP client adding bearer token and applying retry policy
+/// * `translate_url`: base URL of translation API, e.g. https://translation.googleapis.com
 /// * `long_running_operation`: something like projects/345634260051/locations/us-central1/operations/20200711-05421594471378-5f058a16-0000-2dd4-8106-883d24f67490.
 /// Returned by https://translation.googleapis.com/v3/projects/{}/locations/us-central1:batchTranslateText API
 pub async fn batch_translate_text_check_status(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     long_running_operation: &str,
 ) -> Result<GoogleTranslateV3WaitApiResponse> {
-    let url = format!(
-        "https://translation.googleapis.com/v3/{}:wait",
-        long_running_operation
-    );
+    let url = format!("{}/v3/{}:wait", translate_url, long_running_operation);
 
     let body = json!({
         "timeout": "60s"
@@ -472,25 +544,25 @@ pub async fn batch_translate_text_check_status(
     debug!("url: {}", url);
     debug!("body: {}", body);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_json(&body)?
+    // waiting for operation has no side effects, safe to retry
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)).idempotent())
         .await?;
 
-    let body_str = resp.body_string().await?;
+    debug!("response body: {}", resp.body);
+    expect_success("batch_translate_text_check_status", &resp)?;
 
-    debug!("response body: {}", body_str);
-
-    let response_body: GoogleTranslateV3WaitResponse = serde_json::from_str(&body_str)?;
+    let response_body: GoogleTranslateV3WaitResponse = serde_json::from_str(&resp.body)?;
 
     Ok(GoogleTranslateV3WaitApiResponse {
-        status_code: resp.status().as_str().to_string(),
+        status_code: resp.status_code,
         body: response_body,
     })
 }
 
 pub async fn create_glossary(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     project_id: &str,
     source_lang: &str,
     target_lang: &str,
@@ -498,8 +570,8 @@ pub async fn create_glossary(
     bucket_name: &str,
 ) -> Result<GoogleTranslateV3Response> {
     let url = format!(
-        "https://translation.googleapis.com/v3/projects/{}/locations/us-central1/glossaries",
-        project_id
+        "{}/v3/projects/{}/locations/us-central1/glossaries",
+        translate_url, project_id
     );
 
     let glossary_name = format!(
@@ -522,15 +594,15 @@ pub async fn create_glossary(
     debug!("body: {}", body);
     debug!("url: {}", url);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_json(&body)?
+    // starts new long running operation, not idempotent
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)))
         .await?;
 
-    let body_str = resp.body_string().await?;
-    debug!("create_glossary.body_str: {}", body_str);
+    debug!("create_glossary.body_str: {}", resp.body);
+    expect_success("create_glossary", &resp)?;
 
-    let response_body: GoogleTranslateV3Response = serde_json::from_str(&body_str)?;
+    let response_body: GoogleTranslateV3Response = serde_json::from_str(&resp.body)?;
 
     debug!("create_glossary.response_body: {:#?}", response_body);
 
@@ -538,13 +610,11 @@ pub async fn create_glossary(
 }
 
 pub async fn create_glossary_check_status(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     long_running_operation: &str,
 ) -> Result<GoogleCreateGlossaryWaitApiResponse> {
-    let url = format!(
-        "https://translation.googleapis.com/v3/{}:wait",
-        long_running_operation
-    );
+    let url = format!("{}/v3/{}:wait", translate_url, long_running_operation);
 
     let body = json!({
         "timeout": "60s"
@@ -553,41 +623,41 @@ pub async fn create_glossary_check_status(
     debug!("url: {}", url);
     debug!("body: {}", body);
 
-    let mut resp = surf::post(url)
-        .set_header("Authorization", token)
-        .body_json(&body)?
+    // waiting for operation has no side effects, safe to retry
+    let resp = http_client
+        .send(&HttpRequest::post(url, HttpBody::Json(body)).idempotent())
         .await?;
 
-    let body_str = resp.body_string().await?;
+    debug!("response body: {}", resp.body);
+    expect_success("create_glossary_check_status", &resp)?;
 
-    debug!("response body: {}", body_str);
-
-    let response_body: GoogleCreateGlossaryWaitResponse = serde_json::from_str(&body_str)?;
+    let response_body: GoogleCreateGlossaryWaitResponse = serde_json::from_str(&resp.body)?;
 
     Ok(GoogleCreateGlossaryWaitApiResponse {
-        status_code: resp.status().as_str().to_string(),
+        status_code: resp.status_code,
         body: response_body,
     })
 }
 
 pub async fn delete_glossary(
-    token: &str,
+    http_client: &HttpClient,
+    translate_url: &str,
     project_id: &str,
     glossary_id: &str,
 ) -> Result<GoogleTranslateV3Response> {
     let url = format!(
-        "https://translation.googleapis.com/v3/projects/{}/locations/us-central1/glossaries/{}",
-        project_id, glossary_id
+        "{}/v3/projects/{}/locations/us-central1/glossaries/{}",
+        translate_url, project_id, glossary_id
     );
 
     debug!("url: {}", url);
 
-    let mut resp = surf::delete(url).set_header("Authorization", token).await?;
+    let resp = http_client.send(&HttpRequest::delete(url)).await?;
 
-    let body_str = resp.body_string().await?;
-    debug!("delete_glossary.body_str: {}", body_str);
+    debug!("delete_glossary.body_str: {}", resp.body);
+    expect_success("delete_glossary", &resp)?;
 
-    let response_body: GoogleTranslateV3Response = serde_json::from_str(&body_str)?;
+    let response_body: GoogleTranslateV3Response = serde_json::from_str(&resp.body)?;
 
     debug!("delete_glossary.response_body: {:#?}", response_body);
 
@@ -598,6 +668,8 @@ pub async fn delete_glossary(
 mod tests {
     use super::*;
     use crate::google::gcloud::auth::*;
+    use crate::google::gcloud::http::{HttpClient, RetryPolicy};
+    use crate::google::gcloud::DEFAULT_TRANSLATE_URL;
     use crate::init_logging; // set RUST_LOG=gdf_translate::google::gcloud::translate::v3=debug
     use async_std::task;
     use std::fs::File;
@@ -608,29 +680,38 @@ mod tests {
     #[ignore]
     fn test_batch_translate_text() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<GoogleTranslateV3ApiResponse> =
             task::block_on(batch_translate_text(
-                &token,
+                &http_client,
+                DEFAULT_TRANSLATE_URL,
                 "express-tracking",
-                "en",
-                "de",
-                "text/html",
-                "gs://translate_v3_test/translation_map.tsv",
-                "gs://translate_v3_test_out/",
-                None,
+                &BatchTranslateRequest {
+                    source_lang: "en",
+                    target_lang: "de",
+                    mime_type: "text/html",
+                    input_uri: "gs://translate_v3_test/translation_map.tsv",
+                    output_uri_prefix: "gs://translate_v3_test_out/",
+                    glossary: None,
+                    model: None,
+                },
             ));
 
         let api_response = api_response.unwrap();
         println!("api_response {:#?}", api_response);
 
-        let api_response2: Result<GoogleTranslateV3WaitApiResponse> = task::block_on(
-            batch_translate_text_check_status(&token, &api_response.body.name),
-        );
+        let api_response2: Result<GoogleTranslateV3WaitApiResponse> =
+            task::block_on(batch_translate_text_check_status(
+                &http_client,
+                DEFAULT_TRANSLATE_URL,
+                &api_response.body.name,
+            ));
 
         println!("api_response2 {:#?}", api_response2);
         Ok(())
@@ -641,14 +722,17 @@ mod tests {
     #[ignore]
     fn test_batch_translate_text_wait() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<GoogleTranslateV3WaitApiResponse> = task::block_on(
             batch_translate_text_check_status(
-                &token,
+                &http_client,
+                DEFAULT_TRANSLATE_URL,
                 "projects/345634260051/locations/us-central1/operations/20200711-06301594474232-5f0599bc-0000-2328-9a34-883d24f6d7a8"
             ),
         );
@@ -752,13 +836,16 @@ mod tests {
     #[ignore]
     fn test_create_glossary() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
         let api_response: Result<GoogleTranslateV3Response> = task::block_on(create_glossary(
-            &token,
+            &http_client,
+            DEFAULT_TRANSLATE_URL,
             "express-tracking",
             "en",
             "sv",
@@ -769,8 +856,9 @@ mod tests {
         let api_response = api_response.unwrap();
         println!("api_response {:#?}", api_response);
 
-        let api_response2: Result<GoogleCreateGlossaryWaitApiResponse> =
-            task::block_on(create_glossary_check_status(&token, &api_response.name));
+        let api_response2: Result<GoogleCreateGlossaryWaitApiResponse> = task::block_on(
+            create_glossary_check_status(&http_client, DEFAULT_TRANSLATE_URL, &api_response.name),
+        );
 
         println!("api_response2 {:#?}", api_response2);
 
@@ -782,13 +870,19 @@ mod tests {
     #[ignore]
     fn test_delete_glossary() -> Result<()> {
         init_logging();
-        let token: Result<GoogleApisOauthToken> =
-            task::block_on(get_google_api_token("./examples/testdata/credentials.json"));
-        let token = format!("Bearer {}", token.unwrap().access_token);
-
-        println!("access_token {:#?}", token);
-        let api_response: Result<GoogleTranslateV3Response> =
-            task::block_on(delete_glossary(&token, "express-tracking", "my-glossary"));
+        let http_client = HttpClient::new(
+            TokenProvider::new(
+                CredentialSource::from_file("./examples/testdata/credentials.json")?,
+                None,
+            ),
+            RetryPolicy::default(),
+        );
+        let api_response: Result<GoogleTranslateV3Response> = task::block_on(delete_glossary(
+            &http_client,
+            DEFAULT_TRANSLATE_URL,
+            "express-tracking",
+            "my-glossary",
+        ));
 
         let api_response = api_response.unwrap();
         println!("api_response {:#?}", api_response);
@@ -896,6 +990,37 @@ mod tests {
         });
     }
 
+    // cargo test -- --show-output test_translation_models
+    #[test]
+    fn test_translation_models() -> Result<()> {
+        let models = TranslationModels::parse(&[
+            "general/nmt",
+            "de=TRL1234567890",
+            "FR=projects/other-project/locations/us-central1/models/TRL42",
+        ])?;
+        assert_eq!(
+            models.model_for("my-project", "de"),
+            Some("projects/my-project/locations/us-central1/models/TRL1234567890".to_owned())
+        );
+        assert_eq!(
+            models.model_for("my-project", "fr"),
+            Some("projects/other-project/locations/us-central1/models/TRL42".to_owned())
+        );
+        assert_eq!(
+            models.model_for("my-project", "es"),
+            Some("projects/my-project/locations/us-central1/models/general/nmt".to_owned())
+        );
+
+        let models = TranslationModels::parse(&["de=general/base"])?;
+        assert_eq!(models.model_for("my-project", "es"), None);
+        assert_eq!(TranslationModels::parse(&[])?.model_for("p", "de"), None);
+
+        assert!(TranslationModels::parse(&["de="]).is_err());
+        assert!(TranslationModels::parse(&["=general/nmt"]).is_err());
+        assert!(TranslationModels::parse(&["general/nmt", "general/base"]).is_err());
+        Ok(())
+    }
+
     // cargo test -- --show-output test_create_sample_tsv_file
     #[test]
     #[ignore]
@@ -1,4 +1,6 @@
+use crate::ssml::is_ssml;
 use lazy_static::lazy_static;
+use regex::Regex;
 
 const HTML_TAG_NAMES: [&str; 118] = [
     "a",
@@ -122,6 +124,9 @@ const HTML_TAG_NAMES: [&str; 118] = [
 ];
 
 lazy_static! {
+    /// character or entity reference, e.g. &amp;, &#39; or &#x27;
+    static ref RE_ENTITY: Regex = Regex::new(r"^&(?:[A-Za-z][A-Za-z0-9]*|#[0-9]+|#[xX][0-9A-Fa-f]+);").unwrap();
+
     pub static ref HTML_TAGS: Vec<String> = {
         let mut tags = vec![];
         for tag in HTML_TAG_NAMES.iter() {
@@ -138,7 +143,12 @@ lazy_static! {
     };
 }
 
+/// true if text contains HTML tags (or it is SSML), i.e. it must be translated in HTML format
 pub fn is_html(str_val: &str) -> bool {
+    if is_ssml(str_val) {
+        return true;
+    }
+
     for tag in HTML_TAGS.iter() {
         if str_val.to_lowercase().contains(tag) {
             return true;
@@ -148,6 +158,42 @@ pub fn is_html(str_val: &str) -> bool {
     false
 }
 
+/// decodes entities which Google Translate API uses in texts translated as HTML (e.g. &#39; for apostrophe).
+/// Intended for plain texts which were translated as HTML.
+pub fn decode_entities(str_val: &str) -> String {
+    str_val
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// true if text starts with character or entity reference, i.e. its & is not bare ampersand
+pub fn starts_with_entity(str_val: &str) -> bool {
+    RE_ENTITY.is_match(str_val)
+}
+
+/// escapes bare ampersands and less-than signs, entities and tags are kept as they are (tags added
+/// by translator are to be detected by the caller). Intended for texts decoded by
+/// [decode_entities](fn.decode_entities.html) which go back into markup.
+pub fn escape_text(str_val: &str) -> String {
+    let mut escaped = String::with_capacity(str_val.len());
+    for (idx, c) in str_val.char_indices() {
+        match c {
+            '&' if !starts_with_entity(&str_val[idx..]) => escaped.push_str("&amp;"),
+            '<' if !str_val[idx + 1..].starts_with(|next: char| {
+                next.is_ascii_alphabetic() || next == '/' || next == '!' || next == '?'
+            }) =>
+            {
+                escaped.push_str("&lt;")
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -159,5 +205,27 @@ mod tests {
         assert_eq!(is_html("this is html <br/>"), true);
         assert_eq!(is_html("this is html <br>"), true);
         assert_eq!(is_html("and  this is not <brrr/>!!"), false);
+        assert!(is_html(r#"<speak>Hi<break time="1s"/></speak>"#));
+    }
+
+    // cargo test -- --show-output test_decode_entities
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("Rock &amp; Roll, it&#39;s &quot;$name&quot; &amp;lt;"),
+            "Rock & Roll, it's \"$name\" &lt;"
+        );
+    }
+
+    // cargo test -- --show-output test_escape_text
+    #[test]
+    fn test_escape_text() {
+        assert!(starts_with_entity("&amp; B"));
+        assert!(starts_with_entity("&#39;s"));
+        assert!(!starts_with_entity("& B"));
+        assert_eq!(
+            escape_text("A & B &amp; 1 < 2 &#x27; <b>"),
+            "A &amp; B &amp; 1 &lt; 2 &#x27; <b>"
+        );
     }
 }
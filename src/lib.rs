@@ -10,12 +10,23 @@ pub mod errors;
 pub mod google;
 pub mod html;
 pub mod macros;
+pub mod placeholders;
+pub mod ssml;
+pub mod summary;
 pub mod ui;
 pub mod zip;
 
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
 /// Utility function to enable log::debug logging in unit tests
 pub fn init_logging() {
     // enable in unit/integration tests selectivelly only when needed!
     // set RUST_LOG=gdf_translate::google::gcloud::storage_bucket_mgmt=debug
     let _ = env_logger::builder().is_test(true).try_init();
 }
+
+/// random number without pulling in extra crate, RandomState is seeded randomly for every instance
+pub(crate) fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
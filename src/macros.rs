@@ -82,7 +82,11 @@ where
 #[macro_export]
 macro_rules! parse_gdf_agent_files {
     ($name:ident, $type_deserialized:ty, $type_output:ty) => {
-        fn $name(glob_exp: &PathBuf) -> Result<Vec<$type_output>> {
+        fn $name(
+            glob_exp: &PathBuf,
+            strict: bool,
+            report: &mut Vec<ReportItem>,
+        ) -> Result<Vec<$type_output>> {
             let mut output_vec: Vec<$type_output> = vec![];
             let glob_str = glob_exp.as_path().to_str().unwrap();
             debug!(
@@ -111,20 +115,16 @@ macro_rules! parse_gdf_agent_files {
                 let file_str = fs::read_to_string(file_name)?;
                 let deserialized_struct: $type_deserialized = serde_json::from_str(&file_str)?;
 
-                let serialized_str = serde_json::to_string(&deserialized_struct).unwrap();
-                let comparison_result = assert_json_eq_no_panic(
-                    &serde_json::from_str(&serialized_str)?,
-                    &serde_json::from_str(&file_str)?,
-                );
-
-                if let Err(err_msg) = comparison_result {
-                    debug!(
-                        "parse_gdf_agent_files: assert_json_eq_no_panic did not pass {}",
-                        file_name
-                    );
-                    debug!("serialized_str {}", serialized_str);
-                    debug!("deserialized_struct {:#?}", deserialized_struct);
-                    return Err(Error::new(err_msg));
+                match unknown_fields(&deserialized_struct, &file_str, strict) {
+                    Ok(fields) => report_unknown_fields(report, file_name, fields),
+                    Err(err) => {
+                        debug!(
+                            "parse_gdf_agent_files: unknown fields check failed for {}: {:?}",
+                            file_name, err
+                        );
+                        debug!("deserialized_struct {:#?}", deserialized_struct);
+                        return Err(err);
+                    }
                 }
                 debug!("parse_gdf_agent_files: processed file {}", file_name);
                 output_vec.push(<$type_output>::new(
@@ -1,45 +1,188 @@
 use async_std::task;
 use gdf_translate::cli::{get_cmd_line_parser, get_cmdl_options};
 use gdf_translate::errors::Result;
+use gdf_translate::google::dialogflow::api;
+use gdf_translate::google::dialogflow::cx::is_cx_agent_zip;
+use gdf_translate::google::dialogflow::limits::Abbreviations;
+use gdf_translate::google::dialogflow::media::MediaUrls;
+use gdf_translate::google::dialogflow::options::EntityValues;
+use gdf_translate::google::dialogflow::payload::PayloadRules;
+use gdf_translate::google::dialogflow::url_rewrites::UrlRewrites;
 use gdf_translate::google::gcloud::auth::*;
+use gdf_translate::google::gcloud::http::{HttpClient, RetryPolicy, DEFAULT_MAX_BACKOFF_MS};
+use gdf_translate::google::gcloud::rate_limit::RateLimits;
+use gdf_translate::google::gcloud::translate::v3::TranslationModels;
 use gdf_translate::google::gcloud::translate::{
-    GoogleTranslateV2, GoogleTranslateV3, TranslationProviders,
+    GoogleTranslateV2, GoogleTranslateV3, TranslationJob, TranslationProviders, V2Options,
+    V3Options,
 };
+use gdf_translate::google::gcloud::GoogleApiConfig;
+use gdf_translate::summary::RunSummary;
 use gdf_translate::ui::{ProgressMessageType, UserInterface};
+use std::io::{self, Write};
 use std::process;
 use std::sync::mpsc::channel;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // cargo run -- --agent-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/sample_agents/Currency-Converter.zip --output-folder c:/tmp/out --source-lang en --target-lang de --cred-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/testdata/credentials.json
 // cargo run -- --agent-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/sample_agents/Currency-Converter.zip --output-folder c:/tmp/out --source-lang en --target-lang de --cred-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/testdata/credentials.json --api-version v2
 // cargo run -- --agent-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/sample_agents/Currency-Converter.zip --output-folder c:/tmp/out --source-lang en --target-lang de --cred-file C:/Users/abezecny/adam/WORK/_DEV/Rust/gdf_translate/examples/testdata/credentials.json --api-version v3 --create-output-tsv
+/// name of the file in output folder where agent exported by --from-project is stored
+const EXPORTED_AGENT_FILE: &str = "exported_agent.zip";
+
+/// name of the file in output folder where agent is backed up before --deploy
+const BACKUP_AGENT_FILE: &str = "backup_agent.zip";
+
 fn main() {
     env_logger::init();
     let cmd_line_matches = get_cmd_line_parser().get_matches();
     let cmd_line_opts = get_cmdl_options(&cmd_line_matches);
     // println!("cmd_line_opts: {:#?}", cmd_line_opts);
 
-    let token: Result<GoogleApisOauthToken> = task::block_on(get_google_api_token(
-        cmd_line_opts.gcloud_svc_acc_cred.to_str().unwrap(), // TBD: do not unwrap and provide proper err msg in case if None value!
-    ));
-    let token = format!("Bearer {}", token.unwrap().access_token);
+    let credentials = CredentialSource::resolve(cmd_line_opts.gcloud_svc_acc_cred);
 
-    let gdf_credentials = task::block_on(file_to_gdf_credentials(
-        cmd_line_opts.gcloud_svc_acc_cred.to_str().unwrap(),
-    ));
-
-    if let Err(some_error) = gdf_credentials {
+    if let Err(some_error) = credentials {
         println!(
-            "unable to parse credentials file due to following error: {:#?}",
+            "unable to load credentials due to following error: {:#?}",
             some_error
         );
         process::exit(1);
     }
-    let gdf_credentials = gdf_credentials.unwrap();
-    /* println!(
-        "gdf_credentials.project_id: {:#?}",
-        gdf_credentials.project_id
-    ); */
+    let credentials = credentials.unwrap();
+
+    let project_id = match (&cmd_line_opts.project_id, credentials.project_id()) {
+        (Some(project_id), _) => project_id.to_owned(),
+        (None, Some(project_id)) => project_id.to_owned(),
+        (None, None) => "".to_owned(),
+    };
+
+    if project_id.is_empty() {
+        if let TranslationProviders::GoogleTranslateV3 = cmd_line_opts.translation_mode {
+            println!("unable to determine google cloud project, use --project-id");
+            process::exit(1);
+        }
+    }
+
+    let models = match TranslationModels::parse(&cmd_line_opts.models) {
+        Ok(models) => models,
+        Err(err) => {
+            println!("invalid --model option: {:#?}", err);
+            process::exit(1);
+        }
+    };
+
+    let mut agent_options = cmd_line_opts.agent_options.clone();
+    agent_options.entity_values = match EntityValues::parse(&cmd_line_opts.entity_values) {
+        Ok(entity_values) => entity_values,
+        Err(err) => {
+            println!("invalid --entity-values option: {:#?}", err);
+            process::exit(1);
+        }
+    };
+    agent_options.payload_rules = match PayloadRules::parse(
+        &cmd_line_opts.payload_rules,
+        &cmd_line_opts.payload_excluded_keys,
+    ) {
+        Ok(payload_rules) => payload_rules,
+        Err(err) => {
+            println!("invalid --payload-text option: {:#?}", err);
+            process::exit(1);
+        }
+    };
+    if let Some(media_urls_path) = cmd_line_opts.media_urls_path {
+        agent_options.media_urls = match MediaUrls::from_file(media_urls_path) {
+            Ok(media_urls) => Some(media_urls),
+            Err(err) => {
+                println!("invalid --media-urls option: {:#?}", err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(url_rewrites_path) = cmd_line_opts.url_rewrites_path {
+        agent_options.url_rewrites = match UrlRewrites::from_file(url_rewrites_path) {
+            Ok(url_rewrites) => Some(url_rewrites),
+            Err(err) => {
+                println!("invalid --url-rewrites option: {:#?}", err);
+                process::exit(1);
+            }
+        };
+    }
+    if let Some(abbreviations_path) = cmd_line_opts.abbreviations_path {
+        agent_options.abbreviations = match Abbreviations::from_file(abbreviations_path) {
+            Ok(abbreviations) => Some(abbreviations),
+            Err(err) => {
+                println!("invalid --abbreviations option: {:#?}", err);
+                process::exit(1);
+            }
+        };
+    }
+
+    let api_config = GoogleApiConfig::new(
+        cmd_line_opts.storage_url.as_deref(),
+        cmd_line_opts.translate_url.as_deref(),
+        cmd_line_opts.dialogflow_url.as_deref(),
+        cmd_line_opts
+            .bucket_creation_delay
+            .map(Duration::from_millis),
+        cmd_line_opts.operation_timeout.map(Duration::from_millis),
+    );
+
+    // single token provider shared by all API calls, token is refreshed automatically before it expires
+    let token_provider = TokenProvider::new(credentials, cmd_line_opts.token_url.as_deref());
+
+    // all google API calls go through this client applying same retry policy
+    let http_client = HttpClient::new(
+        token_provider,
+        RetryPolicy::new(
+            cmd_line_opts.retry_attempts,
+            Duration::from_millis(cmd_line_opts.retry_backoff),
+            Duration::from_millis(DEFAULT_MAX_BACKOFF_MS),
+        ),
+    );
+
+    if cmd_line_opts.deploy_mode.is_some() && cmd_line_opts.deploy_project.is_none() {
+        println!("unable to determine project to deploy translated agent to, use --deploy-project");
+        process::exit(1);
+    }
+
+    let gdf_agent_path = match (
+        &cmd_line_opts.from_project,
+        cmd_line_opts.gdf_agent_zip_path,
+    ) {
+        (Some(from_project), _) => {
+            println!("Exporting agent from project {}...", from_project);
+            let exported_agent_path = cmd_line_opts.output_folder.join(EXPORTED_AGENT_FILE);
+            let export_result = task::block_on(api::export_agent_zip(
+                &http_client,
+                &api_config,
+                from_project,
+                cmd_line_opts.export_uri.as_deref(),
+                &exported_agent_path,
+            ));
+            if let Err(err) = export_result {
+                println!("Agent export ended with following error: {:#?}", err);
+                process::exit(1);
+            }
+            exported_agent_path.to_str().unwrap().to_owned()
+        }
+        (None, Some(gdf_agent_zip_path)) => gdf_agent_zip_path.to_str().unwrap().to_owned(),
+        (None, None) => unreachable!(), // enforced by clap
+    };
+
+    // deployment uses ES (v2) agent API, CX agent must be restored via CX API or console
+    if cmd_line_opts.deploy_mode.is_some() {
+        match is_cx_agent_zip(&gdf_agent_path) {
+            Ok(false) => {}
+            Ok(true) => {
+                println!("--deploy is not supported for Dialogflow CX agents, restore translated agent in CX console");
+                process::exit(1);
+            }
+            Err(err) => {
+                println!("Unable to read agent {}: {:#?}", gdf_agent_path, err);
+                process::exit(1);
+            }
+        }
+    }
 
     let (tx, rx) = channel::<ProgressMessageType>();
     let mut ui;
@@ -64,53 +207,119 @@ fn main() {
         glossary_path = None;
     }
 
-    match cmd_line_opts.translation_mode {
+    // to_lang must be lower case! pt-BR in intent response instead of pt-br will cause message being not displayed in Dialogflow UI!
+    let to_lang = cmd_line_opts.to_lang.to_lowercase();
+    let job = TranslationJob {
+        gdf_agent_path: &gdf_agent_path,
+        translated_gdf_agent_folder: cmd_line_opts.output_folder.to_str().unwrap(),
+        source_lang: &cmd_line_opts.from_lang,
+        target_lang: &to_lang,
+        skip_entities_translation: cmd_line_opts.skip_entities_translation,
+        skip_utterances_translation: cmd_line_opts.skip_utterances_translation,
+        skip_responses_translation: cmd_line_opts.skip_responses_translation,
+        agent_options: &agent_options,
+    };
+
+    let result = match cmd_line_opts.translation_mode {
         TranslationProviders::GoogleTranslateV2 => {
             println!("Starting V2 translation...");
             let start = Instant::now();
             let result = GoogleTranslateV2::execute_translation(
-                cmd_line_opts.gdf_agent_zip_path.to_str().unwrap(),
-                cmd_line_opts.output_folder.to_str().unwrap(),
-                &token,
-                &cmd_line_opts.from_lang,
-                // to_lang must be lower case! pt-BR in intent response instead of pt-br will cause message being not displayed in Dialogflow UI!
-                &cmd_line_opts.to_lang.to_lowercase(),
+                &job,
+                &http_client,
+                &api_config,
+                &V2Options {
+                    task_count: cmd_line_opts.v2_task_count,
+                    rate_limits: &RateLimits::new(
+                        cmd_line_opts.v2_chars_per_minute,
+                        cmd_line_opts.v2_requests_per_minute,
+                    ),
+                },
                 tx,
-                cmd_line_opts.v2_task_count,
-                cmd_line_opts.skip_entities_translation,
-                cmd_line_opts.skip_utterances_translation,
-                cmd_line_opts.skip_responses_translation,
             );
             let duration = start.elapsed();
-            match result {
-                Err(err) => println!("Translation ended with following error: {:#?}", err),
-                _ => println!("Translation done! Total duration: {:?}", duration),
-            }
+            print_result(&result, duration);
+            result
         }
         TranslationProviders::GoogleTranslateV3 => {
             println!("Starting V3 translation...");
             let start = Instant::now();
             let result = GoogleTranslateV3::execute_translation(
-                cmd_line_opts.gdf_agent_zip_path.to_str().unwrap(),
-                cmd_line_opts.output_folder.to_str().unwrap(),
-                &token,
-                &cmd_line_opts.from_lang,
-                // to_lang must be lower case! pt-BR in intent response instead of pt-br will cause message being not displayed in Dialogflow UI!
-                &cmd_line_opts.to_lang.to_lowercase(),
-                &gdf_credentials.project_id,
+                &job,
+                &http_client,
+                &api_config,
+                &V3Options {
+                    project_id: &project_id,
+                    create_output_tsv: cmd_line_opts.create_output_tsv,
+                    glossary_path,
+                    models: &models,
+                },
                 tx,
-                cmd_line_opts.create_output_tsv,
-                cmd_line_opts.skip_entities_translation,
-                cmd_line_opts.skip_utterances_translation,
-                cmd_line_opts.skip_responses_translation,
-                glossary_path,
             );
             let duration = start.elapsed();
-            match result {
-                Err(err) => println!("Translation ended with following error: {:#?}", err),
-                _ => println!("Translation done! Total duration: {:?}", duration),
-            }
+            print_result(&result, duration);
+            result
         }
         _ => unreachable!(),
+    };
+
+    // failure must be detectable by scripts, agent is not deployed then
+    if result.is_err() {
+        process::exit(1);
+    }
+
+    if let (Some(deploy_mode), Some(deploy_project)) =
+        (cmd_line_opts.deploy_mode, &cmd_line_opts.deploy_project)
+    {
+        println!(
+            "Deploying translated agent to project {}...",
+            deploy_project
+        );
+        let deploy_result = task::block_on(api::deploy_agent(
+            &http_client,
+            &api_config,
+            &api::AgentDeployment {
+                project_id: deploy_project,
+                mode: deploy_mode,
+                agent_zip_path: &cmd_line_opts
+                    .output_folder
+                    .join("_packed")
+                    .join("TranslatedAgent.zip"),
+                backup_zip_path: &cmd_line_opts.output_folder.join(BACKUP_AGENT_FILE),
+                train: cmd_line_opts.train_agent,
+            },
+            &|question| cmd_line_opts.assume_yes || confirm(question),
+        ));
+        match deploy_result {
+            Err(err) => {
+                println!("Deployment ended with following error: {:#?}", err);
+                process::exit(1);
+            }
+            Ok(Some(backup)) => println!("Deployment done! Backup of previous agent: {:?}", backup),
+            Ok(None) => println!("Deployment done!"),
+        }
+    }
+}
+
+/// asks user on command line, only explicit yes is accepted
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = io::stdout().flush();
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+fn print_result(result: &Result<RunSummary>, duration: Duration) {
+    match result {
+        Err(err) => println!("Translation ended with following error: {:#?}", err),
+        Ok(run_summary) => {
+            println!("Translation done! Total duration: {:?}", duration);
+            if let Ok(summary_str) = serde_json::to_string_pretty(&run_summary) {
+                println!("Run summary:\n{}", summary_str);
+            }
+        }
     }
 }
@@ -0,0 +1,489 @@
+//! # Placeholders protecting tokens which must not be translated
+//!
+//! Tokens (e.g. entity references in composite entities like `@sys.number:amount`) are replaced
+//! with numbered placeholders `<span translate="no">0</span>` before the text is sent for translation.
+//! Google Translate API does not translate content of elements marked with `translate="no"`.
+//! Once translated, placeholders are replaced back with original tokens. Translation which lost
+//! or duplicated some placeholder is rejected since the tokens can't be re-inserted reliably.
+//!
+//! References of parameters and contexts used in responses and prompts (`$param`, `$param.original`,
+//! `#context.param`, `@sys.date`, `${...}`) are protected the same way for all translation providers,
+//! see [protect_references](fn.protect_references.html) and [restore_references](fn.restore_references.html).
+use crate::html;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections;
+use std::fmt;
+
+lazy_static! {
+    /// reference of entity used in composite entities, e.g. @sys.number:amount, @currency:cur or @city
+    pub static ref RE_ENTITY_REFERENCE: Regex =
+        Regex::new(r"@[\w-]+(?:\.[\w-]+)*(?::[\w-]+)?").unwrap();
+    /// reference of parameter, context or system entity used in responses and prompts,
+    /// e.g. $param, $param.original, $session.params.name, #context.param, @sys.date or ${expression}
+    pub static ref RE_REFERENCE: Regex = Regex::new(
+        r"\$\{[^}]*\}|\$[\w-]+(?:\.[\w-]+)*|#[\w-]+(?:\.[\w-]+)+|@sys(?:\.[\w-]+)+(?::[\w-]+)?"
+    )
+    .unwrap();
+    static ref RE_PLACEHOLDER: Regex =
+        Regex::new(r#"<span translate=["']?no["']?>\s*(\d+)\s*</span>"#).unwrap();
+}
+
+#[derive(Debug, PartialEq)]
+pub enum PlaceholderIssue {
+    /// token is not present in translated text
+    Missing(String),
+    /// token is present more than once in translated text
+    Duplicated(String),
+    /// token is followed or preceded by translated text so that it is not recognized as token anymore,
+    /// e.g. @sys.time:time followed by Uhr without space
+    Merged(String),
+    /// tokens are present but in different order than in original text, e.g. $first and $last swapped
+    Reordered(String),
+}
+
+impl fmt::Display for PlaceholderIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlaceholderIssue::Missing(token) => write!(f, "{} is missing", token),
+            PlaceholderIssue::Duplicated(token) => write!(f, "{} is duplicated", token),
+            PlaceholderIssue::Merged(token) => write!(f, "{} is merged with adjacent text", token),
+            PlaceholderIssue::Reordered(token) => write!(f, "{} is reordered", token),
+        }
+    }
+}
+
+fn placeholder(idx: usize) -> String {
+    format!(r#"<span translate="no">{}</span>"#, idx)
+}
+
+/// replaces all tokens matching token regex with placeholders.
+/// Returns text with placeholders and replaced tokens (index of token = number of placeholder).
+pub fn protect(text: &str, token_regex: &Regex) -> (String, Vec<String>) {
+    let mut tokens = vec![];
+    let protected = token_regex
+        .replace_all(text, |caps: &regex::Captures| {
+            tokens.push(caps[0].to_owned());
+            placeholder(tokens.len() - 1)
+        })
+        .to_string();
+    (protected, tokens)
+}
+
+/// true if text contains something to translate apart from placeholders,
+/// e.g. "@sys.number:amount @currency:cur" has nothing to translate
+pub fn has_text(protected_text: &str) -> bool {
+    RE_PLACEHOLDER
+        .replace_all(protected_text, "")
+        .chars()
+        .any(char::is_alphabetic)
+}
+
+/// replaces placeholders in translated text with original tokens.
+/// Every token must be present exactly once, otherwise list of issues is returned.
+pub fn restore(
+    translated_text: &str,
+    tokens: &[String],
+) -> std::result::Result<String, Vec<PlaceholderIssue>> {
+    let mut counts: collections::HashMap<usize, usize> = collections::HashMap::new();
+    for caps in RE_PLACEHOLDER.captures_iter(translated_text) {
+        if let Ok(idx) = caps[1].parse::<usize>() {
+            *counts.entry(idx).or_insert(0) += 1;
+        }
+    }
+
+    let mut issues = vec![];
+    for (idx, token) in tokens.iter().enumerate() {
+        match counts.get(&idx) {
+            None => issues.push(PlaceholderIssue::Missing(token.to_owned())),
+            Some(count) if *count > 1 => {
+                issues.push(PlaceholderIssue::Duplicated(token.to_owned()))
+            }
+            _ => {}
+        }
+    }
+    if !issues.is_empty() {
+        return Err(issues);
+    }
+
+    Ok(RE_PLACEHOLDER
+        .replace_all(translated_text, |caps: &regex::Captures| {
+            caps[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| tokens.get(idx))
+                .map(|token| token.to_owned())
+                .unwrap_or_default()
+        })
+        .to_string())
+}
+
+/// escapes bare ampersands and less-than signs outside placeholders (see [escape_text](../html/fn.escape_text.html)),
+/// i.e. translated text decoded from HTML can go back into markup (e.g. SSML)
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    let mut last_end = 0;
+    for mat in RE_PLACEHOLDER.find_iter(text) {
+        escaped.push_str(&html::escape_text(&text[last_end..mat.start()]));
+        escaped.push_str(mat.as_str());
+        last_end = mat.end();
+    }
+    escaped.push_str(&html::escape_text(&text[last_end..]));
+    escaped
+}
+
+/// true if text contains some placeholder, i.e. it must be translated in HTML format
+pub fn has_placeholders(text: &str) -> bool {
+    RE_PLACEHOLDER.is_match(text)
+}
+
+/// text with tokens replaced by placeholders, None if there is nothing to translate apart from tokens
+pub fn protect_for_translation(text: &str, token_regex: &Regex) -> Option<String> {
+    let (protected, _) = protect(text, token_regex);
+    if has_text(&protected) {
+        Some(protected)
+    } else {
+        None
+    }
+}
+
+/// applies translation of text protected by [protect_for_translation](fn.protect_for_translation.html).
+/// Tokens are taken from original (not yet translated) text. If tokens can't be re-inserted
+/// (or re-inserted tokens would not be recognized as tokens anymore) text is left untranslated
+/// and issues are returned.
+pub fn protected_from_translation(
+    text: &mut String,
+    token_regex: &Regex,
+    translations_map: &collections::HashMap<String, String>,
+) -> Vec<PlaceholderIssue> {
+    if let Some(translated) = translations_map.get(&format!("{:p}", text)) {
+        let (_, tokens) = protect(text, token_regex);
+        match restore(translated, &tokens) {
+            Ok(restored) => {
+                let (_, restored_tokens) = protect(&restored, token_regex);
+                let merged: Vec<PlaceholderIssue> = tokens
+                    .iter()
+                    .filter(|token| !restored_tokens.contains(token))
+                    .map(|token| PlaceholderIssue::Merged(token.to_owned()))
+                    .collect();
+                if !merged.is_empty() {
+                    return merged;
+                }
+                *text = restored;
+            }
+            Err(issues) => return issues,
+        }
+    }
+    vec![]
+}
+
+/// references of single text replaced by placeholders before translation
+#[derive(Debug)]
+pub struct References {
+    /// original (not protected) text
+    pub source: String,
+    /// number of first placeholder, placeholders already present in the text
+    /// (e.g. entity references of template utterances) are numbered from zero and left untouched
+    offset: usize,
+    tokens: Vec<String>,
+}
+
+impl References {
+    /// restores references in translated text. Returns text with references and issues which
+    /// do not prevent using the translation (reordered references). Lost, duplicated and merged
+    /// references make the translation unusable, these are returned as error.
+    fn restore(
+        &self,
+        translated_text: &str,
+    ) -> std::result::Result<(String, Vec<PlaceholderIssue>), Vec<PlaceholderIssue>> {
+        let own_idx = |caps: &regex::Captures| {
+            caps[1]
+                .parse::<usize>()
+                .ok()
+                .filter(|idx| *idx >= self.offset && *idx < self.offset + self.tokens.len())
+                .map(|idx| idx - self.offset)
+        };
+
+        let found: Vec<usize> = RE_PLACEHOLDER
+            .captures_iter(translated_text)
+            .filter_map(|caps| own_idx(&caps))
+            .collect();
+
+        let mut issues = vec![];
+        for (idx, token) in self.tokens.iter().enumerate() {
+            match found.iter().filter(|found_idx| **found_idx == idx).count() {
+                0 => issues.push(PlaceholderIssue::Missing(token.to_owned())),
+                1 => {}
+                _ => issues.push(PlaceholderIssue::Duplicated(token.to_owned())),
+            }
+        }
+        if !issues.is_empty() {
+            return Err(issues);
+        }
+
+        let restored = RE_PLACEHOLDER
+            .replace_all(translated_text, |caps: &regex::Captures| {
+                match own_idx(caps) {
+                    Some(idx) => self.tokens[idx].to_owned(),
+                    None => caps[0].to_owned(),
+                }
+            })
+            .to_string();
+
+        let restored_tokens: Vec<&str> = RE_REFERENCE
+            .find_iter(&restored)
+            .map(|mat| mat.as_str())
+            .collect();
+        let merged: Vec<PlaceholderIssue> = self
+            .tokens
+            .iter()
+            .filter(|token| !restored_tokens.contains(&token.as_str()))
+            .map(|token| PlaceholderIssue::Merged(token.to_owned()))
+            .collect();
+        if !merged.is_empty() {
+            return Err(merged);
+        }
+
+        // swapping of identical references (e.g. $name ... $name) is not reordering
+        let reordered = found
+            .iter()
+            .zip(self.tokens.iter())
+            .find(|(found_idx, token)| self.tokens[**found_idx] != **token)
+            .map(|(_, token)| PlaceholderIssue::Reordered(token.to_owned()));
+        Ok((restored, reordered.into_iter().collect()))
+    }
+}
+
+/// replaces references (see [RE_REFERENCE](struct.RE_REFERENCE.html)) in all texts of translation map
+/// with placeholders. Returns replaced references of every protected text (keyed same as translation map).
+pub fn protect_references(
+    translation_map: &mut collections::HashMap<String, String>,
+) -> collections::HashMap<String, References> {
+    let mut references = collections::HashMap::new();
+    for (key, text) in translation_map.iter_mut() {
+        if !RE_REFERENCE.is_match(text) {
+            continue;
+        }
+
+        let offset = RE_PLACEHOLDER
+            .captures_iter(text)
+            .filter_map(|caps| caps[1].parse::<usize>().ok())
+            .map(|idx| idx + 1)
+            .max()
+            .unwrap_or(0);
+        let mut tokens = vec![];
+        let protected = RE_REFERENCE
+            .replace_all(text, |caps: &regex::Captures| {
+                tokens.push(caps[0].to_owned());
+                placeholder(offset + tokens.len() - 1)
+            })
+            .to_string();
+        let source = std::mem::replace(text, protected);
+        references.insert(
+            key.to_owned(),
+            References {
+                source,
+                offset,
+                tokens,
+            },
+        );
+    }
+    references
+}
+
+/// replaces placeholders created by [protect_references](fn.protect_references.html) in translated texts
+/// with original references. Texts where some reference was lost, duplicated or merged with adjacent text
+/// are replaced with their source text, i.e. they are left untranslated. Returns issues found together with
+/// protected references of respective text, [Reordered](enum.PlaceholderIssue.html#variant.Reordered)
+/// is the only issue of texts which were translated anyway.
+pub fn restore_references<'a>(
+    translation_map: &mut collections::HashMap<String, String>,
+    references: &'a collections::HashMap<String, References>,
+) -> Vec<(&'a References, PlaceholderIssue)> {
+    let mut issues = vec![];
+    for (key, text_references) in references.iter() {
+        let translated = match translation_map.get(key) {
+            Some(translated) => translated,
+            None => continue,
+        };
+
+        match text_references.restore(translated) {
+            Ok((restored, text_issues)) => {
+                translation_map.insert(key.to_owned(), restored);
+                issues.extend(
+                    text_issues
+                        .into_iter()
+                        .map(|issue| (text_references, issue)),
+                );
+            }
+            Err(text_issues) => {
+                translation_map.insert(key.to_owned(), text_references.source.to_owned());
+                issues.extend(
+                    text_issues
+                        .into_iter()
+                        .map(|issue| (text_references, issue)),
+                );
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cargo test -- --show-output test_placeholders
+    #[test]
+    fn test_placeholders() {
+        let (protected, tokens) = protect(
+            "@sys.number:amount @currency:cur per month",
+            &RE_ENTITY_REFERENCE,
+        );
+        assert_eq!(
+            protected,
+            r#"<span translate="no">0</span> <span translate="no">1</span> per month"#
+        );
+        assert_eq!(tokens, vec!["@sys.number:amount", "@currency:cur"]);
+        assert!(has_text(&protected));
+        assert!(!has_text(
+            &protect("@sys.number:amount @city.", &RE_ENTITY_REFERENCE).0
+        ));
+
+        // translator can reorder placeholders
+        assert_eq!(
+            restore(
+                r#"<span translate="no">1</span> <span translate="no">0</span> pro Monat"#,
+                &tokens
+            ),
+            Ok("@currency:cur @sys.number:amount pro Monat".to_owned())
+        );
+        assert_eq!(
+            restore(
+                r#"<span translate="no">0</span> <span translate="no">0</span> pro Monat"#,
+                &tokens
+            ),
+            Err(vec![
+                PlaceholderIssue::Duplicated("@sys.number:amount".to_owned()),
+                PlaceholderIssue::Missing("@currency:cur".to_owned())
+            ])
+        );
+
+        // token followed by translated text without space would become different token
+        let mut text = "@sys.date:date at @sys.time:time".to_owned();
+        let mut translations_map = collections::HashMap::new();
+        translations_map.insert(
+            format!("{:p}", &text),
+            r#"<span translate="no">0</span> um <span translate="no">1</span>Uhr"#.to_owned(),
+        );
+        assert_eq!(
+            protected_from_translation(&mut text, &RE_ENTITY_REFERENCE, &translations_map),
+            vec![PlaceholderIssue::Merged("@sys.time:time".to_owned())]
+        );
+        assert_eq!(text, "@sys.date:date at @sys.time:time");
+
+        translations_map.insert(
+            format!("{:p}", &text),
+            r#"<span translate="no">0</span> um <span translate="no">1</span> Uhr"#.to_owned(),
+        );
+        assert!(
+            protected_from_translation(&mut text, &RE_ENTITY_REFERENCE, &translations_map)
+                .is_empty()
+        );
+        assert_eq!(text, "@sys.date:date um @sys.time:time Uhr");
+    }
+
+    // cargo test -- --show-output test_protect_references
+    #[test]
+    fn test_protect_references() {
+        let mut translation_map = collections::HashMap::new();
+        translation_map.insert(
+            "addr1".to_owned(),
+            "Hi $name.original, your order #order.id is ready".to_owned(),
+        );
+        translation_map.insert(
+            "addr2".to_owned(),
+            "Total:${amount * 2}USD on @sys.date, see $session.params.link".to_owned(),
+        );
+        translation_map.insert(
+            "addr3".to_owned(),
+            r#"<span translate="no">0</span> in $city"#.to_owned(),
+        );
+        translation_map.insert("addr4".to_owned(), "$first then $last".to_owned());
+        translation_map.insert("addr5".to_owned(), "No references # here".to_owned());
+
+        let references = protect_references(&mut translation_map);
+        assert_eq!(references.len(), 4);
+        assert_eq!(
+            translation_map["addr1"],
+            r#"Hi <span translate="no">0</span>, your order <span translate="no">1</span> is ready"#
+        );
+        assert_eq!(
+            translation_map["addr2"],
+            r#"Total:<span translate="no">0</span>USD on <span translate="no">1</span>, see <span translate="no">2</span>"#
+        );
+        // placeholders already present are left for the agent
+        assert_eq!(
+            translation_map["addr3"],
+            r#"<span translate="no">0</span> in <span translate="no">1</span>"#
+        );
+        assert_eq!(translation_map["addr5"], "No references # here");
+
+        translation_map.insert(
+            "addr1".to_owned(),
+            r#"Hallo <span translate="no">0</span>, Ihre Bestellung <span translate="no">1</span> ist fertig"#.to_owned(),
+        );
+        // reference lost
+        translation_map.insert(
+            "addr2".to_owned(),
+            r#"Gesamt: <span translate="no">0</span> USD am <span translate="no">1</span>"#
+                .to_owned(),
+        );
+        translation_map.insert(
+            "addr3".to_owned(),
+            r#"<span translate="no">0</span> in <span translate="no">1</span>"#.to_owned(),
+        );
+        translation_map.insert(
+            "addr4".to_owned(),
+            r#"<span translate="no">1</span> nach <span translate="no">0</span>"#.to_owned(),
+        );
+
+        let mut issues: Vec<String> = restore_references(&mut translation_map, &references)
+            .iter()
+            .map(|(references, issue)| format!("{}: {}", references.source, issue))
+            .collect();
+        issues.sort();
+        assert_eq!(
+            issues,
+            vec![
+                "$first then $last: $first is reordered",
+                "Total:${amount * 2}USD on @sys.date, see $session.params.link: $session.params.link is missing",
+            ]
+        );
+        assert_eq!(
+            translation_map["addr1"],
+            "Hallo $name.original, Ihre Bestellung #order.id ist fertig"
+        );
+        assert_eq!(
+            translation_map["addr2"],
+            "Total:${amount * 2}USD on @sys.date, see $session.params.link"
+        );
+        assert_eq!(
+            translation_map["addr3"],
+            r#"<span translate="no">0</span> in $city"#
+        );
+        assert_eq!(translation_map["addr4"], "$last nach $first");
+
+        // reference followed by translated text without space would become different reference
+        let mut translation_map = collections::HashMap::new();
+        translation_map.insert("addr1".to_owned(), "at $time".to_owned());
+        let references = protect_references(&mut translation_map);
+        translation_map.insert(
+            "addr1".to_owned(),
+            r#"um <span translate="no">0</span>Uhr"#.to_owned(),
+        );
+        let issues = restore_references(&mut translation_map, &references);
+        assert_eq!(issues[0].1, PlaceholderIssue::Merged("$time".to_owned()));
+        assert_eq!(translation_map["addr1"], "at $time");
+    }
+}
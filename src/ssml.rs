@@ -0,0 +1,484 @@
+//! # Translation of SSML
+//!
+//! SSML (e.g. `<speak>Hello<break time="1s"/>world</speak>`) can't be sent for translation as a whole,
+//! translator would reorder, translate or break its elements. SSML is split into segments
+//! (text between structural elements like `<speak>`, `<p>`, `<s>`, `<break>` or `<audio>`) which are
+//! translated separately. Inline elements (`<emphasis>`, `<prosody>`, `<lang>`, `<voice>`) are kept
+//! in the segment as placeholders, elements whose content must not be translated (`<say-as>`,
+//! `<phoneme>`, `<sub>`) are replaced with single placeholder including their content.
+//! Translated SSML is put together from the segments and validated (same elements, well-formed)
+//! before it is written into the agent.
+use crate::html::starts_with_entity;
+use crate::placeholders::{escape_text, has_text, restore, PlaceholderIssue};
+use std::collections;
+use std::fmt;
+
+/// elements kept (as placeholders) within translated segment
+const INLINE_ELEMENTS: &[&str] = &["emphasis", "prosody", "lang", "voice"];
+
+/// elements kept (as placeholders) within translated segment together with their content
+const PROTECTED_ELEMENTS: &[&str] = &["say-as", "phoneme", "sub"];
+
+#[derive(Debug, PartialEq)]
+pub enum SsmlIssue {
+    /// SSML (source or translated) is not well-formed
+    Malformed(String),
+    /// translation added or removed some element
+    ElementsChanged,
+    /// inline element can't be re-inserted into translated segment
+    Placeholder(PlaceholderIssue),
+}
+
+impl fmt::Display for SsmlIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SsmlIssue::Malformed(detail) => write!(f, "SSML is not well-formed: {}", detail),
+            SsmlIssue::ElementsChanged => write!(f, "SSML elements changed by translation"),
+            SsmlIssue::Placeholder(issue) => write!(f, "SSML element {}", issue),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagKind {
+    Start,
+    End,
+    Empty,
+    /// comment, processing instruction, doctype
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node<'a> {
+    Tag {
+        name: String,
+        kind: TagKind,
+        raw: &'a str,
+    },
+    Text(&'a str),
+}
+
+/// true if text is SSML document, i.e. it is enclosed in speak element
+pub fn is_ssml(text: &str) -> bool {
+    let text = text.trim().to_lowercase();
+    text.starts_with("<speak") && text.ends_with("</speak>")
+}
+
+/// splits SSML into tags and texts
+fn tokenize(ssml: &str) -> Result<Vec<Node<'_>>, SsmlIssue> {
+    let mut nodes = vec![];
+    let mut rest = ssml;
+
+    while !rest.is_empty() {
+        if !rest.starts_with('<') {
+            let end = rest.find('<').unwrap_or(rest.len());
+            nodes.push(Node::Text(&rest[..end]));
+            rest = &rest[end..];
+            continue;
+        }
+
+        let end = if rest.starts_with("<!--") {
+            rest.find("-->").map(|idx| idx + 3)
+        } else {
+            // > within quoted attribute value does not close the tag
+            let mut quote = None;
+            rest.char_indices()
+                .find(|(_, c)| match quote {
+                    Some(q) if *c == q => {
+                        quote = None;
+                        false
+                    }
+                    Some(_) => false,
+                    None if *c == '"' || *c == '\'' => {
+                        quote = Some(*c);
+                        false
+                    }
+                    None => *c == '>',
+                })
+                .map(|(idx, _)| idx + 1)
+        };
+        let end = end.ok_or_else(|| SsmlIssue::Malformed("unclosed tag".to_owned()))?;
+        let raw = &rest[..end];
+
+        let (kind, name_start) = if raw.starts_with("<!") || raw.starts_with("<?") {
+            (TagKind::Other, 1)
+        } else if raw.starts_with("</") {
+            (TagKind::End, 2)
+        } else if raw.ends_with("/>") {
+            (TagKind::Empty, 1)
+        } else {
+            (TagKind::Start, 1)
+        };
+        let name: String = raw[name_start..]
+            .chars()
+            .take_while(|c| !c.is_whitespace() && *c != '/' && *c != '>')
+            .collect();
+        if name.is_empty() && kind != TagKind::Other {
+            return Err(SsmlIssue::Malformed(format!("invalid tag {}", raw)));
+        }
+
+        nodes.push(Node::Tag {
+            name: name.to_lowercase(),
+            kind,
+            raw,
+        });
+        rest = &rest[end..];
+    }
+
+    Ok(nodes)
+}
+
+/// checks that all elements are properly closed, that everything is enclosed in speak element
+/// and that texts contain no bare & or < (bare < is rejected already by tokenize as invalid tag)
+fn check_well_formed(nodes: &[Node]) -> Result<(), SsmlIssue> {
+    let mut stack: Vec<&str> = vec![];
+    let mut root_closed = false;
+
+    for node in nodes.iter() {
+        match node {
+            Node::Text(text)
+                if text.contains('<')
+                    || text
+                        .match_indices('&')
+                        .any(|(idx, _)| !starts_with_entity(&text[idx..])) =>
+            {
+                return Err(SsmlIssue::Malformed(format!(
+                    "bare & or < in text '{}'",
+                    text.trim()
+                )));
+            }
+            Node::Text(text) if stack.is_empty() && !text.trim().is_empty() => {
+                return Err(SsmlIssue::Malformed(format!(
+                    "text '{}' outside of speak element",
+                    text.trim()
+                )));
+            }
+            Node::Text(_) => {}
+            Node::Tag {
+                kind: TagKind::Other,
+                ..
+            } => {}
+            Node::Tag { name, kind, raw } => {
+                if stack.is_empty() && (root_closed || name != "speak") {
+                    return Err(SsmlIssue::Malformed(format!(
+                        "{} outside of speak element",
+                        raw
+                    )));
+                }
+                match kind {
+                    TagKind::Start => stack.push(name),
+                    TagKind::End => match stack.pop() {
+                        Some(open) if open == name => root_closed = stack.is_empty(),
+                        _ => return Err(SsmlIssue::Malformed(format!("unexpected {}", raw))),
+                    },
+                    _ => root_closed = root_closed || stack.is_empty(),
+                }
+            }
+        }
+    }
+
+    match stack.last() {
+        Some(open) => Err(SsmlIssue::Malformed(format!("<{}> is not closed", open))),
+        None if !root_closed => Err(SsmlIssue::Malformed("speak element is missing".to_owned())),
+        None => Ok(()),
+    }
+}
+
+/// SSML split into parts which are kept as they are and segments which are translated
+#[derive(Debug, PartialEq)]
+enum Part {
+    Kept(String),
+    /// text with placeholders and elements replaced by the placeholders
+    Segment(String, Vec<String>),
+}
+
+fn placeholder(idx: usize) -> String {
+    format!(r#"<span translate="no">{}</span>"#, idx)
+}
+
+/// adds segment to parts, leading/trailing whitespaces are kept outside of the segment
+/// (translator would not preserve them). Segment without any text is not translated.
+fn push_segment(parts: &mut Vec<Part>, segment: &mut String, tokens: &mut Vec<String>) {
+    if segment.is_empty() {
+        return;
+    }
+    let trimmed = segment.trim();
+    if !has_text(trimmed) {
+        let mut kept = segment.to_owned();
+        for (idx, token) in tokens.iter().enumerate() {
+            kept = kept.replacen(&placeholder(idx), token, 1);
+        }
+        parts.push(Part::Kept(kept));
+    } else {
+        let leading = &segment[..segment.len() - segment.trim_start().len()];
+        let trailing = &segment[segment.trim_end().len()..];
+        parts.push(Part::Kept(leading.to_owned()));
+        parts.push(Part::Segment(trimmed.to_owned(), tokens.to_owned()));
+        parts.push(Part::Kept(trailing.to_owned()));
+    }
+    segment.clear();
+    tokens.clear();
+}
+
+fn split(ssml: &str) -> Result<Vec<Part>, SsmlIssue> {
+    let nodes = tokenize(ssml)?;
+    check_well_formed(&nodes)?;
+
+    let mut parts = vec![];
+    let mut segment = String::new();
+    let mut tokens: Vec<String> = vec![];
+    // protected element being collected with its nesting depth
+    let mut protected: Option<(String, usize)> = None;
+
+    for node in nodes.iter() {
+        if let Some((content, depth)) = protected.as_mut() {
+            match node {
+                Node::Text(text) => content.push_str(text),
+                Node::Tag { kind, raw, .. } => {
+                    content.push_str(raw);
+                    match kind {
+                        TagKind::Start => *depth += 1,
+                        TagKind::End => *depth -= 1,
+                        _ => {}
+                    }
+                }
+            }
+            if protected.as_ref().map(|(_, depth)| *depth) == Some(0) {
+                let (content, _) = protected.take().unwrap();
+                segment.push_str(&placeholder(tokens.len()));
+                tokens.push(content);
+            }
+            continue;
+        }
+
+        match node {
+            Node::Text(text) => segment.push_str(text),
+            Node::Tag { name, kind, raw } => {
+                if INLINE_ELEMENTS.contains(&name.as_str()) && *kind != TagKind::Other {
+                    segment.push_str(&placeholder(tokens.len()));
+                    tokens.push(raw.to_string());
+                } else if PROTECTED_ELEMENTS.contains(&name.as_str()) && *kind == TagKind::Start {
+                    protected = Some((raw.to_string(), 1));
+                } else if PROTECTED_ELEMENTS.contains(&name.as_str()) && *kind == TagKind::Empty {
+                    segment.push_str(&placeholder(tokens.len()));
+                    tokens.push(raw.to_string());
+                } else {
+                    push_segment(&mut parts, &mut segment, &mut tokens);
+                    parts.push(Part::Kept(raw.to_string()));
+                }
+            }
+        }
+    }
+    push_segment(&mut parts, &mut segment, &mut tokens);
+
+    Ok(parts)
+}
+
+fn segment_key(ssml: &String, idx: usize) -> String {
+    format!("{:p}_{}", ssml, idx)
+}
+
+/// translation map of SSML segments, keys are derived from address of SSML string.
+/// SSML which is not well-formed is not translated at all.
+pub fn ssml_to_translation(ssml: &String) -> collections::HashMap<String, String> {
+    let mut map_to_translate = collections::HashMap::new();
+    if let Ok(parts) = split(ssml) {
+        for (idx, part) in parts.iter().enumerate() {
+            if let Part::Segment(text, _) = part {
+                map_to_translate.insert(segment_key(ssml, idx), text.to_owned());
+            }
+        }
+    }
+    map_to_translate
+}
+
+/// sorted tags of SSML, used to verify translation did not add or remove any element
+fn tags(nodes: &[Node]) -> Vec<String> {
+    let mut tags: Vec<String> = nodes
+        .iter()
+        .filter_map(|node| match node {
+            Node::Tag { raw, .. } => Some(raw.to_string()),
+            Node::Text(_) => None,
+        })
+        .collect();
+    tags.sort_unstable();
+    tags
+}
+
+/// translated SSML put together from translated segments. None if SSML has no translated segments,
+/// error if translated SSML would not be valid (SSML is to be left untranslated then).
+pub fn translated_ssml(
+    ssml: &String,
+    translations_map: &collections::HashMap<String, String>,
+) -> Result<Option<String>, SsmlIssue> {
+    let parts = split(ssml)?;
+    let mut translated = String::new();
+    let mut is_translated = false;
+
+    for (idx, part) in parts.iter().enumerate() {
+        match part {
+            Part::Kept(text) => translated.push_str(text),
+            Part::Segment(text, tokens) => match translations_map.get(&segment_key(ssml, idx)) {
+                Some(translated_segment) => {
+                    // texts translated as HTML are decoded, they must be escaped to become SSML again
+                    let restored = restore(&escape_text(translated_segment), tokens)
+                        .map_err(|mut issues| SsmlIssue::Placeholder(issues.remove(0)))?;
+                    translated.push_str(&restored);
+                    is_translated = true;
+                }
+                None => {
+                    let restored = restore(text, tokens)
+                        .map_err(|mut issues| SsmlIssue::Placeholder(issues.remove(0)))?;
+                    translated.push_str(&restored);
+                }
+            },
+        }
+    }
+
+    if !is_translated {
+        return Ok(None);
+    }
+
+    let nodes = tokenize(&translated)?;
+    check_well_formed(&nodes)?;
+    if tags(&nodes) != tags(&tokenize(ssml)?) {
+        return Err(SsmlIssue::ElementsChanged);
+    }
+    Ok(Some(translated))
+}
+
+/// applies translation of SSML created by [ssml_to_translation](fn.ssml_to_translation.html),
+/// SSML is left untranslated if translation would not be valid SSML
+pub fn ssml_from_translation(
+    ssml: &mut String,
+    translations_map: &collections::HashMap<String, String>,
+) {
+    if let Ok(Some(translated)) = translated_ssml(ssml, translations_map) {
+        *ssml = translated;
+    }
+}
+
+/// translation map of text which is either SSML or plain text (e.g. text to speech of GA responses)
+pub fn speech_to_translation(speech: &String) -> collections::HashMap<String, String> {
+    if is_ssml(speech) {
+        return ssml_to_translation(speech);
+    }
+    let mut map_to_translate = collections::HashMap::new();
+    map_to_translate.insert(format!("{:p}", speech), speech.to_owned());
+    map_to_translate
+}
+
+/// applies translation of text created by [speech_to_translation](fn.speech_to_translation.html)
+pub fn speech_from_translation(
+    speech: &mut String,
+    translations_map: &collections::HashMap<String, String>,
+) {
+    if is_ssml(speech) {
+        ssml_from_translation(speech, translations_map);
+    } else if let Some(translated) = translations_map.get(&format!("{:p}", speech)) {
+        *speech = translated.to_owned();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::google::gcloud::translate::dummy_translate;
+
+    // cargo test -- --show-output test_ssml_translation
+    #[test]
+    fn test_ssml_translation() {
+        let ssml = r#"<speak><p>Your parcel <emphasis level="strong">arrives</emphasis> today.<break time="1s"/>Call <say-as interpret-as="telephone">555 0100</say-as> for help.</p> <audio src="https://example.com/bell.ogg">bell</audio></speak>"#.to_owned();
+        assert!(is_ssml(&ssml));
+
+        let mut translations_map = ssml_to_translation(&ssml);
+        let mut segments: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+        segments.sort_unstable();
+        assert_eq!(
+            segments,
+            vec![
+                r#"Call <span translate="no">0</span> for help."#,
+                r#"Your parcel <span translate="no">0</span>arrives<span translate="no">1</span> today."#,
+                "bell",
+            ]
+        );
+
+        dummy_translate(&mut translations_map);
+        assert_eq!(
+            translated_ssml(&ssml, &translations_map),
+            Ok(Some(r#"<speak><p>Your parcel <emphasis level="strong">arrives</emphasis> today._translated<break time="1s"/>Call <say-as interpret-as="telephone">555 0100</say-as> for help._translated</p> <audio src="https://example.com/bell.ogg">bell_translated</audio></speak>"#.to_owned()))
+        );
+
+        // translator swapped opening and closing element
+        let key = translations_map
+            .iter()
+            .find(|(_, val)| val.starts_with("Your parcel"))
+            .map(|(key, _)| key.to_owned())
+            .unwrap();
+        translations_map.insert(
+            key.to_owned(),
+            r#"Ihr Paket <span translate="no">1</span>kommt<span translate="no">0</span> heute."#
+                .to_owned(),
+        );
+        assert_eq!(
+            translated_ssml(&ssml, &translations_map),
+            Err(SsmlIssue::Malformed("unexpected </emphasis>".to_owned()))
+        );
+
+        // translator added markup
+        translations_map.insert(
+            key.to_owned(),
+            r#"Ihr Paket <span translate="no">0</span>kommt<span translate="no">1</span> <b>heute</b>."#
+                .to_owned(),
+        );
+        assert_eq!(
+            translated_ssml(&ssml, &translations_map),
+            Err(SsmlIssue::ElementsChanged)
+        );
+
+        translations_map.insert(key, "Ihr Paket kommt heute.".to_owned());
+        assert_eq!(
+            translated_ssml(&ssml, &translations_map),
+            Err(SsmlIssue::Placeholder(PlaceholderIssue::Missing(
+                r#"<emphasis level="strong">"#.to_owned()
+            )))
+        );
+        let mut ssml_copy = ssml.clone();
+        ssml_from_translation(&mut ssml_copy, &translations_map);
+        assert_eq!(ssml_copy, ssml);
+
+        // invalid SSML is not translated
+        assert!(ssml_to_translation(&"<speak><p>Hello</speak>".to_owned()).is_empty());
+        assert!(ssml_to_translation(&"<speak>Hello</speak> world".to_owned()).is_empty());
+        assert!(ssml_to_translation(&"<speak>Tom & Jerry</speak>".to_owned()).is_empty());
+        assert!(ssml_to_translation(&"<speak>1 < 2</speak>".to_owned()).is_empty());
+        assert!(!is_ssml("Hello <break/>"));
+    }
+
+    // cargo test -- --show-output test_speech_translation
+    #[test]
+    fn test_speech_translation() {
+        let mut plain = "Lights are on.".to_owned();
+        let mut ssml =
+            r#"<speak>Lights are on.<break time="1s"/>Anything else?</speak>"#.to_owned();
+
+        let mut translations_map = speech_to_translation(&plain);
+        translations_map.extend(speech_to_translation(&ssml));
+        let mut texts: Vec<&str> = translations_map.values().map(|val| val.as_str()).collect();
+        texts.sort_unstable();
+        // plain text is translated as a whole, only text nodes of SSML are translated
+        assert_eq!(
+            texts,
+            vec!["Anything else?", "Lights are on.", "Lights are on."]
+        );
+
+        dummy_translate(&mut translations_map);
+        speech_from_translation(&mut plain, &translations_map);
+        speech_from_translation(&mut ssml, &translations_map);
+        assert_eq!(plain, "Lights are on._translated");
+        assert_eq!(
+            ssml,
+            r#"<speak>Lights are on._translated<break time="1s"/>Anything else?_translated</speak>"#
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! # Summary of translation run
+//!
+//! Every translation run produces summary describing how the agent was translated
+//! (provider, languages, model, glossary, ...). Summary is printed to CLI
+//! and stored as `run_summary.json` in output folder next to translated agent.
+use crate::errors::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const RUN_SUMMARY_FILE: &str = "run_summary.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub provider: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    /// V3 only: fully qualified model used for target language, None means API default (general NMT)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// glossary file provided by user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub glossary: Option<String>,
+    /// number of texts sent for translation
+    pub translated_items: usize,
+    /// V2 only: number of responses rejected due to rate limits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limited_responses: Option<u64>,
+    /// findings of agent processing which should be checked by the user
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub report: Vec<ReportItem>,
+}
+
+/// single finding of agent processing, e.g. entity reference lost during translation
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReportItem {
+    /// what the finding is about, e.g. composite_entity
+    pub category: String,
+    /// agent file the finding relates to
+    pub file: String,
+    pub message: String,
+}
+
+impl ReportItem {
+    pub fn new(category: &str, file: &str, message: String) -> Self {
+        ReportItem {
+            category: category.to_owned(),
+            file: file.to_owned(),
+            message,
+        }
+    }
+}
+
+impl RunSummary {
+    pub fn new(provider: &str, source_lang: &str, target_lang: &str) -> Self {
+        RunSummary {
+            provider: provider.to_owned(),
+            source_lang: source_lang.to_owned(),
+            target_lang: target_lang.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// stores summary as run_summary.json into given folder
+    pub fn write(&self, target_folder: &str) -> Result<()> {
+        let summary_str = serde_json::to_string_pretty(self)?;
+        fs::create_dir_all(target_folder)?;
+        fs::write(Path::new(target_folder).join(RUN_SUMMARY_FILE), summary_str)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // cargo test -- --show-output test_run_summary_write
+    #[test]
+    fn test_run_summary_write() -> Result<()> {
+        let folder = env::temp_dir().join(format!("gdf_run_summary_{}", std::process::id()));
+        let mut summary = RunSummary::new("v3", "en", "de");
+        summary.model = Some("projects/p/locations/us-central1/models/general/nmt".to_owned());
+        summary.translated_items = 42;
+        summary.write(folder.to_str().unwrap())?;
+
+        let summary_str = fs::read_to_string(folder.join(RUN_SUMMARY_FILE))?;
+        let stored: RunSummary = serde_json::from_str(&summary_str)?;
+        assert_eq!(stored.model, summary.model);
+        assert_eq!(stored.translated_items, 42);
+        assert!(!summary_str.contains("glossary"));
+        assert!(!summary_str.contains("rate_limited_responses"));
+        assert!(!summary_str.contains("report"));
+
+        fs::remove_dir_all(&folder)?;
+        Ok(())
+    }
+}
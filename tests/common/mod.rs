@@ -0,0 +1,548 @@
+//! Mock implementation of Google APIs used by offline end-to-end tests.
+//!
+//! Emulates just enough of OAuth token endpoint, Google Cloud Storage JSON API
+//! and Google Translate V2/V3 APIs to run the whole translation pipeline
+//! without network access or real credentials. Translation simply appends
+//! target language code to the text, e.g. `Hello` -> `Hello [de]`.
+#![allow(dead_code)]
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+pub const AUTHORIZED_USER_JSON: &str = r#"{
+    "client_id": "dummy-client-id.apps.googleusercontent.com",
+    "client_secret": "dummy-client-secret",
+    "quota_project_id": "mock-project",
+    "refresh_token": "dummy-refresh-token",
+    "type": "authorized_user"
+}"#;
+
+#[derive(Debug, Default)]
+pub struct MockState {
+    pub token_requests: usize,
+    pub v2_requests: usize,
+    pub buckets: HashSet<String>,
+    /// key is bucket_name/object_name
+    pub objects: HashMap<String, String>,
+    pub glossaries: HashSet<String>,
+    pub operations: usize,
+    /// bodies of all batchTranslateText requests
+    pub batch_translate_requests: Vec<Value>,
+    /// batch translations never finish
+    pub batch_translations_pending: bool,
+    /// agent ZIP returned by Dialogflow agent export, key is project ID
+    pub agents: HashMap<String, Vec<u8>>,
+    /// binary objects (e.g. exported agents), key is bucket_name/object_name
+    pub binary_objects: HashMap<String, Vec<u8>>,
+    /// responses of pending Dialogflow operations, key is operation name
+    pub dialogflow_operations: HashMap<String, Value>,
+    /// every agent restore/import/train, e.g. "restore mock-project"
+    pub deployments: Vec<String>,
+    /// every request received, e.g. "POST /storage/v1/b"
+    pub requests: Vec<String>,
+}
+
+pub struct MockGoogleApi {
+    pub base_url: String,
+    pub state: Arc<Mutex<MockState>>,
+}
+
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub body: String,
+}
+
+pub struct MockResponse {
+    pub status: u16,
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    fn json(status: u16, body: Value) -> Self {
+        MockResponse {
+            status,
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    fn empty(status: u16) -> Self {
+        MockResponse {
+            status,
+            body: vec![],
+        }
+    }
+
+    fn not_found() -> Self {
+        MockResponse::json(404, json!({"error": {"code": 404, "message": "Not Found"}}))
+    }
+}
+
+pub fn mock_translate(text: &str, target_lang: &str) -> String {
+    format!("{} [{}]", text, target_lang)
+}
+
+impl MockGoogleApi {
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let server_state = state.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = stream.unwrap();
+                let state = server_state.clone();
+                thread::spawn(move || handle_connection(stream, state));
+            }
+        });
+        MockGoogleApi { base_url, state }
+    }
+
+    pub fn token_url(&self) -> String {
+        format!("{}/token", self.base_url)
+    }
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded: Vec<u8> = vec![];
+    let mut idx = 0;
+    while idx < bytes.len() {
+        if bytes[idx] == b'%' && idx + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[idx + 1..idx + 3]).unwrap();
+            decoded.push(u8::from_str_radix(hex, 16).unwrap());
+            idx += 3;
+        } else {
+            decoded.push(bytes[idx]);
+            idx += 1;
+        }
+    }
+    String::from_utf8(decoded).unwrap()
+}
+
+fn decode_chunked(body: &[u8]) -> Vec<u8> {
+    let mut decoded = vec![];
+    let mut rest = body;
+    loop {
+        let line_end = rest.windows(2).position(|w| w == b"\r\n").unwrap();
+        let size_str = std::str::from_utf8(&rest[..line_end]).unwrap();
+        let size = usize::from_str_radix(size_str.split(';').next().unwrap().trim(), 16).unwrap();
+        if size == 0 {
+            return decoded;
+        }
+        let chunk_start = line_end + 2;
+        decoded.extend_from_slice(&rest[chunk_start..chunk_start + size]);
+        rest = &rest[chunk_start + size + 2..];
+    }
+}
+
+fn is_chunked_complete(body: &[u8]) -> bool {
+    // walk through chunks and check final zero sized chunk was received
+    let mut rest = body;
+    loop {
+        let line_end = match rest.windows(2).position(|w| w == b"\r\n") {
+            Some(pos) => pos,
+            None => return false,
+        };
+        let size_str = std::str::from_utf8(&rest[..line_end]).unwrap();
+        let size = usize::from_str_radix(size_str.split(';').next().unwrap().trim(), 16).unwrap();
+        if size == 0 {
+            return rest.len() >= line_end + 4;
+        }
+        if rest.len() < line_end + 2 + size + 2 {
+            return false;
+        }
+        rest = &rest[line_end + 2 + size + 2..];
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<MockRequest> {
+    let mut buf = vec![0u8; 16384];
+    let mut request: Vec<u8> = vec![];
+    let header_end = loop {
+        let n = stream.read(&mut buf).ok()?;
+        if n == 0 {
+            return None;
+        }
+        request.extend_from_slice(&buf[..n]);
+        if let Some(pos) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+
+    let headers = String::from_utf8_lossy(&request[..header_end]).to_string();
+    let headers_lc = headers.to_lowercase();
+    if headers_lc.contains("expect: 100-continue") {
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").ok()?;
+    }
+
+    let chunked = headers_lc.contains("transfer-encoding: chunked");
+    let content_length = headers_lc
+        .lines()
+        .find_map(|l| l.strip_prefix("content-length:"))
+        .map(|v| v.trim().parse::<usize>().unwrap())
+        .unwrap_or(0);
+
+    let mut body = request[header_end..].to_vec();
+    loop {
+        let complete = if chunked {
+            is_chunked_complete(&body)
+        } else {
+            body.len() >= content_length
+        };
+        if complete {
+            break;
+        }
+        let n = stream.read(&mut buf).ok()?;
+        if n == 0 {
+            break;
+        }
+        body.extend_from_slice(&buf[..n]);
+    }
+    if chunked {
+        body = decode_chunked(&body);
+    }
+
+    let request_line = headers.lines().next()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?.to_owned();
+    let target = parts.next()?.to_owned();
+    let (path, query_str) = target.split_once('?').unwrap_or((&target, ""));
+    let query = serde_urlencoded::from_str::<Vec<(String, String)>>(query_str)
+        .unwrap()
+        .into_iter()
+        .collect();
+
+    Some(MockRequest {
+        method,
+        path: percent_decode(path),
+        query,
+        body: String::from_utf8(body).unwrap(),
+    })
+}
+
+fn handle_connection(mut stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let request = match read_request(&mut stream) {
+        Some(request) => request,
+        None => return,
+    };
+    let response = route(&request, &mut state.lock().unwrap());
+    let reason = match response.status {
+        200 => "OK",
+        204 => "No Content",
+        404 => "Not Found",
+        _ => "Error",
+    };
+    let mut raw = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason,
+        response.body.len()
+    )
+    .into_bytes();
+    raw.extend_from_slice(&response.body);
+    let _ = stream.write_all(&raw);
+}
+
+fn route(req: &MockRequest, state: &mut MockState) -> MockResponse {
+    state.requests.push(format!("{} {}", req.method, req.path));
+    let segments: Vec<&str> = req.path.trim_start_matches('/').split('/').collect();
+
+    match (req.method.as_str(), segments.as_slice()) {
+        ("POST", ["token"]) => {
+            state.token_requests += 1;
+            MockResponse::json(
+                200,
+                json!({
+                    "access_token": format!("mock-token-{}", state.token_requests),
+                    "token_type": "Bearer",
+                    "expires_in": 3600
+                }),
+            )
+        }
+
+        // Google Cloud Storage
+        ("POST", ["storage", "v1", "b"]) => {
+            let body: Value = serde_json::from_str(&req.body).unwrap();
+            let name = body["name"].as_str().unwrap().to_owned();
+            state.buckets.insert(name.clone());
+            MockResponse::json(200, json!({ "kind": "storage#bucket", "name": name }))
+        }
+        ("POST", ["upload", "storage", "v1", "b", bucket, "o"]) => {
+            if !state.buckets.contains(*bucket) {
+                return MockResponse::not_found();
+            }
+            let name = req.query["name"].clone();
+            state
+                .objects
+                .insert(format!("{}/{}", bucket, name), req.body.clone());
+            MockResponse::json(
+                200,
+                json!({ "kind": "storage#object", "bucket": bucket, "name": name }),
+            )
+        }
+        ("GET", ["storage", "v1", "b", bucket, "o", object]) => {
+            let key = format!("{}/{}", bucket, object);
+            match (state.objects.get(&key), state.binary_objects.get(&key)) {
+                (Some(content), _) => MockResponse {
+                    status: 200,
+                    body: content.clone().into_bytes(),
+                },
+                (None, Some(content)) => MockResponse {
+                    status: 200,
+                    body: content.clone(),
+                },
+                _ => MockResponse::not_found(),
+            }
+        }
+        ("DELETE", ["storage", "v1", "b", bucket, "o", object]) => {
+            match state.objects.remove(&format!("{}/{}", bucket, object)) {
+                Some(_) => MockResponse::empty(204),
+                None => MockResponse::not_found(),
+            }
+        }
+        ("DELETE", ["storage", "v1", "b", bucket]) => {
+            let prefix = format!("{}/", bucket);
+            if state.objects.keys().any(|key| key.starts_with(&prefix)) {
+                return MockResponse::json(
+                    409,
+                    json!({"error": {"code": 409, "message": "The bucket you tried to delete is not empty."}}),
+                );
+            }
+            if state.buckets.remove(*bucket) {
+                MockResponse::empty(204)
+            } else {
+                MockResponse::not_found()
+            }
+        }
+
+        // Google Translate V2
+        ("POST", ["language", "translate", "v2"]) => {
+            state.v2_requests += 1;
+            let translated = mock_translate(&req.query["q"], &req.query["target"]);
+            MockResponse::json(
+                200,
+                json!({ "data": { "translations": [{ "translatedText": translated }] } }),
+            )
+        }
+
+        // Google Translate V3
+        ("POST", ["v3", "projects", project, "locations", location])
+            if location.ends_with(":batchTranslateText") =>
+        {
+            batch_translate_text(project, req, state)
+        }
+        ("POST", ["v3", "projects", project, "locations", location, "glossaries"]) => {
+            let body: Value = serde_json::from_str(&req.body).unwrap();
+            state
+                .glossaries
+                .insert(body["name"].as_str().unwrap().to_owned());
+            state.operations += 1;
+            MockResponse::json(
+                200,
+                json!({
+                    "name": format!("projects/{}/locations/{}/operations/glossary-{}", project, location, state.operations),
+                    "metadata": {
+                        "@type": "type.googleapis.com/google.cloud.translation.v3.CreateGlossaryMetadata",
+                        "state": "RUNNING"
+                    }
+                }),
+            )
+        }
+        ("DELETE", ["v3", "projects", project, "locations", location, "glossaries", glossary]) => {
+            let name = format!(
+                "projects/{}/locations/{}/glossaries/{}",
+                project, location, glossary
+            );
+            if !state.glossaries.remove(&name) {
+                return MockResponse::not_found();
+            }
+            state.operations += 1;
+            MockResponse::json(
+                200,
+                json!({
+                    "name": format!("projects/{}/locations/{}/operations/glossary-{}", project, location, state.operations),
+                    "metadata": {
+                        "@type": "type.googleapis.com/google.cloud.translation.v3.DeleteGlossaryMetadata",
+                        "state": "RUNNING"
+                    }
+                }),
+            )
+        }
+        ("POST", ["v3", "projects", _, "locations", _, "operations", operation])
+            if operation.ends_with(":wait") && state.batch_translations_pending =>
+        {
+            MockResponse::json(
+                200,
+                json!({
+                    "name": req.path.trim_start_matches("/v3/").trim_end_matches(":wait"),
+                    "metadata": {
+                        "@type": "type.googleapis.com/google.cloud.translation.v3.BatchTranslateMetadata",
+                        "state": "RUNNING",
+                        "submitTime": "2020-07-18T10:00:00Z"
+                    }
+                }),
+            )
+        }
+        ("POST", ["v3", "projects", _, "locations", _, "operations", operation])
+            if operation.ends_with(":wait") =>
+        {
+            MockResponse::json(
+                200,
+                json!({
+                    "name": req.path.trim_start_matches("/v3/").trim_end_matches(":wait"),
+                    "metadata": {
+                        "@type": "type.googleapis.com/google.cloud.translation.v3.BatchTranslateMetadata",
+                        "state": "SUCCEEDED",
+                        "submitTime": "2020-07-18T10:00:00Z"
+                    },
+                    "done": true,
+                    "response": {
+                        "@type": "type.googleapis.com/google.cloud.translation.v3.BatchTranslateResponse",
+                        "totalCharacters": "0",
+                        "translatedCharacters": "0",
+                        "submitTime": "2020-07-18T10:00:00Z",
+                        "endTime": "2020-07-18T10:00:01Z"
+                    }
+                }),
+            )
+        }
+
+        // Dialogflow ES, export finishes on first check of the operation
+        ("POST", ["v2", "projects", project, "agent:export"]) => {
+            let zip = match state.agents.get(*project) {
+                Some(zip) => zip.clone(),
+                None => return MockResponse::not_found(),
+            };
+            let body: Value = serde_json::from_str(&req.body).unwrap();
+            let response = match body["agentUri"].as_str() {
+                Some(agent_uri) => {
+                    let (bucket, object) = split_gs_uri(agent_uri);
+                    state
+                        .binary_objects
+                        .insert(format!("{}/{}", bucket, object), zip);
+                    json!({ "agentUri": agent_uri })
+                }
+                None => json!({ "agentContent": base64::encode(&zip) }),
+            };
+            dialogflow_operation(project, response, state)
+        }
+        ("POST", ["v2", "projects", project, method])
+            if *method == "agent:restore" || *method == "agent:import" =>
+        {
+            let body: Value = serde_json::from_str(&req.body).unwrap();
+            let zip = base64::decode(body["agentContent"].as_str().unwrap()).unwrap();
+            state.agents.insert(project.to_string(), zip);
+            let action = method.trim_start_matches("agent:");
+            state.deployments.push(format!("{} {}", action, project));
+            dialogflow_operation(project, json!({}), state)
+        }
+        ("POST", ["v2", "projects", project, "agent:train"]) => {
+            state.deployments.push(format!("train {}", project));
+            dialogflow_operation(project, json!({}), state)
+        }
+        ("GET", ["v2", "projects", project, "agent", "intents"]) => {
+            if state.agents.contains_key(*project) {
+                MockResponse::json(
+                    200,
+                    json!({ "intents": [{ "name": format!("projects/{}/agent/intents/1", project) }] }),
+                )
+            } else {
+                MockResponse::json(200, json!({}))
+            }
+        }
+        ("GET", ["v2", "projects", _, "agent", "entityTypes"]) => {
+            MockResponse::json(200, json!({}))
+        }
+        ("GET", ["v2", "projects", _, "operations", _]) => {
+            let name = req.path.trim_start_matches("/v2/");
+            match state.dialogflow_operations.get(name) {
+                Some(response) => MockResponse::json(
+                    200,
+                    json!({ "name": name, "done": true, "response": response }),
+                ),
+                None => MockResponse::not_found(),
+            }
+        }
+        _ => MockResponse::not_found(),
+    }
+}
+
+/// registers operation which finishes on first check
+fn dialogflow_operation(project: &str, response: Value, state: &mut MockState) -> MockResponse {
+    state.operations += 1;
+    let name = format!("projects/{}/operations/op-{}", project, state.operations);
+    state.dialogflow_operations.insert(name.clone(), response);
+    MockResponse::json(200, json!({ "name": name }))
+}
+
+fn split_gs_uri(uri: &str) -> (String, String) {
+    let path = uri.trim_start_matches("gs://");
+    let idx = path.find('/').unwrap();
+    (path[..idx].to_owned(), path[idx + 1..].to_owned())
+}
+
+/// translates input TSV synchronously and stores result the same way as Google does, i.e.
+/// gs://<output bucket>/<input bucket>_<input file stem>_<lang>_translations.tsv + index.csv
+fn batch_translate_text(project: &str, req: &MockRequest, state: &mut MockState) -> MockResponse {
+    let body: Value = serde_json::from_str(&req.body).unwrap();
+    state.batch_translate_requests.push(body.clone());
+    let target_lang = body["targetLanguageCodes"].as_str().unwrap().to_owned();
+    let input_uri = body["inputConfigs"][0]["gcsSource"]["inputUri"]
+        .as_str()
+        .unwrap();
+    let output_prefix = body["outputConfig"]["gcsDestination"]["outputUriPrefix"]
+        .as_str()
+        .unwrap();
+
+    let (in_bucket, in_object) = split_gs_uri(input_uri);
+    let (out_bucket, _) = split_gs_uri(output_prefix);
+
+    let input = match state.objects.get(&format!("{}/{}", in_bucket, in_object)) {
+        Some(input) => input.clone(),
+        None => return MockResponse::not_found(),
+    };
+
+    let mut output = String::new();
+    for line in input.lines().filter(|line| line.trim() != "") {
+        let (address, text) = line.split_once('\t').unwrap();
+        let inner = text
+            .trim_start_matches("<to_translate>")
+            .trim_end_matches("</to_translate>");
+        output.push_str(&format!(
+            "{}\t{}\t<to_translate>{}</to_translate>\n",
+            address,
+            text,
+            mock_translate(inner, &target_lang)
+        ));
+    }
+
+    let in_stem = in_object.trim_end_matches(".tsv");
+    let output_object = format!("{}_{}_{}_translations.tsv", in_bucket, in_stem, target_lang);
+    state
+        .objects
+        .insert(format!("{}/{}", out_bucket, output_object), output);
+    state.objects.insert(
+        format!("{}/index.csv", out_bucket),
+        format!(
+            "{},{},gs://{}/{},,,\n",
+            input_uri, target_lang, out_bucket, output_object
+        ),
+    );
+
+    state.operations += 1;
+    MockResponse::json(
+        200,
+        json!({
+            "name": format!("projects/{}/locations/us-central1/operations/batch-{}", project, state.operations),
+            "metadata": {
+                "@type": "type.googleapis.com/google.cloud.translation.v3.BatchTranslateMetadata",
+                "state": "RUNNING"
+            }
+        }),
+    )
+}
@@ -0,0 +1,453 @@
+//! Offline end-to-end tests running whole V2 and V3 translation
+//! against mock Google APIs (see tests/common/mod.rs)
+mod common;
+
+use async_std::task;
+use common::{MockGoogleApi, AUTHORIZED_USER_JSON};
+use gdf_translate::google::dialogflow::api::{self, AgentDeployment, DeployMode};
+use gdf_translate::google::dialogflow::options::AgentOptions;
+use gdf_translate::google::gcloud::auth::{CredentialSource, TokenProvider};
+use gdf_translate::google::gcloud::http::{HttpClient, RetryPolicy};
+use gdf_translate::google::gcloud::rate_limit::RateLimits;
+use gdf_translate::google::gcloud::translate::v3::TranslationModels;
+use gdf_translate::google::gcloud::translate::{
+    GoogleTranslateV2, GoogleTranslateV3, TranslationJob, V2Options, V3Options,
+};
+use gdf_translate::google::gcloud::GoogleApiConfig;
+use gdf_translate::ui::ProgressMessageType;
+use gdf_translate::zip::zip_directory;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+const SAMPLE_AGENT: &str = "./examples/sample_agents/Currency-Converter.zip";
+const SAMPLE_CX_AGENT_FOLDER: &str = "./tests/data/cx_agent";
+
+fn setup(mock: &MockGoogleApi) -> (HttpClient, GoogleApiConfig) {
+    let token_provider = TokenProvider::new(
+        CredentialSource::from_json_str(AUTHORIZED_USER_JSON).unwrap(),
+        Some(&mock.token_url()),
+    );
+    let http_client = HttpClient::new(
+        token_provider,
+        RetryPolicy::new(3, Duration::from_millis(1), Duration::from_millis(10)),
+    );
+    let mut api_config = GoogleApiConfig::new(
+        Some(&mock.base_url),
+        Some(&mock.base_url),
+        Some(&mock.base_url),
+        Some(Duration::from_millis(5)),
+        None,
+    );
+    api_config.operation_poll_interval = Duration::from_millis(5);
+    (http_client, api_config)
+}
+
+fn output_folder(name: &str) -> PathBuf {
+    let folder =
+        std::env::temp_dir().join(format!("gdf_translate_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&folder);
+    fs::create_dir_all(&folder).unwrap();
+    folder
+}
+
+/// translation of given agent from en to de stored in given folder
+fn translation_job<'a>(
+    agent_path: &'a str,
+    folder: &'a Path,
+    agent_options: &'a AgentOptions,
+) -> TranslationJob<'a> {
+    TranslationJob {
+        gdf_agent_path: agent_path,
+        translated_gdf_agent_folder: folder.to_str().unwrap(),
+        source_lang: "en",
+        target_lang: "de",
+        skip_entities_translation: false,
+        skip_utterances_translation: false,
+        skip_responses_translation: false,
+        agent_options,
+    }
+}
+
+fn assert_translated_agent(folder: &Path) {
+    assert!(folder.join("_packed").join("TranslatedAgent.zip").exists());
+
+    let utterances = fs::read_to_string(
+        folder
+            .join("_unpacked")
+            .join("intents")
+            .join("currency.convert_usersays_de.json"),
+    )
+    .unwrap();
+    assert!(utterances.contains(" [de]"));
+
+    let intent = fs::read_to_string(
+        folder
+            .join("_unpacked")
+            .join("intents")
+            .join("Default Fallback intent.json"),
+    )
+    .unwrap();
+    assert!(intent.contains(r#""lang": "de""#));
+    assert!(intent.contains(" [de]"));
+
+    let agent = fs::read_to_string(folder.join("_unpacked").join("agent.json")).unwrap();
+    assert!(agent.contains(r#""de""#));
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_translation_v2
+#[test]
+fn test_offline_translation_v2() {
+    let mock = MockGoogleApi::start();
+    let (http_client, api_config) = setup(&mock);
+    let folder = output_folder("v2");
+    let (tx, _rx) = channel::<ProgressMessageType>();
+
+    let agent_options = AgentOptions {
+        translate_manifest: true,
+        time_zone: Some("Europe/Berlin".to_owned()),
+        spell_correction: Some(true),
+        ..Default::default()
+    };
+
+    let run_summary = GoogleTranslateV2::execute_translation(
+        &translation_job(SAMPLE_AGENT, &folder, &agent_options),
+        &http_client,
+        &api_config,
+        &V2Options {
+            task_count: 3,
+            rate_limits: &RateLimits::new(Some(1_000_000), Some(6_000)),
+        },
+        tx,
+    )
+    .unwrap();
+
+    assert_translated_agent(&folder);
+    let agent = fs::read_to_string(folder.join("_unpacked").join("agent.json")).unwrap();
+    assert!(agent.contains(r#""shortDescription": "Get exchange rates for major currencies [de]""#));
+    assert!(agent.contains(r#""defaultTimezone": "Europe/Berlin""#));
+    assert!(agent.contains(r#""enableSpellCorrection": true"#));
+    assert_eq!(run_summary.provider, "v2");
+    assert!(run_summary.translated_items > 0);
+    assert_eq!(run_summary.rate_limited_responses, Some(0));
+    assert!(folder.join("run_summary.json").exists());
+    let state = mock.state.lock().unwrap();
+    assert!(state.v2_requests > 0);
+    // token is cached and shared by all parallel tasks
+    assert_eq!(state.token_requests, 1);
+    let _ = fs::remove_dir_all(&folder);
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_translation_v3
+#[test]
+fn test_offline_translation_v3() {
+    let mock = MockGoogleApi::start();
+    let (http_client, api_config) = setup(&mock);
+    let folder = output_folder("v3");
+    let (tx, _rx) = channel::<ProgressMessageType>();
+
+    let run_summary = GoogleTranslateV3::execute_translation(
+        &translation_job(SAMPLE_AGENT, &folder, &AgentOptions::default()),
+        &http_client,
+        &api_config,
+        &V3Options {
+            project_id: "mock-project",
+            create_output_tsv: false,
+            glossary_path: None,
+            models: &TranslationModels::parse(&["de=TRL1234567890"]).unwrap(),
+        },
+        tx,
+    )
+    .unwrap();
+
+    assert_translated_agent(&folder);
+    let model = "projects/mock-project/locations/us-central1/models/TRL1234567890";
+    assert_eq!(run_summary.model.as_deref(), Some(model));
+    let stored_summary = fs::read_to_string(folder.join("run_summary.json")).unwrap();
+    assert!(stored_summary.contains(model));
+    let state = mock.state.lock().unwrap();
+    // model is sent for target language together with the glossary
+    for body in state.batch_translate_requests.iter() {
+        assert_eq!(body["models"]["de"], model);
+        assert!(body["glossaries"]["de"]["glossary"].is_string());
+    }
+    assert!(!state.batch_translate_requests.is_empty());
+    // all temporary buckets, objects and glossary must be cleaned up
+    assert!(state.buckets.is_empty(), "{:#?}", state.buckets);
+    assert!(state.objects.is_empty(), "{:#?}", state.objects.keys());
+    assert!(state.glossaries.is_empty(), "{:#?}", state.glossaries);
+    assert!(state
+        .requests
+        .iter()
+        .any(|req| req.ends_with(":batchTranslateText")));
+    assert_eq!(state.token_requests, 1);
+    let _ = fs::remove_dir_all(&folder);
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_translation_v3_timeout
+#[test]
+fn test_offline_translation_v3_timeout() {
+    let mock = MockGoogleApi::start();
+    mock.state.lock().unwrap().batch_translations_pending = true;
+    let (http_client, mut api_config) = setup(&mock);
+    api_config.operation_timeout = Duration::from_millis(50);
+    let folder = output_folder("v3_timeout");
+    let (tx, _rx) = channel::<ProgressMessageType>();
+
+    // batch translation is given up, translation fails
+    let result = GoogleTranslateV3::execute_translation(
+        &translation_job(SAMPLE_AGENT, &folder, &AgentOptions::default()),
+        &http_client,
+        &api_config,
+        &V3Options {
+            project_id: "mock-project",
+            create_output_tsv: false,
+            glossary_path: None,
+            models: &TranslationModels::default(),
+        },
+        tx,
+    );
+
+    assert!(format!("{:?}", result.unwrap_err()).contains("not finished within"));
+    assert!(!folder.join("_packed").join("TranslatedAgent.zip").exists());
+    let state = mock.state.lock().unwrap();
+    // operation is polled with interval until timeout elapses
+    let polls = state
+        .requests
+        .iter()
+        .filter(|req| req.ends_with(":wait"))
+        .count();
+    assert!(polls > 1 && polls < 50, "{}", polls);
+    let _ = fs::remove_dir_all(&folder);
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_translation_cx
+#[test]
+fn test_offline_translation_cx() {
+    let mock = MockGoogleApi::start();
+    let (http_client, api_config) = setup(&mock);
+    let folder = output_folder("cx");
+    let (tx, _rx) = channel::<ProgressMessageType>();
+
+    let cx_agent_zip = folder.join("cx_agent.zip");
+    zip_directory(SAMPLE_CX_AGENT_FOLDER, cx_agent_zip.to_str().unwrap()).unwrap();
+    let translated_folder = folder.join("translated");
+
+    let run_summary = GoogleTranslateV2::execute_translation(
+        &translation_job(
+            cx_agent_zip.to_str().unwrap(),
+            &translated_folder,
+            &AgentOptions::default(),
+        ),
+        &http_client,
+        &api_config,
+        &V2Options {
+            task_count: 2,
+            rate_limits: &RateLimits::new(None, None),
+        },
+        tx,
+    )
+    .unwrap();
+    assert!(run_summary.translated_items > 0);
+    assert!(translated_folder
+        .join("_packed")
+        .join("TranslatedAgent.zip")
+        .exists());
+
+    let unpacked = translated_folder.join("_unpacked");
+    let flow =
+        fs::read_to_string(unpacked.join("flows/Default Start Flow/Default Start Flow.json"))
+            .unwrap();
+    assert!(flow.contains("Hi! Welcome to the coffee shop. [de]"));
+    assert!(flow.contains(r#""languageCode": "de""#));
+
+    let phrases =
+        fs::read_to_string(unpacked.join("intents/order.coffee/trainingPhrases/de.json")).unwrap();
+    assert!(phrases.contains("large [de]"));
+
+    let entities = fs::read_to_string(unpacked.join("entityTypes/size/entities/de.json")).unwrap();
+    assert!(entities.contains("big [de]"));
+
+    let agent = fs::read_to_string(unpacked.join("agent.json")).unwrap();
+    assert!(agent.contains(r#""de""#));
+    assert!(unpacked.join("webhooks").join("orders.json").exists());
+    let _ = fs::remove_dir_all(&folder);
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_translation_from_project
+#[test]
+fn test_offline_translation_from_project() {
+    let mock = MockGoogleApi::start();
+    let agent_zip = fs::read(SAMPLE_AGENT).unwrap();
+    mock.state
+        .lock()
+        .unwrap()
+        .agents
+        .insert("mock-project".to_owned(), agent_zip.clone());
+    let (http_client, api_config) = setup(&mock);
+    let folder = output_folder("from_project");
+    let exported_agent = folder.join("exported_agent.zip");
+
+    // agent returned directly in the response (agentContent)
+    task::block_on(api::export_agent_zip(
+        &http_client,
+        &api_config,
+        "mock-project",
+        None,
+        &exported_agent,
+    ))
+    .unwrap();
+    assert_eq!(fs::read(&exported_agent).unwrap(), agent_zip);
+
+    // agent exported to cloud storage (agentUri)
+    let exported_via_uri = task::block_on(api::export_agent(
+        &http_client,
+        &api_config,
+        "mock-project",
+        Some("gs://exports/agent.zip"),
+    ))
+    .unwrap();
+    assert_eq!(exported_via_uri, agent_zip);
+
+    // unknown project
+    assert!(task::block_on(api::export_agent(
+        &http_client,
+        &api_config,
+        "unknown-project",
+        None
+    ))
+    .is_err());
+
+    let (tx, _rx) = channel::<ProgressMessageType>();
+    GoogleTranslateV2::execute_translation(
+        &translation_job(
+            exported_agent.to_str().unwrap(),
+            &folder,
+            &AgentOptions::default(),
+        ),
+        &http_client,
+        &api_config,
+        &V2Options {
+            task_count: 2,
+            rate_limits: &RateLimits::new(None, None),
+        },
+        tx,
+    )
+    .unwrap();
+
+    assert_translated_agent(&folder);
+    let _ = fs::remove_dir_all(&folder);
+}
+
+// cargo test --test offline_translation -- --show-output test_offline_deploy
+#[test]
+fn test_offline_deploy() {
+    let mock = MockGoogleApi::start();
+    let original_zip = fs::read(SAMPLE_AGENT).unwrap();
+    mock.state
+        .lock()
+        .unwrap()
+        .agents
+        .insert("mock-project".to_owned(), original_zip.clone());
+    let (http_client, api_config) = setup(&mock);
+    let folder = output_folder("deploy");
+    let (tx, _rx) = channel::<ProgressMessageType>();
+
+    GoogleTranslateV2::execute_translation(
+        &translation_job(SAMPLE_AGENT, &folder, &AgentOptions::default()),
+        &http_client,
+        &api_config,
+        &V2Options {
+            task_count: 2,
+            rate_limits: &RateLimits::new(None, None),
+        },
+        tx,
+    )
+    .unwrap();
+    let translated_zip_path = folder.join("_packed").join("TranslatedAgent.zip");
+    let translated_zip = fs::read(&translated_zip_path).unwrap();
+    let backup_path = folder.join("backup_agent.zip");
+
+    // restore over non-empty agent requires confirmation
+    let result = task::block_on(api::deploy_agent(
+        &http_client,
+        &api_config,
+        &AgentDeployment {
+            project_id: "mock-project",
+            mode: DeployMode::Restore,
+            agent_zip_path: &translated_zip_path,
+            backup_zip_path: &backup_path,
+            train: true,
+        },
+        &|_| false,
+    ));
+    assert!(result.is_err());
+    assert!(!backup_path.exists());
+    assert!(mock.state.lock().unwrap().deployments.is_empty());
+
+    let backup = task::block_on(api::deploy_agent(
+        &http_client,
+        &api_config,
+        &AgentDeployment {
+            project_id: "mock-project",
+            mode: DeployMode::Restore,
+            agent_zip_path: &translated_zip_path,
+            backup_zip_path: &backup_path,
+            train: true,
+        },
+        &|_| true,
+    ))
+    .unwrap();
+    assert_eq!(backup, Some(backup_path.clone()));
+    assert_eq!(fs::read(&backup_path).unwrap(), original_zip);
+
+    // import into empty agent: no confirmation, no backup, no training
+    let backup = task::block_on(api::deploy_agent(
+        &http_client,
+        &api_config,
+        &AgentDeployment {
+            project_id: "empty-project",
+            mode: DeployMode::Import,
+            agent_zip_path: &translated_zip_path,
+            backup_zip_path: &folder.join("backup_empty_agent.zip"),
+            train: false,
+        },
+        &|_| panic!("confirmation not expected"),
+    ))
+    .unwrap();
+    assert_eq!(backup, None);
+
+    let state = mock.state.lock().unwrap();
+    assert_eq!(
+        state.deployments,
+        vec![
+            "restore mock-project",
+            "train mock-project",
+            "import empty-project"
+        ]
+    );
+    assert_eq!(state.agents["mock-project"], translated_zip);
+    assert_eq!(state.agents["empty-project"], translated_zip);
+    drop(state);
+
+    // CX agent can not be deployed via ES agent API, nothing is called
+    let cx_agent_zip = folder.join("cx_agent.zip");
+    zip_directory(SAMPLE_CX_AGENT_FOLDER, cx_agent_zip.to_str().unwrap()).unwrap();
+    let result = task::block_on(api::deploy_agent(
+        &http_client,
+        &api_config,
+        &AgentDeployment {
+            project_id: "mock-project",
+            mode: DeployMode::Restore,
+            agent_zip_path: &cx_agent_zip,
+            backup_zip_path: &folder.join("backup_cx_agent.zip"),
+            train: true,
+        },
+        &|_| panic!("confirmation not expected"),
+    ));
+    assert!(result.is_err());
+    assert!(!folder.join("backup_cx_agent.zip").exists());
+    assert_eq!(mock.state.lock().unwrap().deployments.len(), 3);
+    let _ = fs::remove_dir_all(&folder);
+}